@@ -1,27 +1,45 @@
+import datetime
+import hashlib
+import hmac
+import json
 import os
+import secrets
 import mysql.connector
 import toml
 import uuid
 
 
 class User:
-    def __init__(self, ssn, name):
+    def __init__(self, ssn, name, unit=None, rank=None, phone=None, email=None, active=True, end_of_service=None):
         self.ssn = ssn
         self.name = name
+        self.unit = unit
+        self.rank = rank
+        self.phone = phone
+        self.email = email
+        self.active = bool(active)
+        self.end_of_service = end_of_service
 
     def __repr__(self):
-        return f"SSN: {self.ssn}, Name: {self.name}"
+        return (f"SSN: {self.ssn}, Name: {self.name}, Unit: {self.unit}, Rank: {self.rank}, "
+                f"Active: {self.active}, End of Service: {self.end_of_service}")
+
+
+# The columns of the Users table in the order User takes them
+USER_COLUMNS = "SSN, Name, Unit, ServiceRank, Phone, Email, Active, EndOfService"
 
 
 class Item:
-    def __init__(self, item_id, product_id, size, quality):
+    def __init__(self, item_id, product_id, size, quality, serial_number=None, item_code=None):
         self.item_id = item_id
         self.product_id = product_id
         self.size = size
         self.quality = quality
+        self.serial_number = serial_number
+        self.item_code = item_code
 
     def __repr__(self):
-        return f"Item({self.item_id}, {self.product_id}, {self.size}, {self.quality})"
+        return f"Item({self.item_id}, {self.product_id}, {self.size}, {self.quality}, {self.serial_number})"
 
 
 def create_item_dict(item):
@@ -29,7 +47,9 @@ def create_item_dict(item):
         "item_id": item.item_id,
         "product_id": item.product_id,
         "size": item.size,
-        "quality": item.quality
+        "quality": item.quality,
+        "serial_number": item.serial_number,
+        "item_code": item.item_code
     }
 
 
@@ -69,7 +89,7 @@ class NumberBorrow:
 
 
 class AllBorrowed:
-    def __init__(self, lending_id, ssn, name, item_id, product_name, size, borrow_date, return_date):
+    def __init__(self, lending_id, ssn, name, item_id, product_name, size, borrow_date, return_date, item_code):
         if return_date is not None:
             self.return_date = return_date.__str__()
         else:
@@ -79,6 +99,7 @@ class AllBorrowed:
         self.ssn = ssn
         self.name = name
         self.item_id = uuid.UUID(bytes=item_id).__str__()
+        self.item_code = item_code
         self.product_name = product_name
         self.size = size
         self.borrow_date = borrow_date.__str__()
@@ -96,6 +117,267 @@ class AllBorrowed:
         )
 
 
+class ItemHistoryEvent:
+    def __init__(self, event_date, event, ssn, name, end_date, details, _sort_key=None):
+        self.event_date = event_date.__str__()
+        self.event = event
+        self.ssn = ssn
+        self.name = name
+        if end_date is not None:
+            self.end_date = end_date.__str__()
+        else:
+            self.end_date = None
+        self.details = details
+
+    def __repr__(self):
+        return (f"Date: {self.event_date}, Event: {self.event}, SSN: {self.ssn}, Name: {self.name}, "
+                f"Until: {self.end_date}, Details: {self.details}")
+
+
+class ProductRow:
+    def __init__(self, product_id, product_name, product_type):
+        self.product_id = product_id
+        self.product_name = product_name
+        self.product_type = product_type
+
+    def __repr__(self):
+        return f"Product ID: {self.product_id}, Product Name: {self.product_name}, Product Type: {self.product_type}"
+
+
+class ItemRecord:
+    def __init__(self, item_id, product_id, product_name, product_type, size, quality, available, serial_number,
+                 item_code):
+        self.item_id = uuid.UUID(bytes=item_id).__str__()
+        self.item_code = item_code
+        self.product_id = product_id
+        self.product_name = product_name
+        self.product_type = product_type
+        self.size = size
+        self.quality = quality
+        self.available = bool(available)
+        self.serial_number = serial_number
+
+    def __repr__(self):
+        return (f"Item ID: {self.item_id}, Item Code: {self.item_code}, Product ID: {self.product_id}, "
+                f"Product Name: {self.product_name}, Product Type: {self.product_type}, Size: {self.size}, "
+                f"Quality: {self.quality}, Available: {self.available}, Serial Number: {self.serial_number}")
+
+
+class InactiveHolding:
+    def __init__(self, ssn, name, unit, end_of_service, item_id, product_name, size, borrow_date):
+        self.ssn = ssn
+        self.name = name
+        self.unit = unit
+        self.end_of_service = end_of_service
+        self.item_id = uuid.UUID(bytes=item_id).__str__()
+        self.product_name = product_name
+        self.size = size
+        self.borrow_date = borrow_date
+
+    def __repr__(self):
+        return (f"SSN: {self.ssn}, Name: {self.name}, Unit: {self.unit}, Item ID: {self.item_id}, "
+                f"Product Name: {self.product_name}, Borrow Date: {self.borrow_date}")
+
+
+class OpenLoan:
+    def __init__(self, ssn, name, unit, item_id, product_name, product_type, size, borrow_date, item_code):
+        self.ssn = ssn
+        self.name = name
+        self.unit = unit
+        self.item_id = uuid.UUID(bytes=item_id).__str__()
+        self.item_code = item_code
+        self.product_name = product_name
+        self.product_type = product_type
+        self.size = size
+        self.borrow_date = borrow_date
+
+    def __repr__(self):
+        return (f"SSN: {self.ssn}, Name: {self.name}, Unit: {self.unit}, Item ID: {self.item_id}, "
+                f"Product Name: {self.product_name}, Borrow Date: {self.borrow_date}")
+
+
+class AccountabilityEntry:
+    def __init__(self, item_id, serial_number, product_name, product_type, ssn, name, unit, since, operator, witness):
+        self.item_id = uuid.UUID(bytes=item_id).__str__()
+        self.serial_number = serial_number
+        self.product_name = product_name
+        self.product_type = product_type
+        self.status = "On loan" if ssn is not None else "In store"
+        self.ssn = ssn
+        self.name = name
+        self.unit = unit
+        self.since = since
+        self.operator = operator
+        self.witness = witness
+
+    def __repr__(self):
+        return (f"Item ID: {self.item_id}, Serial Number: {self.serial_number}, Product Name: {self.product_name}, "
+                f"Status: {self.status}, SSN: {self.ssn}, Since: {self.since}")
+
+
+class ConsumableStock:
+    def __init__(self, product_id, product_name, product_type, quantity):
+        self.product_id = product_id
+        self.product_name = product_name
+        self.product_type = product_type
+        self.quantity = int(quantity)
+
+    def __repr__(self):
+        return f"Product ID: {self.product_id}, Product Name: {self.product_name}, Quantity: {self.quantity}"
+
+
+class Consumption:
+    def __init__(self, ssn, name, unit, product_id, product_name, quantity, issues):
+        self.ssn = ssn
+        self.name = name
+        self.unit = unit
+        self.product_id = product_id
+        self.product_name = product_name
+        self.quantity = int(quantity)
+        self.issues = int(issues)
+
+    def __repr__(self):
+        return (f"SSN: {self.ssn}, Name: {self.name}, Unit: {self.unit}, Product ID: {self.product_id}, "
+                f"Quantity: {self.quantity}, Issues: {self.issues}")
+
+
+class StockLevel:
+    def __init__(self, product_id, size, available, recent_demand):
+        self.product_id = product_id
+        self.size = size or None
+        self.available = int(available)
+        self.recent_demand = int(recent_demand)
+
+    def __repr__(self):
+        return (f"Product ID: {self.product_id}, Size: {self.size}, Available: {self.available}, "
+                f"Recent Demand: {self.recent_demand}")
+
+
+class ReportItem:
+    def __init__(self, item_id, product_id, product_name, size, quality):
+        self.item_id = uuid.UUID(bytes=item_id).__str__()
+        self.product_id = product_id
+        self.product_name = product_name
+        self.size = size or None
+        self.quality = quality
+
+    def __repr__(self):
+        return (f"Item ID: {self.item_id}, Product ID: {self.product_id}, Product Name: {self.product_name}, "
+                f"Size: {self.size}, Quality: {self.quality}")
+
+
+class ReportLoan:
+    def __init__(self, item_id, borrow_date, return_date):
+        self.item_id = uuid.UUID(bytes=item_id).__str__()
+        self.borrow_date = borrow_date
+        self.return_date = return_date
+
+    def __repr__(self):
+        return f"Item ID: {self.item_id}, Borrow Date: {self.borrow_date}, Return Date: {self.return_date}"
+
+
+class CheckoutFacts:
+    def __init__(self, user, product_type, held):
+        self.user = user
+        self.product_type = product_type
+        self.held = held
+
+    def __repr__(self):
+        return f"User: {self.user}, Product Type: {self.product_type}, Held: {self.held}"
+
+
+class Operator:
+    def __init__(self, username, role):
+        self.username = username
+        self.role = role
+
+    def __repr__(self):
+        return f"Username: {self.username}, Role: {self.role}"
+
+
+# The fields each search scope supports, mapped to the SQL expression and how the value is compared.
+# "text" matches anywhere in the value, "exact" must match the whole value, "number" and "date" also
+# support comparisons and "bool" takes yes/no.
+PRODUCT_SEARCH_FIELDS = {
+    "id": ("p.ProductID", "exact"),
+    "name": ("p.NameOfProduct", "text"),
+    "type": ("p.Type", "exact"),
+}
+
+ITEM_SEARCH_FIELDS = {
+    "id": ("BIN_TO_UUID(i.ItemID)", "exact"),
+    "product": ("i.ProductID", "exact"),
+    "name": ("p.NameOfProduct", "text"),
+    "type": ("p.Type", "exact"),
+    "size": ("i.Size", "exact"),
+    "quality": ("i.Quality", "number"),
+    "available": ("l.ItemID IS NULL AND NOT i.Retired", "bool"),
+    "retired": ("i.Retired", "bool"),
+    "serial": ("i.SerialNumber", "exact"),
+    "code": ("i.ItemCode", "exact"),
+}
+
+USER_SEARCH_FIELDS = {
+    "ssn": ("u.SSN", "exact"),
+    "name": ("u.Name", "text"),
+    "unit": ("u.Unit", "text"),
+    "rank": ("u.ServiceRank", "exact"),
+    "active": ("u.Active", "bool"),
+    "ends": ("u.EndOfService", "date"),
+}
+
+LOAN_SEARCH_FIELDS = {
+    "id": ("BIN_TO_UUID(l.LendingID)", "exact"),
+    "ssn": ("l.SSN", "exact"),
+    "user": ("u.Name", "text"),
+    "item": ("BIN_TO_UUID(l.ItemID)", "exact"),
+    "code": ("i.ItemCode", "exact"),
+    "name": ("p.NameOfProduct", "text"),
+    "type": ("p.Type", "exact"),
+    "size": ("i.Size", "exact"),
+    "borrowed": ("l.BorrowingDate", "date"),
+    "returned": ("l.ReturnDate IS NOT NULL", "bool"),
+}
+
+SEARCH_COMPARISONS = {">": ">", ">=": ">=", "<": "<", "<=": "<="}
+
+
+def item_code_prefix(product_name: str) -> str:
+    """
+    The prefix of the item codes of a product, the initials of the first three words of its name.
+    A name of a single word gives its first two letters, e.g. Field Cap gives FC and Helmet HE.
+    """
+    words = ["".join(c for c in word if c.isascii() and c.isalpha()) for word in product_name.split()]
+    words = [word.upper() for word in words if word]
+    if not words:
+        return "X"
+    if len(words) == 1:
+        return words[0][:2]
+    return "".join(word[0] for word in words[:3])
+
+
+def item_code_check_digit(prefix: str, number: int) -> int:
+    """
+    The check digit of an item code, computed like the check digit of an ISIN. Every letter is replaced by
+    its value as a base 36 digit, A is 10 and Z is 35, and the Luhn algorithm is run over the resulting digits.
+    Must match ItemCode::check_digit on the Rust side.
+    """
+    digits = "".join(str(int(c, 36)) for c in f"{prefix}{number:04d}")
+    total = 0
+    for position, digit in enumerate(reversed(digits)):
+        value = int(digit)
+        if position % 2 == 0:
+            value *= 2
+            if value > 9:
+                value -= 9
+        total += value
+    return (10 - total % 10) % 10
+
+
+def format_item_code(prefix: str, number: int) -> str:
+    return f"{prefix}-{number:04d}-{item_code_check_digit(prefix, number)}"
+
+
 class DBHandler:
     """
     A class for handling database operations on the Armory Atlas system.
@@ -112,21 +394,65 @@ class DBHandler:
         __init__(self): Initializes a new DBHandler instance, establishes database connection using configuration settings.
         get_rand_user(self) -> User: Retrieves a random user from the Users table.
         get_rand_item(self) -> Item: Fetches a random item that is not currently lent out.
-        get_users(self) -> list[User]: Gets a list of all users from the Users table.
-        get_loans(self) -> list[AllBorrowed]: Retrieves a detailed list of all loans, including user and item information.
-        get_items(self) -> list[ItemProduct]: Gets a list of items, along with product details and available quantity.
+        get_users(self, options) -> list[User]: Gets a list of users from the Users table.
+        get_loans(self, options) -> list[AllBorrowed]: Retrieves a detailed list of loans, including user and item information.
+        get_items(self, options) -> list[ItemProduct]: Gets a list of items, along with product details and available quantity.
         get_in_stock_size(self, product_id: str, size: str) -> list[InStockSize]: Gets the stock count for a specific product ID and size.
-        return_item(self, item_id: str) -> None: Executes a stored procedure to return an item and update the Lendings table.
-        user_all_borrowed(self, ssn: str) -> list[AllBorrowed]: Retrieves all borrowed items for a specific user.
-        number_of_borrows(self) -> int: Retrieves the number of borrows for each user, both current and total.
+        return_item(self, item_id: str, sign_off) -> None: Executes a stored procedure to return an item and update the Lendings table.
+        user_all_borrowed(self, ssn: str, options) -> list[AllBorrowed]: Retrieves all borrowed items for a specific user.
+        get_item_history(self, item_id: str) -> list[ItemHistoryEvent]: Retrieves the lifecycle of an item in chronological order.
+        get_inactive_holdings(self) -> list[InactiveHolding]: Retrieves the items held by inactive users or users whose service has ended.
+        get_open_loans(self) -> list[OpenLoan]: Retrieves every item that is lent out and not yet returned.
+        get_checkout_facts(self, ssn, item_id) -> CheckoutFacts: Retrieves what the checkout rules are checked against.
+        get_accountability(self, regulated_types, day) -> list[AccountabilityEntry]: Retrieves where every regulated item was at the end of a day.
+        get_consumable_stock(self) -> list[ConsumableStock]: Retrieves the quantity on hand of every consumable.
+        get_consumption(self, group, since, until) -> list[Consumption]: Retrieves how much of each consumable was issued per user or unit.
+        receive_stock(self, product_id, quantity, day, operator, note) -> None: Adds received units of a consumable to the stock.
+        issue_stock(self, product_id, quantity, ssn, day, operator, note) -> None: Issues units of a consumable to a user.
+        get_stock_levels(self, since) -> list[StockLevel]: Retrieves the available count and recent demand of every product and size.
+        get_report_items(self) -> list[ReportItem]: Retrieves every item with its product for the inventory reports.
+        get_report_loans(self) -> list[ReportLoan]: Retrieves the dates of every loan for the inventory reports.
+        number_of_borrows(self, options) -> int: Retrieves the number of borrows for each user, both current and total.
         get_config() -> dict: Retrieves the configuration settings for the database connection.
         insert_user(self, user) -> None: Inserts a new user into the Users table.
         insert_item(self, item) -> None: Inserts a new item into the Items table.
-        insert_loan(self, loan) -> None: Inserts a new lending into the Lendings table.
+        insert_loan(self, loan, sign_off) -> None: Inserts a new lending into the Lendings table, recording the sign-off of regulated items.
+        insert_checkout(self, checkout) -> None: Inserts the loans of a checkout with their sign-offs in a single transaction.
         insert_product(self, product) -> None: Inserts a new product into the Products table.
+        import_products(self, products) -> None: Inserts products in a single transaction.
+        import_items(self, items) -> None: Inserts items in a single transaction, keeping their IDs if they have one.
+        import_users(self, users) -> None: Inserts users in a single transaction.
+        import_loans(self, loans) -> None: Inserts loans in a single transaction.
+        update_item(self, item_id, product_id, size, quality, serial_number) -> None: Updates the given fields of an item.
+        update_user(self, ssn, name, unit, rank, phone, email, active, end_of_service) -> None: Updates the given fields of a user.
+        update_product(self, product_id, product_name, product_type) -> None: Updates the given fields of a product.
+        update_loan(self, lending_id, borrowing_date, return_date, sign_off) -> None: Updates the dates of a lending, recording the sign-off of a regulated return.
+        record_item_event(self, item_id, event_type, details, day) -> None: Records maintenance or a transfer of an item.
+        retire_item(self, item_id, reason, day) -> None: Retires an item, keeping it with its history.
+        delete_user(self, ssn, force) -> None: Deletes a user, refusing if the user has open loans unless forced.
+        erase_user(self, ssn) -> str: Replaces the personal data of a user with a pseudonym, keeping the user's loans.
+        delete_product(self, product_id, force) -> None: Deletes a product, refusing if it has items unless forced.
+        delete_loan(self, lending_id) -> None: Deletes a lending.
+        export_all(self) -> str: Reads every record in the database except the operators as JSON, used for archives.
+        restore_all(self, data: str) -> None: Restores the records read by export_all into an empty database.
         search_items(self, product_id: str, size: str) -> list[Item]: Searches for items in the Items table based on product ID and size.
-        drop_all(self) -> None: Drops all tables, triggers, functions, procedures, and views in the database.
-        create_all(self) -> None: Creates all tables, triggers, functions, procedures, and views in the database.
+        find_products(self, query) -> list[ProductRow]: Searches products with a parsed search query.
+        find_items(self, query) -> list[ItemRecord]: Searches single items with a parsed search query.
+        get_item_id_by_code(self, item_code) -> str | None: Looks up the ID of the item with a short item code.
+        find_users(self, query) -> list[User]: Searches users with a parsed search query.
+        find_loans(self, query) -> list[AllBorrowed]: Searches loans with a parsed search query.
+        count_operators(self) -> int: Counts the operator accounts, 0 if there is no Operators table yet.
+        get_operator_role(self, username) -> str: Gets the role of an operator, None if there is no such operator.
+        get_operators(self) -> list[Operator]: Gets every operator account.
+        add_operator(self, username, role, password) -> None: Adds an operator account.
+        remove_operator(self, username) -> None: Removes an operator account, refusing to remove the last admin.
+        verify_operator(self, username, password) -> bool: Checks the password of an operator.
+        login_operator(self, username, password) -> str: Logs an operator in and returns a login token, None if the password is wrong.
+        logout_operator(self, username) -> None: Makes the login token of an operator stop working.
+        get_logged_in_role(self, username, token) -> str: Gets the role of an operator by its login token, None if the token is not valid.
+        drop_all(self) -> None: Drops all tables except Operators, triggers, functions, procedures, and views in the database.
+        create_all(self) -> None: Creates all tables, triggers, functions, procedures, and views in the database, migrating tables made by an earlier version.
+        canonical_ssn(ssn, today) -> str: Brings an SSN stored as it was typed into the canonical form YYYYMMDD-XXXX.
     """
 
     def __init__(self):
@@ -153,8 +479,8 @@ class DBHandler:
         :return:
             A User object representing the random user fetched from the database.
         """
-        query = """
-            SELECT * FROM Users ORDER BY RAND() LIMIT 1;
+        query = f"""
+            SELECT {USER_COLUMNS} FROM Users ORDER BY RAND() LIMIT 1;
                     """
 
         self.cursor.execute(query)
@@ -177,9 +503,10 @@ class DBHandler:
             SELECT BIN_TO_UUID(i.ItemID) as ItemID, 
                 i.ProductID, 
                 i.Size, 
-                i.Quality
+                i.Quality,
+                i.SerialNumber
             FROM Items i
-            WHERE i.ItemID NOT IN (
+            WHERE NOT i.Retired AND i.ItemID NOT IN (
                 SELECT ItemID FROM Lendings WHERE ReturnDate IS NULL
             ) order by rand() limit 1;
         """
@@ -195,57 +522,198 @@ class DBHandler:
             raise Exception("No item available to borrow!")
         return item
 
-    def get_users(self) -> list[User]:
+    @staticmethod
+    def _check_filters(options, supported: set) -> None:
+        requested = {
+            "product_type": options.product_type,
+            "size": options.size,
+            "quality": options.min_quality if options.min_quality is not None else options.max_quality,
+            "date": options.from_date if options.from_date is not None else options.to_date,
+        }
+        for name, value in requested.items():
+            if value is not None and name not in supported:
+                raise Exception(f"Filtering on {name} is not supported for this query!")
+
+    @staticmethod
+    def _where(options, columns: dict) -> tuple[list[str], list]:
         """
-        Retrieves all users from the Users table and returns a list of User objects.
+        Builds the conditions for the filters of the query options that have a column in `columns`.
 
+        :param options: The QueryOptions to build the conditions from.
+        :param columns: Maps the filter names (product_type, size, quality, date) to SQL expressions.
         :return:
-            A list of User objects representing all users in the database.
+            The conditions and the parameters to pass along with them.
         """
-        query = """
-            SELECT * FROM Users;
-                    """
-
-        self.cursor.execute(query)
-        users = self.cursor.fetchall()
-        users_list = [User(*users) for users in users]
-        return users_list
+        conditions = []
+        params = []
+        if "product_type" in columns and options.product_type is not None:
+            conditions.append(f"{columns['product_type']} = %s")
+            params.append(options.product_type)
+        if "size" in columns and options.size is not None:
+            conditions.append(f"{columns['size']} = %s")
+            params.append(options.size)
+        if "quality" in columns and options.min_quality is not None:
+            conditions.append(f"{columns['quality']} >= %s")
+            params.append(options.min_quality)
+        if "quality" in columns and options.max_quality is not None:
+            conditions.append(f"{columns['quality']} <= %s")
+            params.append(options.max_quality)
+        if "date" in columns and options.from_date is not None:
+            conditions.append(f"{columns['date']} >= %s")
+            params.append(options.from_date)
+        if "date" in columns and options.to_date is not None:
+            conditions.append(f"{columns['date']} <= %s")
+            params.append(options.to_date)
+        return conditions, params
 
-    def get_loans(self) -> list[AllBorrowed]:
+    @staticmethod
+    def _order_and_page(options, columns: dict, default_order: str) -> tuple[str, list]:
         """
-        Retrieves all loans from the Lendings table and returns a list of AllBorrowed objects.
+        Builds the ORDER BY, LIMIT and OFFSET clauses for the query options.
 
+        :param options: The QueryOptions to build the clauses from.
+        :param columns: Maps the sortable field names to SQL expressions.
+        :param default_order: The ORDER BY expression to use when no sort field is given.
         :return:
-            A list of AllBorrowed objects representing all loans in the database.
+            The clauses and the parameters to pass along with them.
 
+        :raise Exception: If the sort field is not one of `columns`.
         """
-        query = """
-            SELECT l.LendingID, l.SSN, u.Name, l.ItemID, p.NameOfProduct, i.Size, l.BorrowingDate, l.ReturnDate
+        if options is None or options.sort_by is None:
+            order = default_order
+        elif options.sort_by in columns:
+            direction = "DESC" if options.descending else "ASC"
+            order = f"{columns[options.sort_by]} {direction}"
+        else:
+            raise Exception(
+                f"Cannot sort by {options.sort_by}, valid fields are: {', '.join(columns)}"
+            )
+
+        clause = f"ORDER BY {order}"
+        params = []
+        if options is not None and (options.limit is not None or options.offset is not None):
+            # MySQL only accepts an OFFSET together with a LIMIT
+            clause += " LIMIT %s OFFSET %s"
+            params.extend([
+                options.limit if options.limit is not None else 18446744073709551615,
+                options.offset if options.offset is not None else 0,
+            ])
+        return clause, params
+
+    def get_users(self, options=None) -> list[User]:
+        """
+        Retrieves the users from the Users table and returns a list of User objects.
+
+        :param options: Optional QueryOptions for sorting and pagination.
+        :return:
+            A list of User objects representing the users in the database.
+        """
+        if options is not None:
+            self._check_filters(options, set())
+        order, params = self._order_and_page(
+            options, {"ssn": "SSN", "name": "Name", "unit": "Unit", "rank": "ServiceRank"}, "SSN"
+        )
+        query = f"""
+            SELECT {USER_COLUMNS} FROM Users {order};
+                    """
+
+        self.cursor.execute(query, params)
+        users = self.cursor.fetchall()
+        users_list = [User(*users) for users in users]
+        return users_list
+
+    def _query_loans(self, options, ssn: str = None) -> list[AllBorrowed]:
+        conditions, params = [], []
+        if ssn is not None:
+            conditions.append("l.SSN = %s")
+            params.append(ssn)
+        if options is not None:
+            self._check_filters(options, {"product_type", "size", "quality", "date"})
+            filters, filter_params = self._where(options, {
+                "product_type": "p.Type",
+                "size": "i.Size",
+                "quality": "i.Quality",
+                "date": "l.BorrowingDate",
+            })
+            conditions.extend(filters)
+            params.extend(filter_params)
+
+        where = f"WHERE {' AND '.join(conditions)}" if conditions else ""
+        order, page_params = self._order_and_page(
+            options,
+            {
+                "lending_id": "l.LendingID",
+                "ssn": "l.SSN",
+                "name": "u.Name",
+                "item_id": "l.ItemID",
+                "product_name": "p.NameOfProduct",
+                "size": "i.Size",
+                "borrow_date": "l.BorrowingDate",
+                "return_date": "l.ReturnDate",
+            },
+            """u.SSN,
+                CASE WHEN l.ReturnDate IS NULL THEN 0 ELSE 1 END,
+                l.BorrowingDate DESC,
+                l.ReturnDate DESC""",
+        )
+        query = f"""
+            SELECT l.LendingID, l.SSN, u.Name, l.ItemID, p.NameOfProduct, i.Size, l.BorrowingDate, l.ReturnDate,
+                i.ItemCode
             FROM Lendings l
             JOIN Users u ON l.SSN = u.SSN
             JOIN Items i ON l.ItemID = i.ItemID
             JOIN Products p ON i.ProductID = p.ProductID
-
-            ORDER BY 
-                u.SSN,
-                CASE WHEN l.ReturnDate IS NULL THEN 0 ELSE 1 END,
-                l.BorrowingDate DESC,
-                l.ReturnDate DESC;
+            {where}
+            {order};
                     """
 
-        self.cursor.execute(query)
+        self.cursor.execute(query, params + page_params)
         loans = self.cursor.fetchall()
         loans_list = [AllBorrowed(*loan) for loan in loans]
         return loans_list
 
-    def get_items(self) -> list[ItemProduct]:
+    def get_loans(self, options=None) -> list[AllBorrowed]:
+        """
+        Retrieves the loans from the Lendings table and returns a list of AllBorrowed objects.
+
+        :param options: Optional QueryOptions for filtering, sorting and pagination.
+        :return:
+            A list of AllBorrowed objects representing the loans in the database.
+
+        """
+        return self._query_loans(options)
+
+    def get_items(self, options=None) -> list[ItemProduct]:
         """
         Retrieves a list of items along with their product details and quantity.
 
+        :param options: Optional QueryOptions for filtering, sorting and pagination.
         :return:
             A list of ItemProduct objects representing the items fetched from the database.
         """
-        query = """
+        item_conditions, product_conditions, params = [], [], []
+        if options is not None:
+            self._check_filters(options, {"product_type", "size", "quality"})
+            # size and quality filter the items that are counted, the type filters the products
+            item_conditions, item_params = self._where(options, {"size": "Size", "quality": "Quality"})
+            product_conditions, product_params = self._where(options, {"product_type": "p.Type"})
+            params = item_params + product_params
+
+        # Retired items are kept for their history but are no longer part of the inventory
+        item_where = f"WHERE {' AND '.join(['NOT Retired'] + item_conditions)}"
+        product_where = f"WHERE {' AND '.join(product_conditions)}" if product_conditions else ""
+        order, page_params = self._order_and_page(
+            options,
+            {
+                "product_id": "i.ProductID",
+                "product_name": "p.NameOfProduct",
+                "product_type": "p.Type",
+                "quantity": "i.Quantity",
+                "size": "i.Size",
+            },
+            "i.ProductID, i.Size",
+        )
+        query = f"""
             SELECT
                     i.ProductID as product_id,
                     p.NameOfProduct AS product_name,
@@ -255,12 +723,14 @@ class DBHandler:
                 FROM
                     Products p
                         JOIN
-                    (SELECT ProductID, Size, count(*) as Quantity from Items group by ProductID, Size)
+                    (SELECT ProductID, Size, count(*) as Quantity from Items {item_where} group by ProductID, Size)
                         AS
-                        i ON p.ProductID = i.ProductID;
+                        i ON p.ProductID = i.ProductID
+                {product_where}
+                {order};
                     """
 
-        self.cursor.execute(query)
+        self.cursor.execute(query, params + page_params)
         items = self.cursor.fetchall()
         item_list = [ItemProduct(*item) for item in items]
         return item_list
@@ -307,20 +777,36 @@ class DBHandler:
         except mysql.connector.Error as err:
             raise err
 
-    def return_item(self, item_id: str):
+    def return_item(self, item_id: str, sign_off=None):
         """
         Returns an item to the inventory.
 
         :param item_id: The ID of the item to be returned as a string.
+        :param sign_off: The operator and witness signing off the return as a tuple, for regulated items.
         :return:
             None
 
+        :raise Exception: If the return is signed off but the item is not lent out.
         :raise mysql.connector.Error: If there is an error while executing the database query.
         """
         query = f"""
             CALL return_item(UUID_TO_BIN('{item_id}'));
         """
 
+        lending_id = None
+        if sign_off is not None:
+            self.cursor.execute(
+                """
+                SELECT BIN_TO_UUID(LendingID) FROM Lendings
+                WHERE ItemID = UUID_TO_BIN(%s) AND ReturnDate IS NULL;
+                """,
+                (item_id,),
+            )
+            row = self.cursor.fetchone()
+            if row is None:
+                raise Exception(f"Item {item_id} is not lent out!")
+            lending_id = row[0]
+
         try:
             # Execute the stored procedure
             results = self.cursor.execute(query, multi=True)
@@ -332,187 +818,1555 @@ class DBHandler:
                 else:
                     print("Procedure affected rows:", result.rowcount)
 
-            # Commit the transaction
-            self.db.commit()
-        except Exception as e:
-            raise e
+            if lending_id is not None:
+                self._record_sign_off(lending_id, "Return", *sign_off)
+
+            # Commit the transaction
+            self.db.commit()
+        except Exception as e:
+            self.db.rollback()
+            raise e
+
+    def user_all_borrowed(self, ssn: str, options=None) -> list[AllBorrowed]:
+        """
+        Retrieves all borrowed items for a specific user.
+
+        :param ssn: The SSN of the user as a string.
+        :param options: Optional QueryOptions for filtering, sorting and pagination.
+        :return:
+            A list of AllBorrowed objects containing information about the user's borrowed items.
+        """
+        return self._query_loans(options, ssn)
+
+    def get_item_history(self, item_id: str) -> list[ItemHistoryEvent]:
+        """
+        Retrieves the whole lifecycle of an item in chronological order.
+        This combines the recorded item events (creation, condition changes, maintenance, transfers and retirement)
+        with every lending of the item.
+
+        :param item_id: The ID of the item as a string.
+        :return:
+            A list of ItemHistoryEvent objects ordered by date.
+
+        :raise Exception: If no item with the given ID exists.
+        """
+        if self._count("SELECT COUNT(*) FROM Items WHERE ItemID = UUID_TO_BIN(%s);", (item_id,)) == 0:
+            raise Exception(f"No item with ID {item_id} exists!")
+
+        query = """
+            SELECT e.EventDate, e.EventType, NULL, NULL, NULL, e.Details,
+                CASE e.EventType WHEN 'Created' THEN 0 WHEN 'Retired' THEN 3 ELSE 2 END AS SortKey
+            FROM ItemEvents e
+            WHERE e.ItemID = UUID_TO_BIN(%s)
+            UNION ALL
+            SELECT l.BorrowingDate, 'Lent', l.SSN, u.Name, l.ReturnDate,
+                CONCAT('Lending ', BIN_TO_UUID(l.LendingID)), 1 AS SortKey
+            FROM Lendings l
+            JOIN Users u ON l.SSN = u.SSN
+            WHERE l.ItemID = UUID_TO_BIN(%s)
+            UNION ALL
+            SELECT l.ReturnDate, 'Returned', l.SSN, u.Name, NULL,
+                CONCAT('Lending ', BIN_TO_UUID(l.LendingID)), 2 AS SortKey
+            FROM Lendings l
+            JOIN Users u ON l.SSN = u.SSN
+            WHERE l.ItemID = UUID_TO_BIN(%s)
+            AND l.ReturnDate IS NOT NULL
+            ORDER BY 1, 7;
+        """
+
+        self.cursor.execute(query, (item_id, item_id, item_id))
+        events = self.cursor.fetchall()
+        return [ItemHistoryEvent(*event) for event in events]
+
+    def get_inactive_holdings(self) -> list[InactiveHolding]:
+        """
+        Retrieves the items still held by users that are inactive or whose service has ended.
+
+        :return:
+            A list of InactiveHolding objects, one per open loan, sorted by unit and name.
+        """
+        self.cursor.execute(
+            """
+            SELECT u.SSN, u.Name, u.Unit, u.EndOfService, l.ItemID, p.NameOfProduct, i.Size, l.BorrowingDate
+            FROM Lendings l
+            JOIN Users u ON l.SSN = u.SSN
+            JOIN Items i ON l.ItemID = i.ItemID
+            JOIN Products p ON i.ProductID = p.ProductID
+            WHERE l.ReturnDate IS NULL AND (NOT u.Active OR u.EndOfService < CURDATE())
+            ORDER BY u.Unit, u.Name, l.BorrowingDate;
+            """
+        )
+        return [InactiveHolding(*row) for row in self.cursor.fetchall()]
+
+    def get_open_loans(self) -> list[OpenLoan]:
+        """
+        Retrieves every item that is lent out and not yet returned.
+
+        :return:
+            A list of OpenLoan objects sorted by unit, name and borrow date.
+        """
+        self.cursor.execute(
+            """
+            SELECT u.SSN, u.Name, u.Unit, l.ItemID, p.NameOfProduct, p.Type, i.Size, l.BorrowingDate, i.ItemCode
+            FROM Lendings l
+            JOIN Users u ON l.SSN = u.SSN
+            JOIN Items i ON l.ItemID = i.ItemID
+            JOIN Products p ON i.ProductID = p.ProductID
+            WHERE l.ReturnDate IS NULL
+            ORDER BY u.Unit, u.Name, l.BorrowingDate;
+            """
+        )
+        return [OpenLoan(*row) for row in self.cursor.fetchall()]
+
+    def get_accountability(self, regulated_types: list[str], day) -> list[AccountabilityEntry]:
+        """
+        Retrieves where every item of the regulated product types was at the end of a day.
+
+        :param regulated_types: The product types that are regulated.
+        :param day: The day to report on as a date.
+        :return:
+            A list of AccountabilityEntry objects, one per item, with the holder and the sign-off of the
+            checkout for the items that were lent out. Sorted by product type, product name and serial number.
+        """
+        if not regulated_types:
+            return []
+
+        types = ", ".join(["%s"] * len(regulated_types))
+        self.cursor.execute(
+            f"""
+            SELECT i.ItemID, i.SerialNumber, p.NameOfProduct, p.Type, l.SSN, u.Name, u.Unit, l.BorrowingDate,
+                s.Operator, s.Witness
+            FROM Items i
+            JOIN Products p ON i.ProductID = p.ProductID
+            LEFT JOIN Lendings l ON l.ItemID = i.ItemID AND l.BorrowingDate <= %s
+                AND (l.ReturnDate IS NULL OR l.ReturnDate > %s)
+            LEFT JOIN Users u ON l.SSN = u.SSN
+            LEFT JOIN SignOffs s ON s.LendingID = l.LendingID AND s.Action = 'Checkout'
+            WHERE p.Type IN ({types})
+            ORDER BY p.Type, p.NameOfProduct, i.SerialNumber;
+            """,
+            (day, day, *regulated_types),
+        )
+        return [AccountabilityEntry(*row) for row in self.cursor.fetchall()]
+
+    def get_consumable_stock(self) -> list[ConsumableStock]:
+        """
+        Retrieves the quantity on hand of every consumable that has been received at least once.
+
+        :return:
+            A list of ConsumableStock objects sorted by product name.
+        """
+        self.cursor.execute(
+            """
+            SELECT c.ProductID, p.NameOfProduct, p.Type, c.Quantity
+            FROM ConsumableStock c
+            JOIN Products p ON c.ProductID = p.ProductID
+            ORDER BY p.NameOfProduct;
+            """
+        )
+        return [ConsumableStock(*row) for row in self.cursor.fetchall()]
+
+    def get_consumption(self, group: str, since=None, until=None) -> list[Consumption]:
+        """
+        Retrieves how much of each consumable was issued, per user or per unit.
+
+        :param group: "user" to sum the issues per user, "unit" to sum them per unit.
+        :param since: Only count issues on or after this date, if given.
+        :param until: Only count issues on or before this date, if given.
+        :return:
+            A list of Consumption objects sorted by unit, name and product name. The SSN and name are None when
+            grouping per unit.
+        :raise Exception: If the group is not "user" or "unit".
+        """
+        if group == "user":
+            keys = "t.SSN, u.Name, u.Unit"
+        elif group == "unit":
+            keys = "NULL, NULL, u.Unit"
+        else:
+            raise Exception(f"Cannot group consumption by {group}, expected user or unit!")
+
+        group_by = ", ".join(key for key in keys.split(", ") if key != "NULL")
+        self.cursor.execute(
+            f"""
+            SELECT {keys}, t.ProductID, p.NameOfProduct, SUM(t.Quantity), COUNT(*)
+            FROM StockTransactions t
+            JOIN Users u ON t.SSN = u.SSN
+            JOIN Products p ON t.ProductID = p.ProductID
+            WHERE t.Kind = 'Issue'
+            AND (%s IS NULL OR t.TransactionDate >= %s)
+            AND (%s IS NULL OR t.TransactionDate <= %s)
+            GROUP BY {group_by}, t.ProductID, p.NameOfProduct
+            ORDER BY u.Unit, {"u.Name, " if group == "user" else ""}p.NameOfProduct;
+            """,
+            (since, since, until, until),
+        )
+        return [Consumption(*row) for row in self.cursor.fetchall()]
+
+    def receive_stock(self, product_id: str, quantity: int, day, operator: str = None, note: str = None) -> None:
+        """
+        Adds received units of a consumable to the stock and records the receipt.
+
+        :param product_id: The ID of the consumable product.
+        :param quantity: The number of units received, must be positive.
+        :param day: The date the units were received.
+        :param operator: The operator receiving the units, if one is logged in.
+        :param note: A free text note, e.g. the delivery note number.
+        :return:
+            None
+        :raise Exception: If the product does not exist or the quantity is not positive.
+        """
+        if quantity <= 0:
+            raise Exception("The quantity received must be positive!")
+        if self._count("SELECT COUNT(*) FROM Products WHERE ProductID = %s;", (product_id,)) == 0:
+            raise Exception(f"No product with ID {product_id} exists!")
+
+        try:
+            self.cursor.execute(
+                """
+                INSERT INTO ConsumableStock (ProductID, Quantity) VALUES (%s, %s)
+                ON DUPLICATE KEY UPDATE Quantity = Quantity + VALUES(Quantity);
+                """,
+                (product_id, quantity),
+            )
+            self._record_stock_transaction(product_id, "Receive", quantity, None, day, operator, note)
+            self.db.commit()
+        except Exception as err:
+            self.db.rollback()
+            raise err
+
+    def issue_stock(self, product_id: str, quantity: int, ssn: str, day, operator: str = None,
+                    note: str = None) -> None:
+        """
+        Issues units of a consumable to a user. Issued units are used up and never returned.
+
+        :param product_id: The ID of the consumable product.
+        :param quantity: The number of units issued, must be positive.
+        :param ssn: The SSN of the user the units are issued to.
+        :param day: The date the units were issued.
+        :param operator: The operator issuing the units, if one is logged in.
+        :param note: A free text note.
+        :return:
+            None
+        :raise Exception: If the user does not exist or may not borrow, the quantity is not positive or there
+            are not enough units on hand.
+        """
+        if quantity <= 0:
+            raise Exception("The quantity issued must be positive!")
+        if self._count("SELECT COUNT(*) FROM Users WHERE SSN = %s;", (ssn,)) == 0:
+            raise Exception(f"No user with SSN {ssn} exists!")
+        self._check_may_borrow(ssn, day.strftime('%Y-%m-%d'))
+
+        try:
+            self.cursor.execute(
+                "UPDATE ConsumableStock SET Quantity = Quantity - %s WHERE ProductID = %s AND Quantity >= %s;",
+                (quantity, product_id, quantity),
+            )
+            if self.cursor.rowcount == 0:
+                on_hand = self._count(
+                    "SELECT COALESCE(SUM(Quantity), 0) FROM ConsumableStock WHERE ProductID = %s;", (product_id,)
+                )
+                raise Exception(f"Only {on_hand} of {product_id} on hand, cannot issue {quantity}!")
+            self._record_stock_transaction(product_id, "Issue", quantity, ssn, day, operator, note)
+            self.db.commit()
+        except Exception as err:
+            self.db.rollback()
+            raise err
+
+    def get_stock_levels(self, since) -> list[StockLevel]:
+        """
+        Retrieves how many items of every product and size are available, counted with in_stock_for_product,
+        and how many were lent out since a date. Consumables are included with their quantity on hand and the
+        number of units issued since the date.
+
+        :param since: The start of the period the recent demand is counted over.
+        :return:
+            A list of StockLevel objects, the size is None for products without sizes.
+        """
+        self.cursor.execute(
+            """
+            SELECT s.ProductID, s.Size, in_stock_for_product(s.ProductID, s.Size),
+                (SELECT COUNT(*) FROM Lendings l
+                    JOIN Items i ON l.ItemID = i.ItemID
+                    WHERE i.ProductID = s.ProductID AND i.Size <=> s.Size AND l.BorrowingDate >= %s)
+            FROM (SELECT DISTINCT ProductID, Size FROM Items) s
+            UNION ALL
+            SELECT c.ProductID, NULL, c.Quantity,
+                (SELECT COALESCE(SUM(t.Quantity), 0) FROM StockTransactions t
+                    WHERE t.ProductID = c.ProductID AND t.Kind = 'Issue' AND t.TransactionDate >= %s)
+            FROM ConsumableStock c;
+            """,
+            (since, since),
+        )
+        return [StockLevel(*row) for row in self.cursor.fetchall()]
+
+    def get_report_items(self) -> list[ReportItem]:
+        """
+        Retrieves every item with the product it belongs to, the inventory reports are computed from these.
+
+        :return:
+            A list of ReportItem objects.
+        """
+        self.cursor.execute(
+            """
+            SELECT i.ItemID, i.ProductID, p.NameOfProduct, i.Size, i.Quality
+            FROM Items i
+            JOIN Products p ON i.ProductID = p.ProductID;
+            """
+        )
+        return [ReportItem(*row) for row in self.cursor.fetchall()]
+
+    def get_report_loans(self) -> list[ReportLoan]:
+        """
+        Retrieves the item and dates of every loan, the inventory reports are computed from these.
+
+        :return:
+            A list of ReportLoan objects, the return date is None for loans that are still open.
+        """
+        self.cursor.execute("SELECT ItemID, BorrowingDate, ReturnDate FROM Lendings;")
+        return [ReportLoan(*row) for row in self.cursor.fetchall()]
+
+    def get_checkout_facts(self, ssn: str, item_id: str) -> CheckoutFacts:
+        """
+        Retrieves what the checkout rules are checked against when an item is lent to a user.
+
+        :param ssn: The SSN of the borrowing user as a string.
+        :param item_id: The ID of the item as a string.
+        :return:
+            A CheckoutFacts object with the user, the product type of the item and
+            the product type and borrowing date of every item the user holds.
+
+        :raise Exception: If the user or the item does not exist.
+        """
+        self.cursor.execute(f"SELECT {USER_COLUMNS} FROM Users WHERE SSN = %s;", (ssn,))
+        user = self.cursor.fetchone()
+        if user is None:
+            raise Exception(f"No user with SSN {ssn} exists!")
+
+        self.cursor.execute(
+            """
+            SELECT p.Type FROM Items i
+            JOIN Products p ON i.ProductID = p.ProductID
+            WHERE i.ItemID = UUID_TO_BIN(%s);
+            """,
+            (item_id,),
+        )
+        product_type = self.cursor.fetchone()
+        if product_type is None:
+            raise Exception(f"No item with ID {item_id} exists!")
+
+        self.cursor.execute(
+            """
+            SELECT p.Type, l.BorrowingDate FROM Lendings l
+            JOIN Items i ON l.ItemID = i.ItemID
+            JOIN Products p ON i.ProductID = p.ProductID
+            WHERE l.SSN = %s AND l.ReturnDate IS NULL
+            ORDER BY l.BorrowingDate;
+            """,
+            (ssn,),
+        )
+        held = [(held_type, borrowing_date) for held_type, borrowing_date in self.cursor.fetchall()]
+
+        return CheckoutFacts(User(*user), product_type[0], held)
+
+    def number_of_borrowes(self, options=None) -> list[NumberBorrow]:
+        """
+        Retrieves the total number of borrowes for each user.
+
+        :param options: Optional QueryOptions for sorting and pagination.
+        :return:
+            A list of TotBorrowes objects containing information about the total number of borrowes for each user.
+        """
+        if options is not None:
+            self._check_filters(options, set())
+        order, params = self._order_and_page(
+            options,
+            {
+                "ssn": "SSN",
+                "name": "Name",
+                "total_borrows": "TotalLendings",
+                "current_borrows": "currLendings",
+            },
+            "TotalLendings DESC",
+        )
+        query = f"""
+            SELECT * FROM number_of_borrowes {order};
+                    """
+
+        self.cursor.execute(query, params)
+        borrowes = self.cursor.fetchall()
+        borrowes_list = [NumberBorrow(*borrowes) for borrowes in borrowes]
+        return borrowes_list
+
+    @staticmethod
+    def get_config() -> dict:
+        """
+        Retrieves the Armory Atlas config file.
+
+        :return:
+            A dictionary containing the Armory Atlas config.
+        """
+        if os.name == 'nt':
+            home_dir = os.getenv('USERPROFILE')
+        else:
+            home_dir = os.getenv('HOME')
+
+        config_path = os.path.join(home_dir, '.config', 'armoryatlas', 'config.toml')
+        with open(config_path, "r") as f:
+            config = toml.load(f)
+        return config
+
+    def insert_loan(self, loan, sign_off=None) -> None:
+        """
+        Inserts a loan into the database.
+
+        :param loan: The loan object to be inserted. A new ID is generated unless the loan already has one.
+        :param sign_off: The operator and witness signing off the checkout as a tuple, for regulated items.
+        :return:
+            None
+        :raise mysql.connector.Error: If there is an error while executing the database query.
+        """
+        def insert(record):
+            lending_id = self._insert_loan(record)
+            if sign_off is not None:
+                self._record_sign_off(lending_id, "Checkout", *sign_off)
+
+        self._insert_all(insert, [loan])
+
+    def insert_checkout(self, checkout) -> None:
+        """
+        Inserts the loans of a checkout in a single transaction, none of them is inserted if one fails.
+
+        :param checkout: The loans as tuples of the loan and its sign-off, the operator and witness as a tuple for
+            regulated items and None for others.
+        :return:
+            None
+        :raise Exception: If one of the loans can't be made, e.g. because the item is already borrowed.
+        """
+        def insert(record):
+            loan, sign_off = record
+            lending_id = self._insert_loan(loan)
+            if sign_off is not None:
+                self._record_sign_off(lending_id, "Checkout", *sign_off)
+
+        self._insert_all(insert, checkout)
+
+    def insert_product(self, product) -> None:
+        """
+        Inserts a product into the database.
+
+        :param product: The product object to be inserted.
+        :return:
+            None
+        :raise mysql.connector.Error: If there is an error while executing the database query.
+        """
+        self._insert_all(self._insert_product, [product])
+
+    def insert_item(self, item) -> None:
+        """
+        Inserts an item into the database.
+
+        :param item: The item object to be inserted. A new ID is generated unless the item already has one.
+        :return:
+            None
+        :raise mysql.connector.Error: If there is an error while executing the database query.
+        """
+        self._insert_all(self._insert_item, [item])
+
+    def insert_user(self, user) -> None:
+        """
+        Inserts a user into the database.
+
+        :param user: The user object to be inserted.
+        :return:
+            None
+        :raise mysql.connector.Error: If there is an error while executing the database query.
+        """
+        self._insert_all(self._insert_user, [user])
+
+    def import_products(self, products) -> None:
+        """
+        Inserts all products in a single transaction, nothing is inserted if one of them fails.
+
+        :param products: The product objects to be inserted.
+        :return:
+            None
+        :raise mysql.connector.Error: If there is an error while executing the database query.
+        """
+        self._insert_all(self._insert_product, products)
+
+    def import_items(self, items) -> None:
+        """
+        Inserts all items in a single transaction, nothing is inserted if one of them fails.
+
+        :param items: The item objects to be inserted, items with an ID keep it.
+        :return:
+            None
+        :raise mysql.connector.Error: If there is an error while executing the database query.
+        """
+        self._insert_all(self._insert_item, items)
+
+    def import_users(self, users) -> None:
+        """
+        Inserts all users in a single transaction, nothing is inserted if one of them fails.
+
+        :param users: The user objects to be inserted.
+        :return:
+            None
+        :raise mysql.connector.Error: If there is an error while executing the database query.
+        """
+        self._insert_all(self._insert_user, users)
+
+    def import_loans(self, loans) -> None:
+        """
+        Inserts all loans in a single transaction, nothing is inserted if one of them fails.
+
+        :param loans: The loan objects to be inserted.
+        :return:
+            None
+        :raise mysql.connector.Error: If there is an error while executing the database query.
+        """
+        self._insert_all(self._insert_loan, loans)
+
+    def _insert_all(self, insert, records) -> None:
+        try:
+            for record in records:
+                insert(record)
+            self.db.commit()  # Commit the transaction
+        except Exception as err:
+            # Also the checks of the inserts, like an inactive borrower, undo the rows before them
+            self.db.rollback()  # Rollback the transaction in case of error
+            raise err
+
+    def _insert_loan(self, loan) -> str:
+        borrowing_date = loan.borrowing_date.strftime('%Y-%m-%d') if loan.borrowing_date else None
+        return_date = loan.return_date.strftime('%Y-%m-%d') if loan.return_date else None
+        if return_date is None:
+            self._check_may_borrow(loan.ssn, borrowing_date)
+            if self._count("SELECT COUNT(*) FROM Items WHERE ItemID = UUID_TO_BIN(%s) AND Retired;", (loan.item_id,)):
+                raise Exception(f"Item {loan.item_id} is retired and cannot be lent out!")
+        lending_id = loan.id or uuid.uuid4().__str__()
+        query = """
+            INSERT INTO Lendings (LendingID, SSN, ItemID, BorrowingDate, ReturnDate) 
+            VALUES (UUID_TO_BIN(%s), %s, UUID_TO_BIN(%s), %s, %s);
+        """
+
+        self.cursor.execute(query, (lending_id, loan.ssn, loan.item_id, borrowing_date, return_date))
+        return lending_id
+
+    def _insert_product(self, product) -> None:
+        query = """
+            INSERT INTO Products (ProductID, NameOfProduct, Type) VALUES (%s, %s, %s)
+        """
+
+        self.cursor.execute(query, (product.product_id, product.product_name, product.product_type))
+
+    def _insert_item(self, item) -> None:
+        item_id = item.item_id or uuid.uuid4().__str__()
+        item_code = getattr(item, "item_code", None) or self._next_item_code(item.product_id)
+        query = """
+            INSERT INTO Items (ItemID, ProductID, Size, Quality, SerialNumber, ItemCode)
+            VALUES (UUID_TO_BIN(%s), %s, %s, %s, %s, %s)
+        """
+
+        self.cursor.execute(
+            query, (item_id, item.product_id, item.size or None, item.quality, item.serial_number, item_code)
+        )
+        self._record_item_event(item_id, "Created", f"Quality {item.quality:.2f}")
+
+    def _next_item_code(self, product_id: str) -> str:
+        self.cursor.execute("SELECT NameOfProduct FROM Products WHERE ProductID = %s;", (product_id,))
+        row = self.cursor.fetchone()
+        # An unknown product is refused by the insert itself
+        prefix = item_code_prefix(row[0] if row is not None else "")
+
+        # The number is the part between the dashes, the prefix has no dashes of its own
+        self.cursor.execute(
+            """
+            SELECT MAX(CAST(SUBSTRING_INDEX(SUBSTRING_INDEX(ItemCode, '-', 2), '-', -1) AS UNSIGNED))
+            FROM Items WHERE ItemCode LIKE %s;
+            """,
+            (f"{prefix}-%",),
+        )
+        number = (self.cursor.fetchone()[0] or 0) + 1
+        return format_item_code(prefix, number)
+
+    def _assign_item_codes(self) -> None:
+        self.cursor.execute("SELECT BIN_TO_UUID(ItemID), ProductID FROM Items WHERE ItemCode IS NULL ORDER BY ItemID;")
+        for item_id, product_id in self.cursor.fetchall():
+            self.cursor.execute(
+                "UPDATE Items SET ItemCode = %s WHERE ItemID = UUID_TO_BIN(%s);",
+                (self._next_item_code(product_id), item_id),
+            )
+
+    def get_item_id_by_code(self, item_code: str) -> str | None:
+        """
+        Looks up the ID of the item with a short item code.
+
+        :param item_code: The item code, like FC-0042-5.
+        :return:
+            The item ID as a string, or None if no item has the code.
+        """
+        self.cursor.execute("SELECT BIN_TO_UUID(ItemID) FROM Items WHERE ItemCode = %s;", (item_code.upper(),))
+        row = self.cursor.fetchone()
+        return row[0] if row is not None else None
+
+    def _insert_user(self, user) -> None:
+        query = f"""
+            INSERT INTO Users ({USER_COLUMNS}) VALUES (%s, %s, %s, %s, %s, %s, %s, %s)
+        """
+
+        self.cursor.execute(query, (
+            user.ssn, user.name, user.unit, user.rank, user.phone, user.email, user.active, user.end_of_service
+        ))
+
+    def _check_may_borrow(self, ssn: str, borrowing_date: str) -> None:
+        self.cursor.execute("SELECT Active, EndOfService FROM Users WHERE SSN = %s;", (ssn,))
+        row = self.cursor.fetchone()
+        if row is None:
+            return
+        active, end_of_service = row
+        if not active:
+            raise Exception(f"User {ssn} is inactive and cannot borrow items!")
+        if end_of_service is not None and borrowing_date is not None and str(end_of_service) < borrowing_date:
+            raise Exception(f"The service of user {ssn} ended {end_of_service}, the user cannot borrow items!")
+
+    def _record_stock_transaction(self, product_id: str, kind: str, quantity: int, ssn, day, operator, note) -> None:
+        query = """
+            INSERT INTO StockTransactions (TransactionID, ProductID, Kind, Quantity, SSN, TransactionDate, Operator, Note)
+            VALUES (UUID_TO_BIN(UUID()), %s, %s, %s, %s, %s, %s, %s);
+        """
+
+        self.cursor.execute(query, (product_id, kind, quantity, ssn, day, operator, note))
+
+    def _record_sign_off(self, lending_id: str, action: str, operator: str, witness: str) -> None:
+        query = """
+            INSERT INTO SignOffs (SignOffID, LendingID, Action, Operator, Witness, SignedAt)
+            VALUES (UUID_TO_BIN(UUID()), UUID_TO_BIN(%s), %s, %s, %s, NOW());
+        """
+
+        self.cursor.execute(query, (lending_id, action, operator, witness))
+
+    def _record_item_event(self, item_id: str, event_type: str, details: str = None, day=None) -> None:
+        query = """
+            INSERT INTO ItemEvents (EventID, ItemID, EventDate, EventType, Details)
+            VALUES (UUID_TO_BIN(UUID()), UUID_TO_BIN(%s), COALESCE(%s, CURDATE()), %s, %s);
+        """
+
+        self.cursor.execute(query, (item_id, day, event_type, details))
+
+    def _count(self, query: str, params: tuple) -> int:
+        self.cursor.execute(query, params)
+        (count,) = self.cursor.fetchone()
+        return count
+
+    def _execute_update(self, table: str, key_clause: str, key: str, fields: dict) -> None:
+        fields = {column: value for column, value in fields.items() if value is not None}
+        if not fields:
+            raise Exception("Nothing to update, provide at least one field to change!")
+
+        assignments = ", ".join(f"{column} = %s" for column in fields)
+        query = f"UPDATE {table} SET {assignments} WHERE {key_clause};"
+        self.cursor.execute(query, (*fields.values(), key))
+
+    def update_item(self, item_id: str, product_id: str = None, size: str = None, quality: float = None,
+                    serial_number: str = None) -> None:
+        """
+        Updates the given fields of an item. Fields that are None are left unchanged.
+        A change of quality is recorded as a condition change in the item history.
+
+        :param item_id: The ID of the item to update.
+        :param product_id: The new product ID of the item.
+        :param size: The new size of the item.
+        :param quality: The new quality of the item.
+        :param serial_number: The new serial number of the item.
+        :return:
+            None
+        :raise Exception: If the item does not exist or no field is given.
+        """
+        if self._count("SELECT COUNT(*) FROM Items WHERE ItemID = UUID_TO_BIN(%s);", (item_id,)) == 0:
+            raise Exception(f"No item with ID {item_id} exists!")
+
+        try:
+            self._execute_update(
+                "Items",
+                "ItemID = UUID_TO_BIN(%s)",
+                item_id,
+                {"ProductID": product_id, "Size": size, "Quality": quality, "SerialNumber": serial_number},
+            )
+            if quality is not None:
+                self._record_item_event(item_id, "Condition", f"Quality set to {quality:.2f}")
+            self.db.commit()
+        except Exception as err:
+            self.db.rollback()
+            raise err
+
+    def update_user(self, ssn: str, name: str = None, unit: str = None, rank: str = None, phone: str = None,
+                    email: str = None, active: bool = None, end_of_service=None) -> None:
+        """
+        Updates the given fields of a user. Fields that are None are left unchanged.
+
+        :param ssn: The SSN of the user to update.
+        :param name: The new name of the user.
+        :param unit: The new unit or company of the user.
+        :param rank: The new rank of the user.
+        :param phone: The new phone number of the user.
+        :param email: The new email address of the user.
+        :param active: Whether the user is active, inactive users cannot borrow items.
+        :param end_of_service: The new last day of the user's service.
+        :return:
+            None
+        :raise Exception: If the user does not exist or no field is given.
+        """
+        if self._count("SELECT COUNT(*) FROM Users WHERE SSN = %s;", (ssn,)) == 0:
+            raise Exception(f"No user with SSN {ssn} exists!")
+
+        try:
+            self._execute_update("Users", "SSN = %s", ssn, {
+                "Name": name,
+                "Unit": unit,
+                "ServiceRank": rank,
+                "Phone": phone,
+                "Email": email,
+                "Active": active,
+                "EndOfService": end_of_service,
+            })
+            self.db.commit()
+        except Exception as err:
+            self.db.rollback()
+            raise err
+
+    def update_product(self, product_id: str, product_name: str = None, product_type: str = None) -> None:
+        """
+        Updates the given fields of a product. Fields that are None are left unchanged.
+
+        :param product_id: The ID of the product to update.
+        :param product_name: The new name of the product.
+        :param product_type: The new type of the product.
+        :return:
+            None
+        :raise Exception: If the product does not exist or no field is given.
+        """
+        if self._count("SELECT COUNT(*) FROM Products WHERE ProductID = %s;", (product_id,)) == 0:
+            raise Exception(f"No product with ID {product_id} exists!")
+
+        try:
+            self._execute_update(
+                "Products",
+                "ProductID = %s",
+                product_id,
+                {"NameOfProduct": product_name, "Type": product_type},
+            )
+            self.db.commit()
+        except Exception as err:
+            self.db.rollback()
+            raise err
+
+    def update_loan(self, lending_id: str, borrowing_date=None, return_date=None, sign_off=None) -> None:
+        """
+        Updates the dates of a lending. Fields that are None are left unchanged.
+
+        :param lending_id: The ID of the lending to update.
+        :param borrowing_date: The new borrowing date.
+        :param return_date: The new return date.
+        :param sign_off: The operator and witness signing off the return as a tuple, for open lendings of regulated
+            items that are closed with a return date.
+        :return:
+            None
+        :raise Exception: If the lending does not exist, no field is given or the return is signed off but the lending
+            is not open.
+        """
+        if self._count("SELECT COUNT(*) FROM Lendings WHERE LendingID = UUID_TO_BIN(%s);", (lending_id,)) == 0:
+            raise Exception(f"No lending with ID {lending_id} exists!")
+        if sign_off is not None:
+            open_loans = self._count(
+                "SELECT COUNT(*) FROM Lendings WHERE LendingID = UUID_TO_BIN(%s) AND ReturnDate IS NULL;", (lending_id,)
+            )
+            if return_date is None or open_loans == 0:
+                raise Exception(f"Only the return of an open lending can be signed off, {lending_id} is not one!")
+
+        try:
+            self._execute_update(
+                "Lendings",
+                "LendingID = UUID_TO_BIN(%s)",
+                lending_id,
+                {"BorrowingDate": borrowing_date, "ReturnDate": return_date},
+            )
+            if sign_off is not None:
+                self._record_sign_off(lending_id, "Return", *sign_off)
+            self.db.commit()
+        except Exception as err:
+            self.db.rollback()
+            raise err
+
+    def _count_signed_off(self, lendings: str, params: tuple) -> int:
+        # Sign-offs are a record of who handed out and took back regulated items, so the lendings they
+        # belong to are never deleted
+        return self._count(
+            f"SELECT COUNT(DISTINCT LendingID) FROM SignOffs WHERE LendingID IN ({lendings});", params
+        )
+
+    def _delete_items_where(self, clause: str, params: tuple) -> None:
+        # Only items without a history are deleted, the others are retired to keep their history
+        used = self._count(
+            f"""
+            SELECT COUNT(*) FROM Items i
+            WHERE i.ItemID IN (SELECT ItemID FROM Items WHERE {clause})
+            AND (
+                EXISTS (SELECT 1 FROM Lendings l WHERE l.ItemID = i.ItemID)
+                OR EXISTS (SELECT 1 FROM ItemEvents e WHERE e.ItemID = i.ItemID AND e.EventType <> 'Created')
+            );
+            """,
+            params,
+        )
+        if used > 0:
+            raise Exception(f"{used} of the items have a history, retire them instead of deleting them!")
+        self.cursor.execute(
+            f"DELETE FROM ItemEvents WHERE ItemID IN (SELECT ItemID FROM Items WHERE {clause});", params
+        )
+        self.cursor.execute(f"DELETE FROM Items WHERE {clause};", params)
+
+    # The item events that are recorded by hand, creation and condition changes are recorded by the
+    # inserts and updates and retirement by retire_item
+    MANUAL_ITEM_EVENTS = ("Maintenance", "Transfer")
+
+    def record_item_event(self, item_id: str, event_type: str, details: str, day=None) -> None:
+        """
+        Records something that happened to an item in its history.
+
+        :param item_id: The ID of the item.
+        :param event_type: Maintenance or Transfer.
+        :param details: What was done, or where the item was moved to.
+        :param day: When it happened, today if None.
+        :return:
+            None
+        :raise Exception: If the item does not exist or is retired, or the event type is not one of MANUAL_ITEM_EVENTS.
+        """
+        if event_type not in self.MANUAL_ITEM_EVENTS:
+            raise Exception(f"{event_type} is not an item event, expected one of {', '.join(self.MANUAL_ITEM_EVENTS)}!")
+        self._check_not_retired(item_id)
+
+        try:
+            self._record_item_event(item_id, event_type, details, day)
+            self.db.commit()
+        except Exception as err:
+            self.db.rollback()
+            raise err
+
+    def retire_item(self, item_id: str, reason: str, day=None) -> None:
+        """
+        Retires an item. The item is kept with its history but can no longer be lent out and is not counted
+        as part of the inventory.
+
+        :param item_id: The ID of the item to retire.
+        :param reason: Why the item is retired, e.g. worn out or lost.
+        :param day: When the item was retired, today if None.
+        :return:
+            None
+        :raise Exception: If the item does not exist, is already retired or is lent out.
+        """
+        self._check_not_retired(item_id)
+        open_loans = self._count(
+            "SELECT COUNT(*) FROM Lendings WHERE ItemID = UUID_TO_BIN(%s) AND ReturnDate IS NULL;", (item_id,)
+        )
+        if open_loans > 0:
+            raise Exception(f"Item {item_id} is currently lent out, return it before retiring it!")
+
+        try:
+            self.cursor.execute("UPDATE Items SET Retired = TRUE WHERE ItemID = UUID_TO_BIN(%s);", (item_id,))
+            self._record_item_event(item_id, "Retired", reason, day)
+            self.db.commit()
+        except Exception as err:
+            self.db.rollback()
+            raise err
+
+    def _check_not_retired(self, item_id: str) -> None:
+        self.cursor.execute("SELECT Retired FROM Items WHERE ItemID = UUID_TO_BIN(%s);", (item_id,))
+        row = self.cursor.fetchone()
+        if row is None:
+            raise Exception(f"No item with ID {item_id} exists!")
+        if row[0]:
+            raise Exception(f"Item {item_id} is retired!")
+
+    def delete_user(self, ssn: str, force: bool = False) -> None:
+        """
+        Deletes a user together with the user's lendings and the consumables issued to the user.
+
+        :param ssn: The SSN of the user to delete.
+        :param force: Delete the user even if the user has open loans.
+        :return:
+            None
+        :raise Exception: If the user does not exist, has open loans and force is not set or has signed off loans.
+        """
+        if self._count("SELECT COUNT(*) FROM Users WHERE SSN = %s;", (ssn,)) == 0:
+            raise Exception(f"No user with SSN {ssn} exists!")
+
+        open_loans = self._count("SELECT COUNT(*) FROM Lendings WHERE SSN = %s AND ReturnDate IS NULL;", (ssn,))
+        if open_loans > 0 and not force:
+            raise Exception(f"User {ssn} has {open_loans} open loan(s), use --force to delete anyway!")
+
+        if self._count_signed_off("SELECT LendingID FROM Lendings WHERE SSN = %s", (ssn,)) > 0:
+            raise Exception(f"User {ssn} has signed off lendings that are kept, erase the user instead!")
+
+        try:
+            self.cursor.execute("DELETE FROM Lendings WHERE SSN = %s;", (ssn,))
+            self.cursor.execute("DELETE FROM StockTransactions WHERE SSN = %s;", (ssn,))
+            self.cursor.execute("DELETE FROM Users WHERE SSN = %s;", (ssn,))
+            self.db.commit()
+        except Exception as err:
+            self.db.rollback()
+            raise err
+
+    # Erased users get this followed by random hex digits as SSN, matches PSEUDONYM_PREFIX in ssn.rs
+    PSEUDONYM_PREFIX = "ANON-"
+
+    def erase_user(self, ssn: str) -> str:
+        """
+        Erases the personal data of a user. The user is replaced by a new user with a random pseudonym
+        as SSN and no name, and the user's lendings and issued consumables are moved over to it so
+        loan and consumption statistics are kept.
+
+        :param ssn: The SSN of the user to erase.
+        :return:
+            The pseudonym that replaced the SSN.
+        :raise Exception: If the user does not exist, is already erased or has open loans.
+        """
+        if self._count("SELECT COUNT(*) FROM Users WHERE SSN = %s;", (ssn,)) == 0:
+            raise Exception(f"No user with SSN {ssn} exists!")
+        if ssn.startswith(self.PSEUDONYM_PREFIX):
+            raise Exception(f"User {ssn} is already erased!")
+
+        open_loans = self._count("SELECT COUNT(*) FROM Lendings WHERE SSN = %s AND ReturnDate IS NULL;", (ssn,))
+        if open_loans > 0:
+            raise Exception(f"User {ssn} has {open_loans} open loan(s), they must be returned before the user is erased!")
+
+        pseudonym = self.PSEUDONYM_PREFIX + uuid.uuid4().hex[:8]
+        try:
+            # The unit and activity are kept for statistics, everything that identifies the person is not
+            self.cursor.execute(
+                "INSERT INTO Users (SSN, Name, Unit, Active) SELECT %s, %s, Unit, FALSE FROM Users WHERE SSN = %s;",
+                (pseudonym, "Erased user", ssn),
+            )
+            self.cursor.execute("UPDATE Lendings SET SSN = %s WHERE SSN = %s;", (pseudonym, ssn))
+            self.cursor.execute("UPDATE StockTransactions SET SSN = %s WHERE SSN = %s;", (pseudonym, ssn))
+            self.cursor.execute("DELETE FROM Users WHERE SSN = %s;", (ssn,))
+            self.db.commit()
+        except Exception as err:
+            self.db.rollback()
+            raise err
+
+        return pseudonym
+
+    def delete_product(self, product_id: str, force: bool = False) -> None:
+        """
+        Deletes a product. When forced, all items of the product are deleted as well, as long as none of them
+        has a history beyond its creation. The stock and transactions of a consumable product are always
+        deleted with it.
+
+        :param product_id: The ID of the product to delete.
+        :param force: Delete the product even if it has items.
+        :return:
+            None
+        :raise Exception: If the product does not exist, has items and force is not set or has items with a history,
+            which have to be retired instead.
+        """
+        if self._count("SELECT COUNT(*) FROM Products WHERE ProductID = %s;", (product_id,)) == 0:
+            raise Exception(f"No product with ID {product_id} exists!")
+
+        items = self._count("SELECT COUNT(*) FROM Items WHERE ProductID = %s;", (product_id,))
+        if items > 0 and not force:
+            raise Exception(f"Product {product_id} has {items} item(s), use --force to delete them as well!")
+
+        try:
+            self._delete_items_where("ProductID = %s", (product_id,))
+            self.cursor.execute("DELETE FROM StockTransactions WHERE ProductID = %s;", (product_id,))
+            self.cursor.execute("DELETE FROM ConsumableStock WHERE ProductID = %s;", (product_id,))
+            self.cursor.execute("DELETE FROM Products WHERE ProductID = %s;", (product_id,))
+            self.db.commit()
+        except Exception as err:
+            self.db.rollback()
+            raise err
+
+    def delete_loan(self, lending_id: str) -> None:
+        """
+        Deletes a lending.
+
+        :param lending_id: The ID of the lending to delete.
+        :return:
+            None
+        :raise Exception: If the lending does not exist or was signed off.
+        """
+        if self._count("SELECT COUNT(*) FROM Lendings WHERE LendingID = UUID_TO_BIN(%s);", (lending_id,)) == 0:
+            raise Exception(f"No lending with ID {lending_id} exists!")
+        if self._count_signed_off("SELECT UUID_TO_BIN(%s)", (lending_id,)) > 0:
+            raise Exception(f"Lending {lending_id} was signed off and is kept, it can't be deleted!")
+
+        try:
+            self.cursor.execute("DELETE FROM Lendings WHERE LendingID = UUID_TO_BIN(%s);", (lending_id,))
+            self.db.commit()
+        except Exception as err:
+            self.db.rollback()
+            raise err
+
+    def search_items(self, search_param: str) -> list[ItemProduct]:
+        """
+        Searches for items in the database.
+        It will get a list of items that have the search parameter in their name, type or size.
+
+        :param search_param: The search parameter.
+        :return:
+            A list of items that have the search parameter in their name, type or size.
+        """
+        query = f"""
+            SELECT
+                i.ProductID as product_id,
+                p.NameOfProduct AS product_name,
+                p.Type AS product_type,
+                i.Quantity as quantity,
+                i.Size AS size
+            FROM
+                Products p
+                    JOIN
+                (SELECT ProductID, Size, count(*) as Quantity from Items WHERE NOT Retired group by ProductID, Size)
+                    AS
+                    i ON p.ProductID = i.ProductID
+            WHERE
+                (
+                    p.NameOfProduct LIKE '%{search_param}%' OR
+                    p.Type LIKE '%{search_param}%' OR
+                    i.Size LIKE '%{search_param}%'
+                )
+            ORDER BY
+                p.NameOfProduct
+        """
+
+        self.cursor.execute(query)
+        items = self.cursor.fetchall()
+        items_list = [ItemProduct(*item) for item in items]
+        return items_list
+
+    @staticmethod
+    def _search_conditions(query, fields: dict, text_columns: list[str]) -> tuple[str, list]:
+        """
+        Builds the WHERE clause for a parsed search query.
+
+        :param query: The SearchQuery with free text terms and qualifiers.
+        :param fields: The fields the qualifiers may use, see PRODUCT_SEARCH_FIELDS.
+        :param text_columns: The columns the free text terms are matched against.
+        :return:
+            The WHERE clause, empty if there is nothing to filter on, and its parameters.
+
+        :raise Exception: If a qualifier uses an unknown field, operator or value.
+        """
+        conditions = []
+        params = []
+
+        for term in query.terms:
+            conditions.append("(" + " OR ".join(f"{column} LIKE %s" for column in text_columns) + ")")
+            params.extend([f"%{term}%"] * len(text_columns))
+
+        for qualifier in query.qualifiers:
+            if qualifier.field not in fields:
+                raise Exception(
+                    f"Unknown search field '{qualifier.field}', valid fields are: {', '.join(fields)}"
+                )
+            column, kind = fields[qualifier.field]
+
+            if qualifier.op != ":" and kind not in ("number", "date"):
+                raise Exception(f"The field '{qualifier.field}' can not be compared with {qualifier.op}")
+
+            if kind == "bool":
+                value = qualifier.value.lower()
+                if value in ("yes", "y", "true", "1"):
+                    conditions.append(f"({column})")
+                elif value in ("no", "n", "false", "0"):
+                    conditions.append(f"NOT ({column})")
+                else:
+                    raise Exception(f"The field '{qualifier.field}' takes yes or no, not '{qualifier.value}'")
+            elif kind == "text":
+                conditions.append(f"{column} LIKE %s")
+                params.append(f"%{qualifier.value}%")
+            elif kind == "number":
+                try:
+                    value = float(qualifier.value)
+                except ValueError:
+                    raise Exception(f"The field '{qualifier.field}' takes a number, not '{qualifier.value}'")
+                conditions.append(f"{column} {SEARCH_COMPARISONS.get(qualifier.op, '=')} %s")
+                params.append(value)
+            else:
+                conditions.append(f"{column} {SEARCH_COMPARISONS.get(qualifier.op, '=')} %s")
+                params.append(qualifier.value)
+
+        where = f"WHERE {' AND '.join(conditions)}" if conditions else ""
+        return where, params
+
+    def find_products(self, query) -> list[ProductRow]:
+        """
+        Searches products with a parsed search query.
+
+        :param query: The SearchQuery to search with, see PRODUCT_SEARCH_FIELDS for the valid fields.
+        :return:
+            A list of ProductRow objects that match the query.
+        """
+        where, params = self._search_conditions(
+            query, PRODUCT_SEARCH_FIELDS, ["p.ProductID", "p.NameOfProduct", "p.Type"]
+        )
+        self.cursor.execute(
+            f"SELECT p.ProductID, p.NameOfProduct, p.Type FROM Products p {where} ORDER BY p.NameOfProduct;",
+            params,
+        )
+        return [ProductRow(*product) for product in self.cursor.fetchall()]
+
+    def find_items(self, query) -> list[ItemRecord]:
+        """
+        Searches single items with a parsed search query.
+
+        :param query: The SearchQuery to search with, see ITEM_SEARCH_FIELDS for the valid fields.
+        :return:
+            A list of ItemRecord objects that match the query.
+        """
+        where, params = self._search_conditions(
+            query, ITEM_SEARCH_FIELDS, ["p.NameOfProduct", "p.Type", "i.Size", "i.SerialNumber", "i.ItemCode"]
+        )
+        self.cursor.execute(
+            f"""
+            SELECT i.ItemID, i.ProductID, p.NameOfProduct, p.Type, i.Size, i.Quality, l.ItemID IS NULL AND NOT i.Retired,
+                i.SerialNumber, i.ItemCode
+            FROM Items i
+            JOIN Products p ON i.ProductID = p.ProductID
+            LEFT JOIN Lendings l ON i.ItemID = l.ItemID AND l.ReturnDate IS NULL
+            {where}
+            ORDER BY p.NameOfProduct, i.Size;
+            """,
+            params,
+        )
+        return [ItemRecord(*item) for item in self.cursor.fetchall()]
+
+    def find_users(self, query) -> list[User]:
+        """
+        Searches users with a parsed search query.
+
+        :param query: The SearchQuery to search with, see USER_SEARCH_FIELDS for the valid fields.
+        :return:
+            A list of User objects that match the query.
+        """
+        where, params = self._search_conditions(query, USER_SEARCH_FIELDS, ["u.SSN", "u.Name"])
+        columns = ", ".join(f"u.{column}" for column in USER_COLUMNS.split(", "))
+        self.cursor.execute(f"SELECT {columns} FROM Users u {where} ORDER BY u.Name;", params)
+        return [User(*user) for user in self.cursor.fetchall()]
+
+    def find_loans(self, query) -> list[AllBorrowed]:
+        """
+        Searches loans with a parsed search query.
+
+        :param query: The SearchQuery to search with, see LOAN_SEARCH_FIELDS for the valid fields.
+        :return:
+            A list of AllBorrowed objects that match the query.
+        """
+        where, params = self._search_conditions(
+            query, LOAN_SEARCH_FIELDS, ["u.Name", "l.SSN", "p.NameOfProduct"]
+        )
+        self.cursor.execute(
+            f"""
+            SELECT l.LendingID, l.SSN, u.Name, l.ItemID, p.NameOfProduct, i.Size, l.BorrowingDate, l.ReturnDate,
+                i.ItemCode
+            FROM Lendings l
+            JOIN Users u ON l.SSN = u.SSN
+            JOIN Items i ON l.ItemID = i.ItemID
+            JOIN Products p ON i.ProductID = p.ProductID
+            {where}
+            ORDER BY l.BorrowingDate DESC;
+            """,
+            params,
+        )
+        return [AllBorrowed(*loan) for loan in self.cursor.fetchall()]
+
+    ROLES = ("viewer", "clerk", "quartermaster", "admin")
+    PASSWORD_ITERATIONS = 200_000
+
+    def _create_operators_table(self) -> None:
+        self.cursor.execute(
+            """CREATE TABLE IF NOT EXISTS Operators (
+                -- Primary key
+                Username VARCHAR(64) NOT NULL,
+
+                -- Attributes
+                Role VARCHAR(16) NOT NULL,
+                PasswordHash VARCHAR(128) NOT NULL,
+                LoginTokenHash CHAR(64),
+
+                PRIMARY KEY(Username)
+            );"""
+        )
+        self._add_column_if_missing("Operators", "LoginTokenHash", "CHAR(64) AFTER PasswordHash")
+
+    def _add_column_if_missing(self, table: str, column: str, definition: str) -> bool:
+        """
+        Adds a column to a table created before the column existed.
 
-    def user_all_borrowed(self, ssn: str) -> list[AllBorrowed]:
+        :param table: The table to add the column to.
+        :param column: The name of the column.
+        :param definition: The type and position of the column, as in ALTER TABLE ... ADD COLUMN.
+        :return:
+            True if the column was added, False if it already existed.
         """
-        Retrieves all borrowed items for a specific user.
+        self.cursor.execute(
+            """SELECT COUNT(*) FROM information_schema.COLUMNS
+               WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = %s AND COLUMN_NAME = %s;""",
+            (table, column),
+        )
+        if self.cursor.fetchone()[0] > 0:
+            return False
+        self.cursor.execute(f"ALTER TABLE {table} ADD COLUMN {column} {definition};")
+        return True
 
-        :param ssn: The SSN of the user as a string.
+    def _add_unique_key_if_missing(self, table: str, key: str, columns: str) -> bool:
+        """
+        Adds a unique key to a table created before the key existed.
+
+        :param table: The table to add the key to.
+        :param key: The name of the key.
+        :param columns: The columns of the key, separated by commas.
         :return:
-            A list of AllBorrowed objects containing information about the user's borrowed items.
+            True if the key was added, False if it already existed.
         """
-        query = f"""
-            select * from show_borrowed_view where SSN = ('{ssn}');
-                    """
+        self.cursor.execute(
+            """SELECT COUNT(*) FROM information_schema.STATISTICS
+               WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = %s AND INDEX_NAME = %s;""",
+            (table, key),
+        )
+        if self.cursor.fetchone()[0] > 0:
+            return False
+        self.cursor.execute(f"ALTER TABLE {table} ADD UNIQUE KEY {key} ({columns});")
+        return True
 
-        self.cursor.execute(query)
-        allborrowed = self.cursor.fetchall()
-        allborrowed_list = [AllBorrowed(*allborrowed) for allborrowed in allborrowed]
-        return allborrowed_list
+    def _hash_password(self, password: str, salt: bytes = None) -> str:
+        salt = salt or os.urandom(16)
+        digest = hashlib.pbkdf2_hmac("sha256", password.encode(), salt, self.PASSWORD_ITERATIONS)
+        return f"{salt.hex()}${digest.hex()}"
 
-    def number_of_borrowes(self) -> list[NumberBorrow]:
+    def count_operators(self) -> int:
         """
-        Retrieves the total number of borrowes for each user.
+        Counts the operator accounts.
 
         :return:
-            A list of TotBorrowes objects containing information about the total number of borrowes for each user.
+            The number of operators, 0 if the Operators table has not been created yet.
         """
-        query = """
-            SELECT * FROM number_of_borrowes;
-                    """
-
-        self.cursor.execute(query)
-        borrowes = self.cursor.fetchall()
-        borrowes_list = [NumberBorrow(*borrowes) for borrowes in borrowes]
-        return borrowes_list
+        self.cursor.execute("SHOW TABLES LIKE 'Operators';")
+        if self.cursor.fetchone() is None:
+            return 0
+        return self._count("SELECT COUNT(*) FROM Operators;", ())
 
-    @staticmethod
-    def get_config() -> dict:
+    def get_operator_role(self, username: str):
         """
-        Retrieves the Armory Atlas config file.
+        Gets the role of an operator.
 
+        :param username: The username of the operator.
         :return:
-            A dictionary containing the Armory Atlas config.
+            The role of the operator or None if there is no such operator.
         """
-        if os.name == 'nt':
-            home_dir = os.getenv('USERPROFILE')
-        else:
-            home_dir = os.getenv('HOME')
+        if self.count_operators() == 0:
+            return None
+        self.cursor.execute("SELECT Role FROM Operators WHERE Username = %s;", (username,))
+        row = self.cursor.fetchone()
+        return row[0] if row else None
 
-        config_path = os.path.join(home_dir, '.config', 'armoryatlas', 'config.toml')
-        with open(config_path, "r") as f:
-            config = toml.load(f)
-        return config
+    def get_operators(self) -> list[Operator]:
+        """
+        Gets every operator account, without the password hashes.
 
-    def insert_loan(self, loan) -> None:
+        :return:
+            The operators sorted by username.
         """
-        Inserts a loan into the database.
+        if self.count_operators() == 0:
+            return []
+        self.cursor.execute("SELECT Username, Role FROM Operators ORDER BY Username;")
+        return [Operator(*row) for row in self.cursor.fetchall()]
 
-        :param loan: The loan object to be inserted.
+    def add_operator(self, username: str, role: str, password: str) -> None:
+        """
+        Adds an operator account, creating the Operators table if needed.
+
+        :param username: The username of the operator.
+        :param role: One of viewer, clerk, quartermaster or admin.
+        :param password: The password the operator logs in with.
         :return:
             None
-        :raise mysql.connector.Error: If there is an error while executing the database query.
-        """
-        borrowing_date = loan.borrowing_date.strftime('%Y-%m-%d') if loan.borrowing_date else None
-        return_date = loan.return_date.strftime('%Y-%m-%d') if loan.return_date else None
-        query = f"""
-            INSERT INTO Lendings (LendingID, SSN, ItemID, BorrowingDate, ReturnDate) 
-            VALUES (UUID_TO_BIN(UUID()), '{loan.ssn}', UUID_TO_BIN('{loan.item_id}'), '{borrowing_date}', %s);
+        :raise Exception: If the username is taken, the role is unknown or the password is empty.
         """
+        if role not in self.ROLES:
+            raise Exception(f"{role} is not a role, expected one of {', '.join(self.ROLES)}!")
+        if not username or not password:
+            raise Exception("An operator needs both a username and a password!")
+
+        self._create_operators_table()
+        if self._count("SELECT COUNT(*) FROM Operators WHERE Username = %s;", (username,)) > 0:
+            raise Exception(f"An operator called {username} already exists!")
 
         try:
-            self.cursor.execute(query, (return_date,))
-            self.db.commit()  # Commit the transaction
-        except mysql.connector.Error as err:
-            self.db.rollback()  # Rollback the transaction in case of error
+            self.cursor.execute(
+                "INSERT INTO Operators (Username, Role, PasswordHash) VALUES (%s, %s, %s);",
+                (username, role, self._hash_password(password)),
+            )
+            self.db.commit()
+        except Exception as err:
+            self.db.rollback()
             raise err
 
-    def insert_product(self, product) -> None:
+    def remove_operator(self, username: str) -> None:
         """
-        Inserts a product into the database.
+        Removes an operator account.
 
-        :param product: The product object to be inserted.
+        :param username: The username of the operator to remove.
         :return:
             None
-        :raise mysql.connector.Error: If there is an error while executing the database query.
+        :raise Exception: If there is no such operator or it is the last admin.
         """
-        query = f"""
-            INSERT INTO Products (ProductID, NameOfProduct, Type) VALUES ('{product.product_id}', '{product.product_name}', '{product.product_type}')
-            """
+        role = self.get_operator_role(username)
+        if role is None:
+            raise Exception(f"No operator called {username} exists!")
+
+        # Without an admin nobody could manage the operators, and without any operator everyone would have full rights
+        admins = self._count("SELECT COUNT(*) FROM Operators WHERE Role = 'admin';", ())
+        if role == "admin" and admins == 1:
+            raise Exception(f"{username} is the last admin, add another admin before removing it!")
 
         try:
-            self.cursor.execute(query)
-            self.db.commit()  # Commit the transaction
-        except mysql.connector.Error as err:
-            self.db.rollback()  # Rollback the transaction in case of error
+            self.cursor.execute("DELETE FROM Operators WHERE Username = %s;", (username,))
+            self.db.commit()
+        except Exception as err:
+            self.db.rollback()
             raise err
 
-    def insert_item(self, item) -> None:
+    def verify_operator(self, username: str, password: str) -> bool:
         """
-        Inserts an item into the database.
+        Checks the password of an operator.
 
-        :param item: The item object to be inserted.
+        :param username: The username of the operator.
+        :param password: The password to check.
         :return:
-            None
-        :raise mysql.connector.Error: If there is an error while executing the database query.
+            True if the operator exists and the password is correct.
         """
-        query = f"""
-            INSERT INTO Items (ItemID, ProductID, Size, Quality) VALUES (UUID_TO_BIN(UUID()), \"{item.product_id}\", \"{item.size}\", {item.quality})
+        if self.count_operators() == 0:
+            return False
+        self.cursor.execute("SELECT PasswordHash FROM Operators WHERE Username = %s;", (username,))
+        row = self.cursor.fetchone()
+        if row is None:
+            return False
+
+        salt, _ = row[0].split("$", 1)
+        return hmac.compare_digest(row[0], self._hash_password(password, bytes.fromhex(salt)))
+
+    def login_operator(self, username: str, password: str):
         """
+        Logs an operator in, replacing the token of any earlier login.
 
-        try:
-            self.cursor.execute(query)
-            self.db.commit()  # Commit the transaction
-        except mysql.connector.Error as err:
-            self.db.rollback()  # Rollback the transaction in case of error
-            raise err
+        Only a hash of the token is stored, so the token can't be read from the database.
 
-    def insert_user(self, user) -> None:
+        :param username: The username of the operator.
+        :param password: The password of the operator.
+        :return:
+            The login token or None if the username or password is wrong.
         """
-        Inserts a user into the database.
+        if not self.verify_operator(username, password):
+            return None
 
-        :param user: The user object to be inserted.
+        token = secrets.token_hex(32)
+        self._set_login_token_hash(username, hashlib.sha256(token.encode()).hexdigest())
+        return token
+
+    def logout_operator(self, username: str) -> None:
+        """
+        Logs an operator out, the token of the last login stops working.
+
+        :param username: The username of the operator.
         :return:
             None
-        :raise mysql.connector.Error: If there is an error while executing the database query.
-        """
-        query = f"""
-            INSERT INTO Users (SSN, Name) VALUES (\"{user.ssn}\", \"{user.name}\")
         """
+        if self.count_operators() > 0:
+            self._set_login_token_hash(username, None)
 
+    def _set_login_token_hash(self, username: str, token_hash) -> None:
+        self._create_operators_table()
         try:
-            self.cursor.execute(query)
-            self.db.commit()  # Commit the transaction
-        except mysql.connector.Error as err:
-            self.db.rollback()  # Rollback the transaction in case of error
+            self.cursor.execute(
+                "UPDATE Operators SET LoginTokenHash = %s WHERE Username = %s;",
+                (token_hash, username),
+            )
+            self.db.commit()
+        except Exception as err:
+            self.db.rollback()
             raise err
 
-    def search_items(self, search_param: str) -> list[ItemProduct]:
+    def get_logged_in_role(self, username: str, token: str):
         """
-        Searches for items in the database.
-        It will get a list of items that have the search parameter in their name, type or size.
+        Gets the role of a logged in operator.
 
-        :param search_param: The search parameter.
+        :param username: The username of the operator.
+        :param token: The token the operator got when logging in.
         :return:
-            A list of items that have the search parameter in their name, type or size.
+            The role of the operator or None if there is no such operator or the token is not the
+            one of its last login.
         """
-        query = f"""
-            SELECT
-                i.ProductID as product_id,
-                p.NameOfProduct AS product_name,
-                p.Type AS product_type,
-                i.Quantity as quantity,
-                i.Size AS size
-            FROM
-                Products p
-                    JOIN
-                (SELECT ProductID, Size, count(*) as Quantity from Items group by ProductID, Size)
-                    AS
-                    i ON p.ProductID = i.ProductID
-            WHERE
-                (
-                    p.NameOfProduct LIKE '%{search_param}%' OR
-                    p.Type LIKE '%{search_param}%' OR
-                    i.Size LIKE '%{search_param}%'
-                )
-            ORDER BY
-                p.NameOfProduct
+        if self.count_operators() == 0:
+            return None
+        self._create_operators_table()
+        self.cursor.execute(
+            "SELECT Role, LoginTokenHash FROM Operators WHERE Username = %s;", (username,)
+        )
+        row = self.cursor.fetchone()
+        if row is None or row[1] is None:
+            return None
+
+        token_hash = hashlib.sha256(token.encode()).hexdigest()
+        return row[0] if hmac.compare_digest(row[1], token_hash) else None
+
+    # The tables of an archive in the order they can be restored in, with the columns read for each
+    # field. Returned loans come before open ones so the check_borrowed trigger accepts them. Operators
+    # are left out, restoring an archive must not grant the accounts of another depot access.
+    ARCHIVE_TABLES = {
+        "products": ("Products", [
+            ("product_id", "ProductID"),
+            ("product_name", "NameOfProduct"),
+            ("product_type", "Type"),
+        ], "ProductID"),
+        "items": ("Items", [
+            ("item_id", "ItemID"),
+            ("product_id", "ProductID"),
+            ("size", "Size"),
+            ("quality", "Quality"),
+            ("serial_number", "SerialNumber"),
+            ("item_code", "ItemCode"),
+            ("retired", "Retired"),
+        ], "ItemID"),
+        "users": ("Users", [
+            ("ssn", "SSN"),
+            ("name", "Name"),
+            ("unit", "Unit"),
+            ("rank", "ServiceRank"),
+            ("phone", "Phone"),
+            ("email", "Email"),
+            ("active", "Active"),
+            ("end_of_service", "EndOfService"),
+        ], "SSN"),
+        "loans": ("Lendings", [
+            ("lending_id", "LendingID"),
+            ("ssn", "SSN"),
+            ("item_id", "ItemID"),
+            ("borrow_date", "BorrowingDate"),
+            ("return_date", "ReturnDate"),
+        ], "ReturnDate IS NULL, BorrowingDate"),
+        "item_events": ("ItemEvents", [
+            ("event_id", "EventID"),
+            ("item_id", "ItemID"),
+            ("event_date", "EventDate"),
+            ("event_type", "EventType"),
+            ("details", "Details"),
+        ], "EventDate"),
+        "sign_offs": ("SignOffs", [
+            ("sign_off_id", "SignOffID"),
+            ("lending_id", "LendingID"),
+            ("action", "Action"),
+            ("operator", "Operator"),
+            ("witness", "Witness"),
+            ("signed_at", "SignedAt"),
+        ], "SignedAt"),
+        "consumable_stock": ("ConsumableStock", [
+            ("product_id", "ProductID"),
+            ("quantity", "Quantity"),
+        ], "ProductID"),
+        "stock_transactions": ("StockTransactions", [
+            ("transaction_id", "TransactionID"),
+            ("product_id", "ProductID"),
+            ("kind", "Kind"),
+            ("quantity", "Quantity"),
+            ("ssn", "SSN"),
+            ("transaction_date", "TransactionDate"),
+            ("operator", "Operator"),
+            ("note", "Note"),
+        ], "TransactionDate"),
+    }
+
+    # Columns holding UUIDs, they are stored as BINARY(16) and archived as text
+    UUID_COLUMNS = {"ItemID", "LendingID", "EventID", "SignOffID", "TransactionID"}
+    # Columns holding booleans, MySQL returns them as 0 or 1
+    BOOL_COLUMNS = {"Active", "Retired"}
+
+    def export_all(self) -> str:
         """
+        Reads every product, item, user, lending, item event, sign-off, consumable stock and stock transaction record in
+        the database, operator accounts are not read.
 
-        self.cursor.execute(query)
-        items = self.cursor.fetchall()
-        items_list = [ItemProduct(*item) for item in items]
-        return items_list
+        :return:
+            A JSON object with one list of records per table, IDs are written as UUID strings and dates as YYYY-MM-DD.
+        :raise mysql.connector.Error: If there is an error while executing the database query.
+        """
+        data = {}
+        for name, (table, fields, order) in self.ARCHIVE_TABLES.items():
+            columns = ", ".join(
+                f"BIN_TO_UUID({column})" if column in self.UUID_COLUMNS else column for _, column in fields
+            )
+            self.cursor.execute(f"SELECT {columns} FROM {table} ORDER BY {order};")
+            data[name] = [dict(zip([field for field, _ in fields], row)) for row in self.cursor.fetchall()]
+            for field, column in fields:
+                if column in self.BOOL_COLUMNS:
+                    for record in data[name]:
+                        record[field] = bool(record[field])
+
+        return json.dumps(data, default=str)
+
+    def restore_all(self, data: str) -> None:
+        """
+        Restores the records read by export_all, keeping their IDs. Everything is restored in a single transaction.
+        Operators in archives of older versions are ignored.
+
+        :param data: The JSON object returned by export_all.
+        :return:
+            None
+        :raise Exception: If the database already contains data.
+        :raise mysql.connector.Error: If there is an error while executing the database query.
+        """
+        data = json.loads(data)
+
+        for table, _, _ in self.ARCHIVE_TABLES.values():
+            if self._count(f"SELECT COUNT(*) FROM {table};", ()) > 0:
+                raise Exception(f"The {table} table is not empty, restore into an empty database!")
+
+        try:
+            for name, (table, fields, _) in self.ARCHIVE_TABLES.items():
+                columns = ", ".join(column for _, column in fields)
+                values = ", ".join(
+                    "UUID_TO_BIN(%s)" if column in self.UUID_COLUMNS else "%s" for _, column in fields
+                )
+                query = f"INSERT INTO {table} ({columns}) VALUES ({values});"
+                records = data.get(name, [])
+                if name == "loans":
+                    records = sorted(records, key=lambda loan: (loan["return_date"] is None, loan["borrow_date"]))
+                for record in records:
+                    self.cursor.execute(query, tuple(record[field] for field, _ in fields))
+            # Archives made before items had codes get them now
+            self._assign_item_codes()
+            self.db.commit()
+        except Exception as err:
+            self.db.rollback()
+            raise err
 
     def _drop_tables(self):
         queries = [
+            """
+                DROP TABLE IF EXISTS StockTransactions;
+            """,
+            """
+                DROP TABLE IF EXISTS ConsumableStock;
+            """,
+            """
+                DROP TABLE IF EXISTS SignOffs;
+            """,
             """
                 DROP TABLE IF EXISTS Lendings;
             """,
+            """
+                DROP TABLE IF EXISTS ItemEvents;
+            """,
             """
                 DROP TABLE IF EXISTS Items;
             """,
@@ -576,7 +2430,8 @@ class DBHandler:
 
     def drop_all(self):
         """
-        Drops all the tables in the database.
+        Drops all the tables in the database. The operator accounts are kept, otherwise anyone could
+        act as admin until the first account is added again.
 
         :return:
             None
@@ -591,10 +2446,16 @@ class DBHandler:
         queries = [
             """CREATE TABLE IF NOT EXISTS Users (
                 -- Primary key
-                SSN VARCHAR(11) NOT NULL,
+                SSN VARCHAR(13) NOT NULL,
             
                 -- Attributes
                 Name VARCHAR(250) NOT NULL,
+                Unit VARCHAR(250),
+                ServiceRank VARCHAR(64),
+                Phone VARCHAR(32),
+                Email VARCHAR(250),
+                Active BOOLEAN NOT NULL DEFAULT TRUE,
+                EndOfService DATE,
             
                 PRIMARY KEY(SSN)
             );""",
@@ -618,8 +2479,13 @@ class DBHandler:
                 -- Attributes
                 Size VARCHAR(4),
                 Quality FLOAT NOT NULL,
+                SerialNumber VARCHAR(64),
+                ItemCode VARCHAR(16),
+                Retired BOOLEAN NOT NULL DEFAULT FALSE,
             
                 PRIMARY KEY(ItemID),
+                UNIQUE KEY SerialPerProduct (ProductID, SerialNumber),
+                UNIQUE KEY ItemCode (ItemCode),
             
                 CONSTRAINT FKs
                     FOREIGN KEY(ProductID) REFERENCES Products(ProductID)
@@ -629,7 +2495,7 @@ class DBHandler:
                 LendingID BINARY(16) NOT NULL,
             
                 -- Foreign Key
-                SSN VARCHAR(11) NOT NULL,
+                SSN VARCHAR(13) NOT NULL,
                 ItemID BINARY(16) NOT NULL,
             
                 -- Attributes
@@ -643,12 +2509,285 @@ class DBHandler:
                 CONSTRAINT FK2
                     FOREIGN KEY(ItemID) REFERENCES Items(ItemID)
             );""",
+            """CREATE TABLE IF NOT EXISTS ItemEvents (
+                -- Primary key
+                EventID BINARY(16) NOT NULL,
+            
+                -- Foreign Key
+                ItemID BINARY(16) NOT NULL,
+            
+                -- Attributes
+                EventDate DATE NOT NULL,
+                EventType VARCHAR(32) NOT NULL,
+                Details VARCHAR(250),
+            
+                PRIMARY KEY(EventID),
+            
+                CONSTRAINT FK3
+                    FOREIGN KEY(ItemID) REFERENCES Items(ItemID)
+            );""",
+            """CREATE TABLE IF NOT EXISTS SignOffs (
+                -- Primary key
+                SignOffID BINARY(16) NOT NULL,
+            
+                -- Foreign Key
+                LendingID BINARY(16) NOT NULL,
+            
+                -- Attributes
+                Action VARCHAR(16) NOT NULL,
+                Operator VARCHAR(64) NOT NULL,
+                Witness VARCHAR(64) NOT NULL,
+                SignedAt DATETIME NOT NULL,
+            
+                PRIMARY KEY(SignOffID),
+            
+                CONSTRAINT FK4
+                    FOREIGN KEY(LendingID) REFERENCES Lendings(LendingID)
+            );""",
+            """CREATE TABLE IF NOT EXISTS ConsumableStock (
+                -- Primary key
+                ProductID VARCHAR(16) NOT NULL,
+            
+                -- Attributes
+                Quantity INT NOT NULL CHECK (Quantity >= 0),
+            
+                PRIMARY KEY(ProductID),
+            
+                CONSTRAINT FK5
+                    FOREIGN KEY(ProductID) REFERENCES Products(ProductID)
+            );""",
+            """CREATE TABLE IF NOT EXISTS StockTransactions (
+                -- Primary key
+                TransactionID BINARY(16) NOT NULL,
+            
+                -- Foreign Key
+                ProductID VARCHAR(16) NOT NULL,
+                SSN VARCHAR(13),
+            
+                -- Attributes
+                Kind VARCHAR(16) NOT NULL,
+                Quantity INT NOT NULL,
+                TransactionDate DATE NOT NULL,
+                Operator VARCHAR(64),
+                Note VARCHAR(250),
+            
+                PRIMARY KEY(TransactionID),
+            
+                CONSTRAINT FK6
+                    FOREIGN KEY(ProductID) REFERENCES Products(ProductID),
+                CONSTRAINT FK7
+                    FOREIGN KEY(SSN) REFERENCES Users(SSN)
+            );""",
 
         ]
 
         for query in queries:
             self.cursor.execute(query)
 
+        self._create_operators_table()
+        self._migrate()
+
+    # The schema changes made after the first release, in the order they are applied. Tables created
+    # by _create_tables already have the latest schema, so every step leaves them unchanged.
+    MIGRATIONS = [
+        (1, "_migrate_canonical_ssns"),
+        (2, "_migrate_user_profiles"),
+        (3, "_migrate_serial_numbers"),
+        (4, "_migrate_item_codes"),
+        (5, "_migrate_item_history"),
+    ]
+
+    def _migrate(self) -> None:
+        """
+        Brings a database created by an earlier version up to the latest schema.
+
+        The version of the schema is kept in the SchemaVersion table and only the newer steps of
+        MIGRATIONS are run.
+
+        :return:
+            None
+        """
+        self.cursor.execute(
+            """CREATE TABLE IF NOT EXISTS SchemaVersion (
+                Version INT NOT NULL,
+                MigratedAt DATETIME NOT NULL,
+
+                PRIMARY KEY(Version)
+            );"""
+        )
+        self.cursor.execute("SELECT COALESCE(MAX(Version), 0) FROM SchemaVersion;")
+        current = self.cursor.fetchone()[0]
+
+        for version, step in self.MIGRATIONS:
+            if version <= current:
+                continue
+            getattr(self, step)()
+            self.cursor.execute(
+                "INSERT INTO SchemaVersion (Version, MigratedAt) VALUES (%s, NOW());", (version,)
+            )
+            self.db.commit()
+
+    # Every column holding an SSN, the primary key of Users is referenced by the others
+    SSN_COLUMNS = [("Users", "NOT NULL"), ("Lendings", "NOT NULL"), ("StockTransactions", "NULL")]
+
+    def _migrate_canonical_ssns(self) -> None:
+        """
+        Widens the SSN columns to fit YYYYMMDD-XXXX and rewrites the stored YYMMDD-XXXX numbers
+        into that form, see canonical_ssn.
+
+        The foreign key checks are turned off meanwhile, as Lendings and StockTransactions refer to
+        the SSNs that are rewritten.
+
+        :return:
+            None
+        """
+        today = datetime.date.today()
+        self.cursor.execute("SET FOREIGN_KEY_CHECKS = 0;")
+        try:
+            for table, null in self.SSN_COLUMNS:
+                self.cursor.execute(f"ALTER TABLE {table} MODIFY SSN VARCHAR(13) {null};")
+
+            self.cursor.execute("SELECT SSN FROM Users;")
+            renamed = [
+                (canonical, ssn)
+                for (ssn,) in self.cursor.fetchall()
+                if (canonical := self.canonical_ssn(ssn, today)) != ssn
+            ]
+            for table, _ in self.SSN_COLUMNS:
+                self.cursor.executemany(f"UPDATE {table} SET SSN = %s WHERE SSN = %s;", renamed)
+            self.db.commit()
+        except Exception as err:
+            self.db.rollback()
+            raise err
+        finally:
+            self.cursor.execute("SET FOREIGN_KEY_CHECKS = 1;")
+
+    def _migrate_user_profiles(self) -> None:
+        """
+        Adds the profile columns to the Users table, existing users are left active without an end
+        of service.
+
+        :return:
+            None
+        """
+        self._add_column_if_missing("Users", "Unit", "VARCHAR(250) AFTER Name")
+        self._add_column_if_missing("Users", "ServiceRank", "VARCHAR(64) AFTER Unit")
+        self._add_column_if_missing("Users", "Phone", "VARCHAR(32) AFTER ServiceRank")
+        self._add_column_if_missing("Users", "Email", "VARCHAR(250) AFTER Phone")
+        self._add_column_if_missing("Users", "Active", "BOOLEAN NOT NULL DEFAULT TRUE AFTER Email")
+        self._add_column_if_missing("Users", "EndOfService", "DATE AFTER Active")
+
+    def _migrate_serial_numbers(self) -> None:
+        """
+        Adds the serial number of the items and its key, existing items get no serial number.
+
+        :return:
+            None
+        """
+        self._add_column_if_missing("Items", "SerialNumber", "VARCHAR(64) AFTER Quality")
+        self._add_unique_key_if_missing("Items", "SerialPerProduct", "ProductID, SerialNumber")
+
+    def _migrate_item_codes(self) -> None:
+        """
+        Adds the short item codes and gives every existing item one.
+
+        :return:
+            None
+        """
+        self._add_column_if_missing("Items", "ItemCode", "VARCHAR(16) AFTER SerialNumber")
+        self._add_unique_key_if_missing("Items", "ItemCode", "ItemCode")
+        try:
+            self._assign_item_codes()
+            self.db.commit()
+        except Exception as err:
+            self.db.rollback()
+            raise err
+
+    def _migrate_item_history(self) -> None:
+        """
+        Adds retirement to the items and back-fills the history of the items made before it was kept.
+
+        Items without a Created event get one, dated on their first lending or on the day of the migration
+        if they were never lent out. Lendings need no back-fill, the history reads them from Lendings. The
+        in_stock_for_product function and the update_quality trigger are replaced, as they were created
+        before items could be retired and returns recorded condition changes.
+
+        :return:
+            None
+        """
+        self._add_column_if_missing("Items", "Retired", "BOOLEAN NOT NULL DEFAULT FALSE AFTER ItemCode")
+        try:
+            self.cursor.execute(
+                """
+                INSERT INTO ItemEvents (EventID, ItemID, EventDate, EventType, Details)
+                SELECT UUID_TO_BIN(UUID()), i.ItemID,
+                    COALESCE((SELECT MIN(l.BorrowingDate) FROM Lendings l WHERE l.ItemID = i.ItemID), CURDATE()),
+                    'Created', 'Created before the item history was kept'
+                FROM Items i
+                WHERE NOT EXISTS (SELECT 1 FROM ItemEvents e WHERE e.ItemID = i.ItemID AND e.EventType = 'Created');
+                """
+            )
+            self.db.commit()
+        except Exception as err:
+            self.db.rollback()
+            raise err
+
+        self.cursor.execute("DROP FUNCTION IF EXISTS in_stock_for_product;")
+        self.cursor.execute("DROP TRIGGER IF EXISTS update_quality;")
+        self._create_functions()
+        self._create_triggers()
+
+    @staticmethod
+    def canonical_ssn(ssn: str, today: datetime.date) -> str:
+        """
+        Brings an SSN stored as it was typed, e.g. YYMMDD-XXXX, into the canonical form
+        YYYYMMDD-XXXX.
+
+        The century of 10 digit numbers is taken like SSN::parse_at in ssn.rs does: the person is
+        younger than 100, or at least 100 with the + separator. Anything else, like pseudonyms and
+        numbers with a wrong control digit, is returned as it is.
+
+        :param ssn: The SSN as it is stored.
+        :param today: The day the age of the person is counted from.
+        :return:
+            The SSN in its canonical form.
+        """
+        digits, separator = ssn, ""
+        if len(ssn) in (11, 13) and ssn[-5] in "-+":
+            digits, separator = ssn[:-5] + ssn[-4:], ssn[-5]
+        if not digits.isdigit() or len(digits) not in (10, 12):
+            return ssn
+        if len(digits) == 12 and separator == "+":
+            return ssn
+        century, digits = digits[:-10], digits[-10:]
+
+        luhn = sum(
+            sum(divmod(int(digit) * (2 - index % 2), 10)) for index, digit in enumerate(digits[:9])
+        )
+        if (10 - luhn % 10) % 10 != int(digits[9]):
+            return ssn
+
+        yy, month, day = int(digits[:2]), int(digits[2:4]), int(digits[4:6])
+        if day > 60:
+            day -= 60
+        if century:
+            year = int(century) * 100 + yy
+        else:
+            year = today.year // 100 * 100 + yy
+            try:
+                if datetime.date(year, month, day) > today:
+                    year -= 100
+            except ValueError:
+                return ssn
+            if separator == "+":
+                year -= 100
+        try:
+            datetime.date(year, month, day)
+        except ValueError:
+            return ssn
+
+        return f"{year:04}{digits[2:6]}-{digits[6:]}"
+
     def _create_triggers(self):
         queries = [
             """
@@ -684,6 +2823,12 @@ class DBHandler:
                     UPDATE Items
                     SET Quality = (Quality - 0.10)
                     WHERE ItemID = NEW.ItemID;
+
+                    INSERT INTO ItemEvents (EventID, ItemID, EventDate, EventType, Details)
+                    SELECT UUID_TO_BIN(UUID()), ItemID, NEW.ReturnDate, 'Condition',
+                        CONCAT('Quality lowered to ', ROUND(Quality, 2), ' after use')
+                    FROM Items
+                    WHERE ItemID = NEW.ItemID;
                 END IF;
             END;
             """
@@ -711,6 +2856,8 @@ class DBHandler:
                     i.ProductID = product
                 AND
                     (i.Size = size OR (i.Size IS NULL AND size IS NULL))
+                AND
+                    NOT i.Retired
                 AND
                     l.ItemID IS NULL;
             
@@ -788,6 +2935,8 @@ class DBHandler:
         """
         Create all tables, triggers, functions, procedures and views
 
+        Tables that already exist are migrated to the latest schema, see _migrate.
+
         :return:
             None
         """
@@ -1,4 +1,5 @@
 from dataclasses import dataclass
+from datetime import date
 
 
 def run_cli(args: list[str] | None) -> None:
@@ -17,6 +18,8 @@ class Item:
     product_id: str
     size: str
     quality: float
+    serial_number: str | None
+    item_code: str | None = None
 
 
 
@@ -42,6 +45,7 @@ class DetailedLoan:
     size: str
     borrow_date: str
     return_date: str | None = None
+    item_code: str | None = None
 
 @dataclass
 class DetailedLoans:
@@ -51,6 +55,12 @@ class DetailedLoans:
 class User:
     ssn: str
     name: str
+    unit: str | None = None
+    rank: str | None = None
+    phone: str | None = None
+    email: str | None = None
+    active: bool = True
+    end_of_service: date | None = None
     
 @dataclass
 class Users:
@@ -78,17 +88,175 @@ class InStockSize:
 class InStockSizes:
     sizes: list[InStockSize]
 
+@dataclass
+class ItemHistoryEvent:
+    event_date: str
+    event: str
+    ssn: str | None = None
+    name: str | None = None
+    end_date: str | None = None
+    details: str | None = None
+
+@dataclass
+class ItemHistory:
+    events: list[ItemHistoryEvent]
+
+@dataclass
+class QueryOptions:
+    limit: int | None = None
+    offset: int | None = None
+    sort_by: str | None = None
+    descending: bool = False
+    product_type: str | None = None
+    size: str | None = None
+    min_quality: float | None = None
+    max_quality: float | None = None
+    from_date: date | None = None
+    to_date: date | None = None
+
+@dataclass
+class Qualifier:
+    field: str
+    op: str
+    value: str
+
+@dataclass
+class SearchQuery:
+    terms: list[str]
+    qualifiers: list[Qualifier]
+
+@dataclass
+class Product:
+    product_id: str
+    product_name: str
+    product_type: str
+
+@dataclass
+class Products:
+    products: list[Product]
+
+@dataclass
+class ProductMatch:
+    product_id: str
+    product_name: str
+    product_type: str
+    score: float
+
+@dataclass
+class ProductMatches:
+    matches: list[ProductMatch]
+
+@dataclass
+class Operator:
+    username: str
+    role: str
+
+@dataclass
+class Operators:
+    operators: list[Operator]
+
+@dataclass
+class InactiveHolding:
+    ssn: str
+    name: str
+    unit: str | None
+    end_of_service: date | None
+    item_id: str
+    product_name: str
+    size: str | None
+    borrow_date: date
+
+@dataclass
+class InactiveHoldings:
+    holdings: list[InactiveHolding]
+
+@dataclass
+class OpenLoan:
+    ssn: str
+    name: str
+    unit: str | None
+    item_id: str
+    product_name: str
+    product_type: str
+    size: str | None
+    borrow_date: date
+    due_date: date | None
+    item_code: str | None
+
+@dataclass
+class OpenLoans:
+    loans: list[OpenLoan]
+
+@dataclass
+class ItemRecord:
+    item_id: str
+    product_id: str
+    product_name: str
+    product_type: str
+    size: str | None
+    quality: float
+    available: bool
+    serial_number: str | None
+    item_code: str | None
+
+@dataclass
+class ItemRecords:
+    items: list[ItemRecord]
+
+@dataclass
+class AccountabilityEntry:
+    item_id: str
+    serial_number: str | None
+    product_name: str
+    product_type: str
+    status: str
+    ssn: str | None
+    name: str | None
+    unit: str | None
+    since: date | None
+    operator: str | None
+    witness: str | None
+
+@dataclass
+class AccountabilityReport:
+    entries: list[AccountabilityEntry]
+
+@dataclass
+class ConsumableStock:
+    product_id: str
+    product_name: str
+    product_type: str
+    quantity: int
+
+@dataclass
+class ConsumableStocks:
+    stock: list[ConsumableStock]
+
+@dataclass
+class Consumption:
+    ssn: str | None
+    name: str | None
+    unit: str | None
+    product_id: str
+    product_name: str
+    quantity: int
+    issues: int
+
+@dataclass
+class Consumptions:
+    consumption: list[Consumption]
+
 @dataclass
 class DBHandler:
-    def user_all_borrowed(self, ssn: str) -> list[DetailedLoan]:
+    def user_all_borrowed(self, ssn: str, options: QueryOptions) -> list[DetailedLoan]:
         ...
-    def get_items(self) -> list[DetailedItem]:
+    def get_items(self, options: QueryOptions) -> list[DetailedItem]:
         ...
 
-    def get_loans(self) -> list[DetailedLoan]:
+    def get_loans(self, options: QueryOptions) -> list[DetailedLoan]:
         ...
 
-    def get_users(self) -> list[User]:
+    def get_users(self, options: QueryOptions) -> list[User]:
         ...
     
     def get_rand_user(self) -> User:
@@ -120,6 +288,75 @@ class DBHandler:
     
     def create_all(self) -> None:
         ...
+
+    def get_item_history(self, item_id: str) -> ItemHistory:
+        ...
+
+    def get_inactive_holdings(self) -> InactiveHoldings:
+        ...
+
+    def get_open_loans(self) -> OpenLoans:
+        ...
+
+    def get_accountability(self, regulated_types: list[str], date: date) -> AccountabilityReport:
+        ...
+
+    def get_consumable_stock(self) -> list[ConsumableStock]:
+        ...
+
+    def receive_stock(self, product_id: str, quantity: int, date: date, note: str | None) -> None:
+        ...
+
+    def issue_stock(self, product_id: str, quantity: int, ssn: str, date: date, note: str | None) -> None:
+        ...
+
+    def update_item(self, item_id: str, product_id: str | None, size: str | None, quality: float | None,
+                    serial_number: str | None) -> None:
+        ...
+
+    def update_user(self, ssn: str, name: str | None, unit: str | None = None, rank: str | None = None,
+                    phone: str | None = None, email: str | None = None, active: bool | None = None,
+                    end_of_service: date | None = None) -> None:
+        ...
+
+    def update_product(self, product_id: str, product_name: str | None, product_type: str | None) -> None:
+        ...
+
+    def update_loan(self, lending_id: str, borrowing_date: date | None, return_date: date | None) -> None:
+        ...
+
+    def record_item_event(self, item_id: str, event_type: str, details: str, day: date | None) -> None:
+        ...
+
+    def retire_item(self, item_id: str, reason: str, day: date | None) -> None:
+        ...
+
+    def delete_user(self, ssn: str, force: bool) -> None:
+        ...
+
+    def erase_user(self, ssn: str) -> str:
+        ...
+
+    def delete_product(self, product_id: str, force: bool) -> None:
+        ...
+
+    def delete_loan(self, lending_id: str) -> None:
+        ...
+
+    def find_products(self, query: SearchQuery) -> list[Product]:
+        ...
+
+    def find_items(self, query: SearchQuery) -> list[ItemRecord]:
+        ...
+
+    def find_users(self, query: SearchQuery) -> list[User]:
+        ...
+
+    def find_loans(self, query: SearchQuery) -> list[DetailedLoan]:
+        ...
+
+    def get_item_id_by_code(self, item_code: str) -> str | None:
+        ...
     ...
 
 """
@@ -138,4 +375,26 @@ create_all
 get_users
 return_item
 user_all_borrowed
+get_item_history
+get_inactive_holdings
+get_open_loans
+get_accountability
+get_consumable_stock
+receive_stock
+issue_stock
+update_item
+update_user
+update_product
+update_loan
+record_item_event
+retire_item
+delete_user
+erase_user
+delete_product
+delete_loan
+find_products
+find_items
+find_users
+find_loans
+get_item_id_by_code
 """
\ No newline at end of file
@@ -0,0 +1,409 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local, NaiveDate};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "python-db")]
+use crate::python_db_handler::DBHandlerPy as DBHandler;
+use crate::users::ssn::SSN;
+
+/// Written to every archive so other gzip files are not mistaken for one
+pub const ARCHIVE_FORMAT: &str = "armory-atlas-archive";
+/// The version of the archive layout, bumped whenever a record changes
+pub const ARCHIVE_VERSION: u32 = 8;
+
+/// A backup of every record in the database
+///
+/// Archives are gzip compressed JSON. The records have their own types instead of reusing the
+/// ones of the handlers, so the layout only changes together with [`ARCHIVE_VERSION`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Archive {
+    pub format: String,
+    pub version: u32,
+    /// When the archive was made, in RFC 3339
+    pub created: String,
+    #[serde(flatten)]
+    pub data: ArchiveData,
+}
+
+/// The records of an archive, one list per table
+///
+/// Operator accounts are not archived, restoring must not grant the accounts of one depot access to
+/// another. The operators that versions 6 and 7 archived are ignored when reading.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ArchiveData {
+    pub products: Vec<ArchivedProduct>,
+    pub items: Vec<ArchivedItem>,
+    pub users: Vec<ArchivedUser>,
+    pub loans: Vec<ArchivedLoan>,
+    pub item_events: Vec<ArchivedItemEvent>,
+    /// Added in version 3
+    #[serde(default)]
+    pub sign_offs: Vec<ArchivedSignOff>,
+    /// Added in version 4
+    #[serde(default)]
+    pub consumable_stock: Vec<ArchivedConsumableStock>,
+    /// Added in version 4
+    #[serde(default)]
+    pub stock_transactions: Vec<ArchivedStockTransaction>,
+}
+
+impl ArchiveData {
+    /// Brings every SSN into the canonical form, using `today` for the century of 10 digit numbers
+    ///
+    /// Archives made before SSNs were normalized hold them as they were typed. Pseudonyms of
+    /// erased users and anything else that is not an SSN are kept as they are.
+    pub fn normalize_ssns(&mut self, today: NaiveDate) {
+        let normalize = |ssn: &mut String| {
+            if let Ok(normalized) = SSN::parse_at(ssn, today) {
+                *ssn = normalized.into();
+            }
+        };
+        self.users
+            .iter_mut()
+            .for_each(|user| normalize(&mut user.ssn));
+        self.loans
+            .iter_mut()
+            .for_each(|loan| normalize(&mut loan.ssn));
+        self.stock_transactions
+            .iter_mut()
+            .filter_map(|transaction| transaction.ssn.as_mut())
+            .for_each(normalize);
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ArchivedProduct {
+    pub product_id: String,
+    pub product_name: String,
+    pub product_type: String,
+}
+
+/// An item, the serial number was added in version 3, the item code in version 5 and retirement
+/// in version 7
+///
+/// Items restored without a code are given one.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ArchivedItem {
+    pub item_id: String,
+    pub product_id: String,
+    pub size: Option<String>,
+    pub quality: f32,
+    #[serde(default)]
+    pub serial_number: Option<String>,
+    #[serde(default)]
+    pub item_code: Option<String>,
+    #[serde(default)]
+    pub retired: bool,
+}
+
+/// A user, the profile fields were added in version 2 and are left empty when reading version 1
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ArchivedUser {
+    pub ssn: String,
+    pub name: String,
+    #[serde(default)]
+    pub unit: Option<String>,
+    #[serde(default)]
+    pub rank: Option<String>,
+    #[serde(default)]
+    pub phone: Option<String>,
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default = "active_by_default")]
+    pub active: bool,
+    #[serde(default)]
+    pub end_of_service: Option<NaiveDate>,
+}
+
+fn active_by_default() -> bool {
+    true
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ArchivedLoan {
+    pub lending_id: String,
+    pub ssn: String,
+    pub item_id: String,
+    pub borrow_date: NaiveDate,
+    pub return_date: Option<NaiveDate>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ArchivedItemEvent {
+    pub event_id: String,
+    pub item_id: String,
+    pub event_date: NaiveDate,
+    pub event_type: String,
+    pub details: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ArchivedSignOff {
+    pub sign_off_id: String,
+    pub lending_id: String,
+    pub action: String,
+    pub operator: String,
+    pub witness: String,
+    /// When the sign-off was made, as `YYYY-MM-DD HH:MM:SS`
+    pub signed_at: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ArchivedConsumableStock {
+    pub product_id: String,
+    pub quantity: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ArchivedStockTransaction {
+    pub transaction_id: String,
+    pub product_id: String,
+    pub kind: String,
+    pub quantity: u32,
+    pub ssn: Option<String>,
+    pub transaction_date: NaiveDate,
+    pub operator: Option<String>,
+    pub note: Option<String>,
+}
+
+impl Archive {
+    pub fn new(data: ArchiveData) -> Self {
+        Self {
+            format: ARCHIVE_FORMAT.to_string(),
+            version: ARCHIVE_VERSION,
+            created: Local::now().to_rfc3339(),
+            data,
+        }
+    }
+
+    /// Writes the archive to a gzip compressed file
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let file =
+            File::create(path).with_context(|| format!("Could not create {}", path.display()))?;
+        let mut encoder = GzEncoder::new(BufWriter::new(file), Compression::default());
+        serde_json::to_writer(&mut encoder, self)?;
+        encoder.finish()?;
+
+        Ok(())
+    }
+
+    /// Reads an archive, checking that it is one and that this version can read it
+    ///
+    /// The SSNs are normalized as of the day the archive was made.
+    pub fn read(path: &Path) -> Result<Self> {
+        let file =
+            File::open(path).with_context(|| format!("Could not open {}", path.display()))?;
+        let value: serde_json::Value =
+            serde_json::from_reader(GzDecoder::new(BufReader::new(file)))
+                .with_context(|| format!("{} is not a gzip compressed archive", path.display()))?;
+
+        if value["format"] != ARCHIVE_FORMAT {
+            bail!("{} is not an Armory Atlas archive", path.display());
+        }
+        match value["version"].as_u64() {
+            Some(version) if version <= ARCHIVE_VERSION as u64 => {}
+            Some(version) => bail!(
+                "The archive has version {version} but this version of Armory Atlas only reads up to version {ARCHIVE_VERSION}"
+            ),
+            None => bail!("The archive has no version"),
+        }
+
+        let mut archive: Self = serde_json::from_value(value)?;
+        let created = DateTime::parse_from_rfc3339(&archive.created)
+            .map(|created| created.date_naive())
+            .unwrap_or_else(|_| Local::now().date_naive());
+        archive.data.normalize_ssns(created);
+
+        Ok(archive)
+    }
+
+    /// A one line summary of what the archive contains
+    pub fn summary(&self) -> String {
+        format!(
+            "{} products, {} items, {} users, {} loans, {} item events, {} sign-offs, {} consumable stock levels and {} stock transactions",
+            self.data.products.len(),
+            self.data.items.len(),
+            self.data.users.len(),
+            self.data.loans.len(),
+            self.data.item_events.len(),
+            self.data.sign_offs.len(),
+            self.data.consumable_stock.len(),
+            self.data.stock_transactions.len()
+        )
+    }
+}
+
+/// Exports every record in the database to an archive
+///
+/// # Arguments
+///
+/// * `db_handler`: The database handler to read the records with.
+/// * `path`: The file to write the archive to.
+///
+#[cfg(feature = "python-db")]
+pub fn export(db_handler: &DBHandler, path: &Path) -> Result<()> {
+    let archive = Archive::new(db_handler.export_all()?);
+    archive.write(path)?;
+    println!("Exported {} to {}", archive.summary(), path.display());
+
+    Ok(())
+}
+
+/// Restores an archive into an empty database, keeping the IDs of every record
+///
+/// # Arguments
+///
+/// * `db_handler`: The database handler to restore the records with.
+/// * `path`: The archive to restore.
+///
+#[cfg(feature = "python-db")]
+pub fn restore(db_handler: &DBHandler, path: &Path) -> Result<()> {
+    let archive = Archive::read(path)?;
+    let summary = archive.summary();
+    db_handler.restore_all(archive.data)?;
+    println!("Restored {summary} from {}", path.display());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_data() -> ArchiveData {
+        ArchiveData {
+            products: vec![ArchivedProduct {
+                product_id: "M240003".to_string(),
+                product_name: "Sniper Rifle".to_string(),
+                product_type: "Weapon".to_string(),
+            }],
+            items: vec![ArchivedItem {
+                item_id: "0b9f6a8e-3c1d-4e0a-9f8b-2a6c1e4d5f70".to_string(),
+                product_id: "M240003".to_string(),
+                size: None,
+                quality: 0.75,
+                serial_number: Some("SR-001".to_string()),
+                item_code: Some("SR-0001-2".to_string()),
+                retired: false,
+            }],
+            users: vec![ArchivedUser {
+                ssn: "19811218-9876".to_string(),
+                name: "Liam Smith".to_string(),
+                unit: Some("1st Company".to_string()),
+                rank: None,
+                phone: None,
+                email: None,
+                active: true,
+                end_of_service: NaiveDate::from_ymd_opt(2026, 6, 30),
+            }],
+            loans: vec![ArchivedLoan {
+                lending_id: "5d1c0a4e-8f2b-4c3d-9e7a-1b2c3d4e5f60".to_string(),
+                ssn: "19811218-9876".to_string(),
+                item_id: "0b9f6a8e-3c1d-4e0a-9f8b-2a6c1e4d5f70".to_string(),
+                borrow_date: NaiveDate::from_ymd_opt(2024, 1, 10).unwrap(),
+                return_date: None,
+            }],
+            item_events: vec![],
+            sign_offs: vec![ArchivedSignOff {
+                sign_off_id: "7e3a1c2b-4d5e-4f60-8a9b-0c1d2e3f4a5b".to_string(),
+                lending_id: "5d1c0a4e-8f2b-4c3d-9e7a-1b2c3d4e5f60".to_string(),
+                action: "Checkout".to_string(),
+                operator: "anna".to_string(),
+                witness: "erik".to_string(),
+                signed_at: "2024-01-10 08:30:00".to_string(),
+            }],
+            consumable_stock: vec![ArchivedConsumableStock {
+                product_id: "M240006-3708463".to_string(),
+                quantity: 38,
+            }],
+            stock_transactions: vec![ArchivedStockTransaction {
+                transaction_id: "2f6b8d0c-1a3e-4c5b-9d7f-8e0a1b2c3d4e".to_string(),
+                product_id: "M240006-3708463".to_string(),
+                kind: "Issue".to_string(),
+                quantity: 2,
+                ssn: Some("19811218-9876".to_string()),
+                transaction_date: NaiveDate::from_ymd_opt(2024, 1, 10).unwrap(),
+                operator: Some("anna".to_string()),
+                note: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let path = std::env::temp_dir().join("armory_atlas_test_round_trip.json.gz");
+        let archive = Archive::new(test_data());
+
+        archive.write(&path).unwrap();
+        let read = Archive::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(read, archive);
+    }
+
+    #[test]
+    fn test_version_1_users_are_active() {
+        let user: ArchivedUser =
+            serde_json::from_str(r#"{"ssn": "811218-9876", "name": "Liam Smith"}"#).unwrap();
+
+        assert!(user.active);
+        assert_eq!(user.unit, None);
+    }
+
+    #[test]
+    fn test_version_1_ssns_are_normalized() {
+        let mut data = test_data();
+        data.users[0].ssn = "811218-9876".to_string();
+        data.loans[0].ssn = "8112189876".to_string();
+        data.stock_transactions[0].ssn = Some("ANON-3f9a0c1e".to_string());
+
+        data.normalize_ssns(NaiveDate::from_ymd_opt(2024, 1, 10).unwrap());
+
+        assert_eq!(data.users[0].ssn, "19811218-9876");
+        assert_eq!(data.loans[0].ssn, "19811218-9876");
+        assert_eq!(
+            data.stock_transactions[0].ssn.as_deref(),
+            Some("ANON-3f9a0c1e")
+        );
+    }
+
+    #[test]
+    fn test_summary() {
+        let summary = Archive::new(test_data()).summary();
+
+        assert!(summary.contains("1 consumable stock levels"));
+        assert!(summary.ends_with("1 stock transactions"));
+    }
+
+    #[test]
+    fn test_version_7_operators_are_ignored() {
+        let mut value = serde_json::to_value(test_data()).unwrap();
+        value["operators"] = serde_json::json!([
+            {"username": "anna", "role": "admin", "password_hash": "00ff$abcd"}
+        ]);
+
+        let data: ArchiveData = serde_json::from_value(value).unwrap();
+
+        assert_eq!(data, test_data());
+    }
+
+    #[test]
+    fn test_newer_version_is_rejected() {
+        let path = std::env::temp_dir().join("armory_atlas_test_newer_version.json.gz");
+        let mut archive = Archive::new(test_data());
+        archive.version = ARCHIVE_VERSION + 1;
+
+        archive.write(&path).unwrap();
+        let read = Archive::read(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(read.is_err());
+    }
+}
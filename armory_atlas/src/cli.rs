@@ -1,6 +1,17 @@
 use crate::config::AppConfig;
+use crate::consumables::ConsumptionGroup;
+use crate::documents::DocumentFormat;
+use crate::import::{parse_mapping, ImportFormat, ImportKind};
+use crate::labels::{parse_label_size, LabelFormat, LabelSize};
+use crate::leandings::receipt::ReceiptFormat;
+use crate::leandings::simulation::{parse_popularity, DurationDistribution};
+use crate::operators::Role;
+use crate::output::OutputFormat;
+use crate::search::SearchScope;
+use crate::users::ssn::parse_ssn;
 use chrono::NaiveDate;
 use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
 
 #[derive(Parser)]
 #[command(
@@ -27,6 +38,20 @@ pub struct Command {
         help = "The database to use when connecting to the database. Note that this will override whats written in the config file"
     )]
     pub database: Option<String>,
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        default_value_t = OutputFormat::Table,
+        help = "The format to print the results of the get and report subcommands in"
+    )]
+    pub format: OutputFormat,
+    #[arg(
+        long,
+        global = true,
+        help = "Show SSNs in full instead of masked as YYMMDD-****, requires the quartermaster or admin role"
+    )]
+    pub show_ssn: bool,
     #[command(subcommand)]
     pub subcommands: CommandType,
 }
@@ -49,6 +74,269 @@ pub enum CommandType {
     Return(ReturnArgs),
     #[command(about = "Subcommands for inserting data into the database")]
     Insert(InsertArgs),
+    #[command(about = "Subcommands for correcting data already in the database")]
+    Update(UpdateArgs),
+    #[command(about = "Subcommands for deleting data from the database")]
+    Delete(DeleteArgs),
+    #[command(
+        about = "Subcommands for recording what happens to an item",
+        long_about = "Subcommands for recording what happens to an item. Everything recorded is shown by `get item-history`. Items are retired instead of deleted, so their history is kept"
+    )]
+    Item(ItemArgs),
+    #[command(about = "Subcommands for handling the personal data of users")]
+    Users(UserDataArgs),
+    #[command(
+        about = "Log in as an operator",
+        long_about = "Log in as an operator. The operator and a login token are saved in the config file and every following command is checked against the operator's role. Logging in again, here or elsewhere, ends the earlier login"
+    )]
+    Login(LoginArgs),
+    #[command(about = "Log out the current operator")]
+    Logout,
+    #[command(about = "Subcommands for managing the operator accounts, only for admins")]
+    Operators(OperatorsArgs),
+    #[command(
+        about = "Search products, items, users or loans",
+        long_about = "Search products, items, users or loans. Words are matched against names, qualifiers narrow the search, e.g: type:Weapon size:M name:\"combat knife\" available:yes quality>0.5. Items can be looked up by serial number with serial:<SERIAL> and by item code with code:<CODE>"
+    )]
+    Search(SearchArgs),
+    #[command(
+        about = "Import products, items, users or loans from a CSV or JSON file",
+        long_about = "Import products, items, users or loans from a CSV or JSON file. Every row is validated before anything is written and the whole file is imported in a single transaction, so either every row ends up in the database or none of them do"
+    )]
+    Import(ImportArgs),
+    #[command(
+        about = "Subcommands for consumables, which are issued from stock instead of lent out",
+        long_about = "Subcommands for consumables, which are issued from stock instead of lent out. Products are consumables when they have \"Consumable\": true in the product catalog"
+    )]
+    Stock(StockArgs),
+    #[command(
+        about = "Subcommands for statistics about how the inventory is used",
+        long_about = "Subcommands for statistics about how the inventory is used. Every report covers the period given by --from and --to, which defaults to everything from the first loan up to today"
+    )]
+    Report(ReportArgs),
+    #[command(
+        about = "Subcommands for writing printable summaries as HTML or Markdown documents",
+        long_about = "Subcommands for writing printable summaries as HTML or Markdown documents. The HTML documents are self-contained, so they can be mailed or printed as they are"
+    )]
+    Document(DocumentArgs),
+    #[command(
+        about = "Print labels with a barcode and a QR code for items, as SVG or PNG",
+        long_about = "Print labels with a barcode and a QR code for items, as SVG or PNG. The barcode holds the item ID and the QR code the item and product ID, so items can be scanned instead of typed. Items are chosen with the same query as `search items`, every item gets a label if no query is given"
+    )]
+    Labels(LabelsArgs),
+    #[command(
+        about = "Check out and return items with a barcode scanner",
+        long_about = "Check out and return items with a barcode scanner. Scan a user's SSN and then their items, and press Enter to lend the items out. Items scanned without a user are returned. Type CANCEL to drop the scanned items and QUIT to leave"
+    )]
+    Scan(ScanArgs),
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ScanArgs {
+    #[arg(
+        short,
+        long,
+        help = "The second operator signing off regulated items, their password is asked for every checkout or return with regulated items"
+    )]
+    pub witness: Option<String>,
+    #[arg(long, help = "Don't beep when a scan is refused")]
+    pub silent: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct LabelsArgs {
+    #[arg(help = "The file to write the labels to, replaced if it exists")]
+    pub file: PathBuf,
+    #[arg(help = "The items to print labels for, words and qualifiers like type:Weapon or size:M")]
+    pub query: Vec<String>,
+    #[arg(short, long, help = "Only print labels for items of this product")]
+    pub product: Option<String>,
+    #[arg(
+        short,
+        long,
+        default_value = "100x50",
+        value_parser = parse_label_size,
+        help = "The size of a label in millimetres, WIDTHxHEIGHT"
+    )]
+    pub size: LabelSize,
+    #[arg(
+        short,
+        long,
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u16).range(1..),
+        help = "The number of labels side by side, for printers with several labels across the roll"
+    )]
+    pub columns: u16,
+    #[arg(
+        long,
+        default_value_t = 300,
+        value_parser = clap::value_parser!(u32).range(72..=2400),
+        help = "The resolution of the label printer in dots per inch, barcode bars are made a whole number of dots wide"
+    )]
+    pub dpi: u32,
+    #[arg(
+        long,
+        value_enum,
+        help = "The format of the labels, guessed from the file extension if not given"
+    )]
+    pub label_format: Option<LabelFormat>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct DocumentArgs {
+    #[command(subcommand)]
+    pub subcommands: DocumentSubCommands,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum DocumentSubCommands {
+    #[command(
+        about = "The available count of every product and size, what is below its minimum stock level and the consumables on hand"
+    )]
+    StockStatus(StockStatusDocumentArgs),
+    #[command(about = "Every item that is lent out, one section per unit")]
+    OpenLoans(DocumentFileArgs),
+    #[command(about = "Every item that should have been returned by now")]
+    Overdue(DocumentFileArgs),
+    #[command(
+        about = "A receipt listing the items a user holds and has returned, with lines to sign"
+    )]
+    Receipt(ReceiptDocumentArgs),
+    #[command(
+        about = "A hand-over receipt for the borrower to sign, as plain text or PDF",
+        long_about = "A hand-over receipt for the borrower to sign, as plain text or PDF. Lists the items, their sizes and IDs, when they were borrowed and when they are due back, with lines for the borrower and operator to sign"
+    )]
+    HandOver(HandOverDocumentArgs),
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct HandOverDocumentArgs {
+    #[arg(help = "The Social Security Number of the borrower", value_parser = parse_ssn)]
+    pub ssn: String,
+    #[arg(help = "The file to write the receipt to, replaced if it exists")]
+    pub file: PathBuf,
+    #[arg(
+        short,
+        long,
+        help = "The day the items were borrowed, today if not given. Ignored when items are given with --item"
+    )]
+    pub date: Option<NaiveDate>,
+    #[arg(
+        short,
+        long = "item",
+        help = "The ID or item code of an item to put on the receipt, can be given more than once"
+    )]
+    pub items: Vec<String>,
+    #[arg(
+        long,
+        value_enum,
+        help = "The format of the receipt, guessed from the file extension if not given"
+    )]
+    pub receipt_format: Option<ReceiptFormat>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct DocumentFileArgs {
+    #[arg(help = "The file to write the document to, replaced if it exists")]
+    pub file: PathBuf,
+    #[arg(
+        long,
+        value_enum,
+        help = "The format of the document, guessed from the file extension if not given"
+    )]
+    pub document_format: Option<DocumentFormat>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct StockStatusDocumentArgs {
+    #[arg(
+        short,
+        long,
+        default_value_t = 30,
+        help = "The number of days the recent demand is counted over"
+    )]
+    pub days: u64,
+    #[command(flatten)]
+    pub file: DocumentFileArgs,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ReceiptDocumentArgs {
+    #[arg(help = "The Social Security Number of the user", value_parser = parse_ssn)]
+    pub ssn: String,
+    #[command(flatten)]
+    pub file: DocumentFileArgs,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ReportArgs {
+    #[arg(
+        long,
+        global = true,
+        help = "The first day of the period to report on, the day of the first loan if not given"
+    )]
+    pub from: Option<NaiveDate>,
+    #[arg(
+        long,
+        global = true,
+        help = "The last day of the period to report on, today if not given"
+    )]
+    pub to: Option<NaiveDate>,
+    #[command(subcommand)]
+    pub subcommands: ReportSubCommands,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum ReportSubCommands {
+    #[command(about = "The share of the period the items of every product were lent out")]
+    Utilisation,
+    #[command(about = "The number of loans of every product and how many days they lasted")]
+    LoanDuration,
+    #[command(about = "The sizes that were borrowed the most, the busiest first")]
+    BusiestSizes,
+    #[command(about = "The items that were not borrowed during the period")]
+    NeverBorrowed,
+    #[command(about = "How the qualities of the items of every product are spread")]
+    Quality,
+    #[command(about = "The number of loans made and returned every month")]
+    LoansPerMonth,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ImportArgs {
+    #[arg(value_enum, help = "What the file contains")]
+    pub kind: ImportKind,
+    #[arg(help = "The CSV or JSON file to import")]
+    pub file: PathBuf,
+    #[arg(
+        long,
+        value_enum,
+        help = "The format of the file, guessed from the file extension if not given"
+    )]
+    pub input_format: Option<ImportFormat>,
+    #[arg(
+        short,
+        long = "map",
+        value_parser = parse_mapping,
+        help = "Map a column of the file to a field, e.g. --map Namn=name. Leave the field empty to skip the column"
+    )]
+    pub mappings: Vec<(String, String)>,
+    #[arg(
+        long,
+        help = "Only validate the file and report the errors, nothing is written to the database"
+    )]
+    pub dry_run: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct SearchArgs {
+    #[arg(value_enum, help = "What to search for")]
+    pub scope: SearchScope,
+    #[arg(
+        required = true,
+        help = "The search query, words and qualifiers like type:Weapon or quality>0.5"
+    )]
+    pub query: Vec<String>,
 }
 
 #[derive(Args, Debug, Clone)]
@@ -73,21 +361,44 @@ pub struct InsertItemArgs {
     pub size: String,
     #[arg(help = "The quality of the item to insert")]
     pub quality: f32,
+    #[arg(
+        long,
+        help = "The serial number of the item, unique among the items of its product"
+    )]
+    pub serial: Option<String>,
 }
 
 #[derive(Args, Debug, Clone)]
 pub struct InsertUserArgs {
-    #[arg(help = "The Social Security Number of the user to insert")]
+    #[arg(help = "The Social Security Number of the user to insert", value_parser = parse_ssn)]
     pub ssn: String,
     #[arg(help = "The name of the user to insert")]
     pub name: String,
+    #[arg(long, help = "The unit or company the user belongs to")]
+    pub unit: Option<String>,
+    #[arg(long, help = "The rank of the user")]
+    pub rank: Option<String>,
+    #[arg(long, help = "The phone number of the user")]
+    pub phone: Option<String>,
+    #[arg(long, help = "The email address of the user")]
+    pub email: Option<String>,
+    #[arg(
+        long,
+        help = "The last day of the user's service, the user cannot borrow anything after it"
+    )]
+    pub end_of_service: Option<NaiveDate>,
+    #[arg(
+        long,
+        help = "Insert the user as inactive, inactive users cannot borrow anything"
+    )]
+    pub inactive: bool,
 }
 
 #[derive(Args, Debug, Clone)]
 pub struct InsertLoanArgs {
-    #[arg(help = "The Social Security Number of the user that is making the loan")]
+    #[arg(help = "The Social Security Number of the user that is making the loan", value_parser = parse_ssn)]
     pub ssn: String,
-    #[arg(help = "The ID of the item that is being borrowed")]
+    #[arg(help = "The ID or item code of the item that is being borrowed")]
     pub item_id: String,
     #[arg(help = "The date that the loan was made")]
     pub borrow_date: NaiveDate,
@@ -97,6 +408,12 @@ pub struct InsertLoanArgs {
         long_help = "The date that the loan was returned. This should only be used if the loan was made before this system was implemented!"
     )]
     pub return_date: Option<NaiveDate>,
+    #[arg(
+        short,
+        long,
+        help = "The second operator signing off the checkout, required for regulated items"
+    )]
+    pub witness: Option<String>,
 }
 
 #[derive(Args, Debug, Clone)]
@@ -111,6 +428,295 @@ pub struct InsertProductArgs {
     pub product_type: String,
 }
 
+#[derive(Args, Debug, Clone)]
+pub struct UpdateArgs {
+    #[command(subcommand)]
+    pub subcommands: UpdateSubCommands,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum UpdateSubCommands {
+    #[command(about = "Update the product, size, quality or serial number of an item")]
+    Item(UpdateItemArgs),
+    #[command(about = "Update the profile of a user")]
+    User(UpdateUserArgs),
+    #[command(about = "Update the borrowing or return date of a loan")]
+    Loan(UpdateLoanArgs),
+    #[command(about = "Update the name or type of a product")]
+    Product(UpdateProductArgs),
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct UpdateItemArgs {
+    #[arg(help = "The ID or item code of the item to update")]
+    pub item_id: String,
+    #[arg(short, long, help = "The new Product ID of the item")]
+    pub product_id: Option<String>,
+    #[arg(short, long, help = "The new size of the item")]
+    pub size: Option<String>,
+    #[arg(short, long, help = "The new quality of the item")]
+    pub quality: Option<f32>,
+    #[arg(long, help = "The new serial number of the item")]
+    pub serial: Option<String>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct UpdateUserArgs {
+    #[arg(help = "The Social Security Number of the user to update", value_parser = parse_ssn)]
+    pub ssn: String,
+    #[arg(short, long, help = "The new name of the user")]
+    pub name: Option<String>,
+    #[arg(long, help = "The new unit or company of the user")]
+    pub unit: Option<String>,
+    #[arg(long, help = "The new rank of the user")]
+    pub rank: Option<String>,
+    #[arg(long, help = "The new phone number of the user")]
+    pub phone: Option<String>,
+    #[arg(long, help = "The new email address of the user")]
+    pub email: Option<String>,
+    #[arg(
+        long,
+        help = "Whether the user is active, inactive users cannot borrow anything"
+    )]
+    pub active: Option<bool>,
+    #[arg(long, help = "The new last day of the user's service")]
+    pub end_of_service: Option<NaiveDate>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct UpdateLoanArgs {
+    #[arg(help = "The ID of the loan to update")]
+    pub lending_id: String,
+    #[arg(short, long, help = "The new date that the loan was made")]
+    pub borrow_date: Option<NaiveDate>,
+    #[arg(short, long, help = "The new date that the loan was returned")]
+    pub return_date: Option<NaiveDate>,
+    #[arg(
+        short,
+        long,
+        help = "The second operator signing off the return, required to close a loan of regulated items"
+    )]
+    pub witness: Option<String>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct UpdateProductArgs {
+    #[arg(help = "The ID of the product to update")]
+    pub product_id: String,
+    #[arg(short = 'n', long, help = "The new name of the product")]
+    pub product_name: Option<String>,
+    #[arg(short = 't', long, help = "The new type of the product")]
+    pub product_type: Option<String>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct DeleteArgs {
+    #[command(subcommand)]
+    pub subcommands: DeleteSubCommands,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum DeleteSubCommands {
+    #[command(about = "Delete a user together with the users loans")]
+    User(DeleteUserArgs),
+    #[command(about = "Delete a single loan")]
+    Loan(DeleteLoanArgs),
+    #[command(
+        about = "Delete a product, with --force all of its items are deleted as well",
+        long_about = "Delete a product, with --force all of its items are deleted as well. Items that have been lent out or have other history than their creation are never deleted, retire them with `item retire` instead"
+    )]
+    Product(DeleteProductArgs),
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct DeleteUserArgs {
+    #[arg(help = "The Social Security Number of the user to delete", value_parser = parse_ssn)]
+    pub ssn: String,
+    #[arg(short, long, help = "Delete the user even if the user has open loans")]
+    pub force: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct DeleteLoanArgs {
+    #[arg(help = "The ID of the loan to delete")]
+    pub lending_id: String,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct DeleteProductArgs {
+    #[arg(help = "The ID of the product to delete")]
+    pub product_id: String,
+    #[arg(short, long, help = "Delete the product even if it has items")]
+    pub force: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct UserDataArgs {
+    #[command(subcommand)]
+    pub subcommands: UserDataSubCommands,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum UserDataSubCommands {
+    #[command(
+        about = "Erase the personal data of a user",
+        long_about = "Erase the personal data of a user. The SSN and name are replaced by a random pseudonym that cannot be traced back to the person, while the user's loans are kept so the loan statistics stay intact. Users with open loans cannot be erased"
+    )]
+    Erase(EraseUserArgs),
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct EraseUserArgs {
+    #[arg(help = "The Social Security Number of the user to erase", value_parser = parse_ssn)]
+    pub ssn: String,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct StockArgs {
+    #[command(subcommand)]
+    pub subcommands: StockSubCommands,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum StockSubCommands {
+    #[command(about = "Issue units of a consumable to a user, they are used up and not returned")]
+    Issue(IssueStockArgs),
+    #[command(about = "Receive units of a consumable into stock")]
+    Receive(ReceiveStockArgs),
+    #[command(about = "List the quantity on hand of every consumable")]
+    List,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct IssueStockArgs {
+    #[arg(help = "The Product ID of the consumable to issue")]
+    pub product_id: String,
+    #[arg(help = "The number of units to issue")]
+    pub quantity: u32,
+    #[arg(help = "The Social Security Number of the user the units are issued to", value_parser = parse_ssn)]
+    pub ssn: String,
+    #[arg(
+        short,
+        long,
+        help = "The date the units were issued, today if not given"
+    )]
+    pub date: Option<NaiveDate>,
+    #[arg(short, long, help = "A note to keep with the transaction")]
+    pub note: Option<String>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ReceiveStockArgs {
+    #[arg(help = "The Product ID of the consumable to receive")]
+    pub product_id: String,
+    #[arg(help = "The number of units received")]
+    pub quantity: u32,
+    #[arg(
+        short,
+        long,
+        help = "The date the units were received, today if not given"
+    )]
+    pub date: Option<NaiveDate>,
+    #[arg(
+        short,
+        long,
+        help = "A note to keep with the transaction, e.g. the delivery note number"
+    )]
+    pub note: Option<String>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ItemArgs {
+    #[command(subcommand)]
+    pub subcommands: ItemSubCommands,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum ItemSubCommands {
+    #[command(about = "Record maintenance of an item, e.g. a repair or an inspection")]
+    Maintain(MaintainItemArgs),
+    #[command(about = "Record that an item was moved to another unit or storage")]
+    Transfer(TransferItemArgs),
+    #[command(
+        about = "Retire an item that is worn out, lost or scrapped",
+        long_about = "Retire an item that is worn out, lost or scrapped. The item is kept with its history but can no longer be lent out and is not counted in the inventory. Lent out items have to be returned first"
+    )]
+    Retire(RetireItemArgs),
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct MaintainItemArgs {
+    #[arg(help = "The ID or item code of the item")]
+    pub item_id: String,
+    #[arg(help = "What was done to the item")]
+    pub details: String,
+    #[arg(short, long, help = "The date of the maintenance, today if not given")]
+    pub date: Option<NaiveDate>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct TransferItemArgs {
+    #[arg(help = "The ID or item code of the item")]
+    pub item_id: String,
+    #[arg(help = "Where the item was moved to")]
+    pub destination: String,
+    #[arg(short, long, help = "The date of the transfer, today if not given")]
+    pub date: Option<NaiveDate>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct RetireItemArgs {
+    #[arg(help = "The ID or item code of the item to retire")]
+    pub item_id: String,
+    #[arg(help = "Why the item is retired")]
+    pub reason: String,
+    #[arg(
+        short,
+        long,
+        help = "The date the item was retired, today if not given"
+    )]
+    pub date: Option<NaiveDate>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct LoginArgs {
+    #[arg(help = "The username of the operator to log in as")]
+    pub username: String,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct OperatorsArgs {
+    #[command(subcommand)]
+    pub subcommands: OperatorSubCommands,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum OperatorSubCommands {
+    #[command(
+        about = "Add an operator account",
+        long_about = "Add an operator account, the password is asked for. While no accounts exist anyone may run every command, so the first account should be an admin"
+    )]
+    Add(AddOperatorArgs),
+    #[command(about = "Remove an operator account")]
+    Remove(RemoveOperatorArgs),
+    #[command(about = "List the operator accounts and their roles")]
+    List,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct AddOperatorArgs {
+    #[arg(help = "The username of the operator")]
+    pub username: String,
+    #[arg(value_enum, help = "What the operator may do")]
+    pub role: Role,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct RemoveOperatorArgs {
+    #[arg(help = "The username of the operator to remove")]
+    pub username: String,
+}
+
 #[derive(Args, Debug, Clone)]
 pub struct ReturnArgs {
     #[command(subcommand)]
@@ -124,8 +730,14 @@ pub enum ReturnSubCommands {
 
 #[derive(Args, Debug, Clone)]
 pub struct ReturnItemArgs {
-    #[arg(help = "The ID of the item to return")]
+    #[arg(help = "The ID or item code of the item to return")]
     pub item_id: String,
+    #[arg(
+        short,
+        long,
+        help = "The second operator signing off the return, required for regulated items"
+    )]
+    pub witness: Option<String>,
 }
 
 #[derive(Args, Debug, Clone)]
@@ -146,30 +758,138 @@ pub enum GetSubCommands {
     Users(GetUsersArgs),
     #[command(about = "Get the number of loans for each user, both current and total")]
     NumberOfLoans(NumberOfLoansArgs),
+    #[command(about = "Get the whole lifecycle of an item in chronological order")]
+    ItemHistory(ItemHistoryArgs),
+    #[command(
+        about = "Get the items still held by users that are inactive or whose service has ended"
+    )]
+    InactiveHolders,
+    #[command(
+        about = "Get where every item of a regulated product type was at the end of a day, and who signed it out"
+    )]
+    Accountability(AccountabilityArgs),
+    #[command(about = "Get how much of each consumable was issued per user or unit")]
+    Consumption(ConsumptionArgs),
+    #[command(
+        about = "Get every item that is lent out, with the day it is due back",
+        long_about = "Get every item that is lent out, with the day it is due back. The due date comes from the loan periods in the checkout rules, loans of product types without a loan period have none"
+    )]
+    OpenLoans(OpenLoansArgs),
+    #[command(
+        about = "Get every product and size that is below its minimum stock level, with a suggested reorder quantity",
+        long_about = "Get every product and size in the catalog that is below its minimum stock level (\"MinStock\" in the product catalog). The suggested reorder quantity brings the stock up to the minimum plus the number of loans or issued units during the recent period"
+    )]
+    LowStock(LowStockArgs),
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct OpenLoansArgs {
+    #[arg(long, help = "Only get the loans that are overdue")]
+    pub overdue: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct LowStockArgs {
+    #[arg(
+        short,
+        long,
+        default_value_t = 30,
+        help = "The number of days the recent demand is counted over"
+    )]
+    pub days: u64,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ConsumptionArgs {
+    #[arg(short, long, value_enum, default_value_t = ConsumptionGroup::User, help = "What to sum the issued units per")]
+    pub by: ConsumptionGroup,
+    #[arg(long, help = "Only count issues on or after this date")]
+    pub since: Option<NaiveDate>,
+    #[arg(long, help = "Only count issues on or before this date")]
+    pub until: Option<NaiveDate>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct AccountabilityArgs {
+    #[arg(short, long, help = "The day to report on, today if not given")]
+    pub date: Option<NaiveDate>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ItemHistoryArgs {
+    #[arg(help = "The ID or item code of the item to get the history for")]
+    pub item_id: String,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct PageArgs {
+    #[arg(long, help = "Skip this many rows before returning results")]
+    pub offset: Option<usize>,
+    #[arg(
+        long,
+        help = "The field to sort by, uses the same names as the JSON output"
+    )]
+    pub sort: Option<String>,
+    #[arg(long, help = "Sort in descending order")]
+    pub desc: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct FilterArgs {
+    #[arg(long = "type", help = "Only include products of this type")]
+    pub product_type: Option<String>,
+    #[arg(long, help = "Only include items of this size")]
+    pub size: Option<String>,
+    #[arg(long, help = "Only include items with at least this quality")]
+    pub min_quality: Option<f32>,
+    #[arg(long, help = "Only include items with at most this quality")]
+    pub max_quality: Option<f32>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct DateRangeArgs {
+    #[arg(long, help = "Only include loans made on or after this date")]
+    pub from: Option<NaiveDate>,
+    #[arg(long, help = "Only include loans made on or before this date")]
+    pub to: Option<NaiveDate>,
 }
 
 #[derive(Args, Debug, Clone)]
 pub struct NumberOfLoansArgs {
     #[arg(long, short)]
     pub limit: Option<usize>,
+    #[command(flatten)]
+    pub page: PageArgs,
 }
 
 #[derive(Args, Debug, Clone)]
 pub struct GetUsersArgs {
     pub limit: Option<usize>,
+    #[command(flatten)]
+    pub page: PageArgs,
 }
 
 #[derive(Args, Debug, Clone)]
 pub struct GetLoansArgs {
-    #[arg(help = "If provided will only return loans for the given ssn")]
+    #[arg(help = "If provided will only return loans for the given ssn", value_parser = parse_ssn)]
     pub ssn: Option<String>,
     #[arg(long, short)]
     pub limit: Option<usize>,
+    #[command(flatten)]
+    pub page: PageArgs,
+    #[command(flatten)]
+    pub filter: FilterArgs,
+    #[command(flatten)]
+    pub dates: DateRangeArgs,
 }
 
 #[derive(Args, Debug, Clone)]
 pub struct GetItemsArgs {
     pub limit: Option<usize>,
+    #[command(flatten)]
+    pub page: PageArgs,
+    #[command(flatten)]
+    pub filter: FilterArgs,
 }
 
 #[derive(Args, Debug, Clone)]
@@ -186,6 +906,28 @@ pub struct ManageArgs {
     pub drop_all: bool,
     #[arg(short, long)]
     pub create_all: bool,
+    #[command(subcommand)]
+    pub subcommands: Option<ManageSubCommands>,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum ManageSubCommands {
+    #[command(
+        about = "Export every product, item, user and loan to a compressed archive",
+        long_about = "Export every product, item, user and loan to a compressed archive. The IDs are kept so the archive can be restored into another database. Operator accounts are not exported"
+    )]
+    Export(ArchiveArgs),
+    #[command(
+        about = "Restore an archive made with manage export into an empty database",
+        long_about = "Restore an archive made with manage export into an empty database. Everything is restored in a single transaction, so either the whole archive ends up in the database or nothing does"
+    )]
+    Import(ArchiveArgs),
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ArchiveArgs {
+    #[arg(help = "The archive file, e.g. depot.json.gz")]
+    pub file: PathBuf,
 }
 
 #[derive(Subcommand, Debug, Clone)]
@@ -210,11 +952,55 @@ pub struct UsersArgs {
 pub struct LoansArgs {
     #[arg(default_value = "10", help = "Number of loans to generate")]
     pub num_loans: usize,
+    #[command(flatten)]
+    pub simulation: SimulationArgs,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct SimulationArgs {
+    #[arg(
+        long,
+        long_help = "Simulate a loan history day by day instead of making random loans. Items are issued and returned over the simulated days and never lent to two users at once, loans that are not due by the last day are left open"
+    )]
+    pub simulate: bool,
+    #[arg(
+        long,
+        default_value_t = 365,
+        help = "The number of days to simulate, with --simulate"
+    )]
+    pub days: u64,
+    #[arg(
+        long,
+        help = "The first day to simulate, with --simulate. Defaults to the day that makes the simulation end on --today"
+    )]
+    pub from: Option<NaiveDate>,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = DurationDistribution::Exponential,
+        help = "How the durations of the loans are distributed, with --simulate"
+    )]
+    pub duration: DurationDistribution,
+    #[arg(
+        long,
+        default_value_t = 14.0,
+        help = "The mean duration of a loan in days, with --simulate"
+    )]
+    pub mean_duration: f64,
+    #[arg(
+        long,
+        value_parser = parse_popularity,
+        help = "How popular a product is compared to the others, e.g. --popularity M240003=5. Products default to 1, with --simulate"
+    )]
+    pub popularity: Vec<(String, f64)>,
 }
 
 #[derive(Args, Debug, Clone)]
 pub struct ItemsArgs {
-    #[arg(default_value = "10", help = "Number of items to generate")]
+    #[arg(
+        default_value = "10",
+        help = "Number of items to generate for each product that has no stock target in products.json"
+    )]
     pub num_items: usize,
 }
 
@@ -230,4 +1016,18 @@ pub struct GenerateArgs {
         help = "Number of rows to generate for each catagory if no subcommand is provided"
     )]
     pub num_to_generate: Option<usize>,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Seed for the random generator, the same seed and database always give the same data"
+    )]
+    pub seed: Option<u64>,
+
+    #[arg(
+        long,
+        global = true,
+        help = "The day the data is generated as of, it decides who is still serving and when a simulation ends. Defaults to 2024-06-01 with --seed and to today otherwise"
+    )]
+    pub today: Option<NaiveDate>,
 }
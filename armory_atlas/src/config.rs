@@ -81,6 +81,38 @@ pub fn write_config(app_config: &AppConfig, password: &str) -> anyhow::Result<()
     Ok(())
 }
 
+/// Saves the operator the app runs as with its login token, `None` logs the current operator out
+pub fn set_login(login: Option<(&str, &str)>) -> anyhow::Result<()> {
+    #[cfg(not(target_os = "windows"))]
+    let path = PathBuf::new()
+        .join(env!("HOME"))
+        .join(format!("{CONFIG_DIR}/{CONFIG_FILE}"));
+
+    #[cfg(target_os = "windows")]
+    let path = PathBuf::new()
+        .join(env!("USERPROFILE"))
+        .join(format!("{CONFIG_DIR}/{CONFIG_FILE}"));
+
+    if !path.exists() {
+        create_config_file(&path)?;
+    }
+
+    let mut config: toml::Table = std::fs::read_to_string(&path)?.parse()?;
+    match login {
+        Some((operator, token)) => {
+            config.insert("operator".to_string(), operator.into());
+            config.insert("login_token".to_string(), token.into());
+        }
+        None => {
+            config.remove("operator");
+            config.remove("login_token");
+        }
+    }
+    std::fs::write(path, toml::to_string(&config)?)?;
+
+    Ok(())
+}
+
 fn create_config_file(path: &PathBuf) -> anyhow::Result<()> {
     // create parent directory and file if it doesn't exist
     if !path.exists() {
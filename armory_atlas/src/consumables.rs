@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+use chrono::Local;
+use clap::ValueEnum;
+
+use crate::cli::{IssueStockArgs, ReceiveStockArgs};
+use crate::products::{self, Product};
+#[cfg(feature = "python-db")]
+use crate::python_db_handler::DBHandlerPy as DBHandler;
+
+/// What the consumption report sums the issued units per
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConsumptionGroup {
+    /// One row per user and consumable
+    User,
+    /// One row per unit and consumable
+    Unit,
+}
+
+impl ConsumptionGroup {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ConsumptionGroup::User => "user",
+            ConsumptionGroup::Unit => "unit",
+        }
+    }
+}
+
+/// Finds the product in the catalog, failing unless it is a consumable
+pub fn consumable<'a>(products: &'a [Product], product_id: &str) -> Result<&'a Product> {
+    match products
+        .iter()
+        .find(|product| product.product_id == product_id)
+    {
+        Some(product) if product.consumable => Ok(product),
+        Some(product) => bail!(
+            "{} is not a consumable, its items are lent out with `insert loan`",
+            product.product_name
+        ),
+        None => bail!("{product_id} is not in the product catalog"),
+    }
+}
+
+/// How many units of each consumable have to be received to reach its stock target
+///
+/// Consumables without a stock target are stocked up to `num_items`, like the items of other
+/// products. Consumables that already have enough on hand are left out.
+pub fn restock_amounts(
+    products: &[Product],
+    num_items: usize,
+    on_hand: &HashMap<String, usize>,
+) -> Vec<(String, usize)> {
+    products
+        .iter()
+        .filter(|product| product.consumable)
+        .filter_map(|product| {
+            let target = product
+                .stock
+                .as_ref()
+                .map_or(num_items, |stock| stock.target(None));
+            let have = on_hand.get(&product.product_id).copied().unwrap_or(0);
+            let missing = target.saturating_sub(have);
+            (missing > 0).then(|| (product.product_id.clone(), missing))
+        })
+        .collect()
+}
+
+/// Receives enough units of every consumable in the catalog to reach its stock target
+pub fn restock(db_handler: &DBHandler, products: &[Product], num_items: usize) -> Result<()> {
+    let on_hand: HashMap<String, usize> = db_handler
+        .get_consumable_stock()?
+        .into_iter()
+        .map(|stock| (stock.product_id, stock.quantity as usize))
+        .collect();
+    let today = Local::now().date_naive();
+
+    let amounts = restock_amounts(products, num_items, &on_hand);
+    for (product_id, quantity) in &amounts {
+        db_handler.receive_stock(
+            product_id.clone(),
+            *quantity as u32,
+            today,
+            Some("Generated stock".to_string()),
+        )?;
+    }
+    if !amounts.is_empty() {
+        println!("Restocked {} consumables!", amounts.len());
+    }
+
+    Ok(())
+}
+
+/// Issues units of a consumable from the catalog to a user
+pub fn issue(db_handler: &DBHandler, args: IssueStockArgs) -> Result<()> {
+    let products = products::get_products()?;
+    let product = consumable(&products, &args.product_id)?;
+    let date = args.date.unwrap_or_else(|| Local::now().date_naive());
+
+    db_handler.issue_stock(
+        args.product_id.clone(),
+        args.quantity,
+        args.ssn,
+        date,
+        args.note,
+    )?;
+    println!("Issued {} {}", args.quantity, product.product_name);
+
+    Ok(())
+}
+
+/// Receives units of a consumable from the catalog into stock
+pub fn receive(db_handler: &DBHandler, args: ReceiveStockArgs) -> Result<()> {
+    let products = products::get_products()?;
+    let product = consumable(&products, &args.product_id)?;
+    let date = args.date.unwrap_or_else(|| Local::now().date_naive());
+
+    db_handler.receive_stock(args.product_id.clone(), args.quantity, date, args.note)?;
+    println!("Received {} {}", args.quantity, product.product_name);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catalog() -> Vec<Product> {
+        serde_json::from_str(
+            r#"[
+                {"ProductID": "M1", "NameOfProduct": "Signal Flare", "Type": "Equipment", "Sizes": [], "Stock": 40, "Consumable": true},
+                {"ProductID": "M2", "NameOfProduct": "Water Purification Tablets", "Type": "Supplies", "Sizes": [], "Consumable": true},
+                {"ProductID": "M3", "NameOfProduct": "Field Cap", "Type": "Hat", "Sizes": ["S", "M"], "Stock": 5}
+            ]"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_consumable() {
+        let products = catalog();
+
+        assert_eq!(
+            consumable(&products, "M1").unwrap().product_name,
+            "Signal Flare"
+        );
+        assert!(consumable(&products, "M3").is_err());
+        assert!(consumable(&products, "M9").is_err());
+    }
+
+    #[test]
+    fn test_restock_amounts() {
+        let on_hand = HashMap::from([("M1".to_string(), 15), ("M2".to_string(), 30)]);
+
+        assert_eq!(
+            restock_amounts(&catalog(), 20, &on_hand),
+            vec![("M1".to_string(), 25)]
+        );
+        assert_eq!(
+            restock_amounts(&catalog(), 20, &HashMap::new()),
+            vec![("M1".to_string(), 40), ("M2".to_string(), 20)]
+        );
+    }
+}
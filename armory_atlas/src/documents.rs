@@ -0,0 +1,273 @@
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use chrono::NaiveDate;
+use clap::ValueEnum;
+use prettytable::Table;
+
+use crate::output::to_markdown;
+
+/// The file formats a document can be written in
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DocumentFormat {
+    /// A self-contained HTML page with the styling inlined, ready to print
+    Html,
+    /// A Markdown document with GitHub flavoured tables
+    Markdown,
+}
+
+impl DocumentFormat {
+    /// Guesses the format from the file extension
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_lowercase);
+
+        match extension.as_deref() {
+            Some("html" | "htm") => Ok(Self::Html),
+            Some("md" | "markdown") => Ok(Self::Markdown),
+            _ => bail!(
+                "Can't tell the format of {} from its extension, use --document-format",
+                path.display()
+            ),
+        }
+    }
+}
+
+/// A printable summary made of headed sections
+///
+/// The tables are the same `Table` conversions the `get` commands print, so a document shows the
+/// same columns as the terminal.
+///
+/// # Example
+///
+/// ```
+/// # use armory_atlas_lib::documents::{Document, DocumentFormat};
+/// # use chrono::NaiveDate;
+/// # use prettytable::{row, Table};
+/// let mut table = Table::new();
+/// table.add_row(row!["Product Name", "Available"]);
+/// table.add_row(row!["Combat Knife", 4]);
+///
+/// let document = Document::new("Stock Status", NaiveDate::from_ymd_opt(2024, 6, 1).unwrap())
+///     .table("Stock levels", table);
+///
+/// assert!(document.render(DocumentFormat::Markdown).contains("| Combat Knife | 4 "));
+/// ```
+///
+#[derive(Debug)]
+pub struct Document {
+    title: String,
+    date: NaiveDate,
+    sections: Vec<Section>,
+}
+
+#[derive(Debug)]
+struct Section {
+    heading: String,
+    content: Content,
+}
+
+#[derive(Debug)]
+enum Content {
+    /// A table with the header as its first row
+    Table(Table),
+    Text(String),
+    /// Lines for the people that have to sign the document
+    Signatures(Vec<String>),
+}
+
+impl Document {
+    /// An empty document, `date` is printed under the title
+    pub fn new(title: impl Into<String>, date: NaiveDate) -> Self {
+        Self {
+            title: title.into(),
+            date,
+            sections: Vec::new(),
+        }
+    }
+
+    /// Adds a section with a table, tables without any rows are printed as "Nothing to report"
+    pub fn table(mut self, heading: impl Into<String>, table: impl Into<Table>) -> Self {
+        self.sections.push(Section {
+            heading: heading.into(),
+            content: Content::Table(table.into()),
+        });
+        self
+    }
+
+    /// Adds a section with a paragraph of text
+    pub fn text(mut self, heading: impl Into<String>, text: impl Into<String>) -> Self {
+        self.sections.push(Section {
+            heading: heading.into(),
+            content: Content::Text(text.into()),
+        });
+        self
+    }
+
+    /// Adds a section with a signature line for each of the `signers`
+    pub fn signatures(mut self, heading: impl Into<String>, signers: &[&str]) -> Self {
+        self.sections.push(Section {
+            heading: heading.into(),
+            content: Content::Signatures(signers.iter().map(|signer| signer.to_string()).collect()),
+        });
+        self
+    }
+
+    pub fn render(&self, format: DocumentFormat) -> String {
+        match format {
+            DocumentFormat::Html => self.to_html(),
+            DocumentFormat::Markdown => self.to_markdown(),
+        }
+    }
+
+    /// Renders the document and writes it to `path`, replacing the file if it exists
+    pub fn write(&self, path: &Path, format: DocumentFormat) -> Result<()> {
+        std::fs::write(path, self.render(format))
+            .with_context(|| format!("Could not write {}", path.display()))
+    }
+
+    fn to_markdown(&self) -> String {
+        let mut markdown = format!("# {}\n\n_{}_\n", self.title, self.date);
+        for section in &self.sections {
+            markdown.push_str(&format!("\n## {}\n\n", section.heading));
+            match &section.content {
+                Content::Table(table) if table.len() <= 1 => {
+                    markdown.push_str("Nothing to report.\n");
+                }
+                Content::Table(table) => markdown.push_str(&to_markdown(table.clone())),
+                Content::Text(text) => markdown.push_str(&format!("{text}\n")),
+                Content::Signatures(signers) => {
+                    for signer in signers {
+                        markdown.push_str(&format!("{signer}: ______________________________\n\n"));
+                    }
+                }
+            }
+        }
+        markdown
+    }
+
+    fn to_html(&self) -> String {
+        let mut body = format!(
+            "<h1>{}</h1>\n<p class=\"date\">{}</p>\n",
+            escape(&self.title),
+            self.date
+        );
+        for section in &self.sections {
+            body.push_str(&format!("<h2>{}</h2>\n", escape(&section.heading)));
+            match &section.content {
+                Content::Table(table) if table.len() <= 1 => {
+                    body.push_str("<p class=\"empty\">Nothing to report.</p>\n");
+                }
+                Content::Table(table) => body.push_str(&html_table(table)),
+                Content::Text(text) => body.push_str(&format!("<p>{}</p>\n", escape(text))),
+                Content::Signatures(signers) => {
+                    for signer in signers {
+                        body.push_str(&format!(
+                            "<div class=\"signature\"><span>{}</span></div>\n",
+                            escape(signer)
+                        ));
+                    }
+                }
+            }
+        }
+
+        format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{STYLE}</style>\n</head>\n<body>\n{body}</body>\n</html>\n",
+            escape(&self.title)
+        )
+    }
+}
+
+const STYLE: &str = "\
+body { font-family: sans-serif; font-size: 11pt; margin: 2em; color: #000; }
+h1 { margin-bottom: 0; }
+h2 { margin-top: 1.5em; border-bottom: 1px solid #000; }
+.date, .empty { color: #555; }
+table { border-collapse: collapse; width: 100%; }
+th, td { border: 1px solid #999; padding: 0.25em 0.5em; text-align: left; }
+th { background: #eee; }
+tr { page-break-inside: avoid; }
+.signature { margin-top: 3em; width: 20em; border-top: 1px solid #000; }
+@media print { body { margin: 0; } }
+";
+
+fn html_table(table: &Table) -> String {
+    let mut rows = table.row_iter();
+    let mut html = String::from("<table>\n");
+    if let Some(header) = rows.next() {
+        html.push_str("<thead><tr>");
+        for cell in header.iter() {
+            html.push_str(&format!("<th>{}</th>", escape(&cell.get_content())));
+        }
+        html.push_str("</tr></thead>\n");
+    }
+    html.push_str("<tbody>\n");
+    for row in rows {
+        html.push_str("<tr>");
+        for cell in row.iter() {
+            html.push_str(&format!("<td>{}</td>", escape(&cell.get_content())));
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</tbody>\n</table>\n");
+    html
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prettytable::row;
+
+    fn document() -> Document {
+        let mut table = Table::new();
+        table.add_row(row!["Name", "Product Name"]);
+        table.add_row(row!["Liam Smith", "Knife <M9> & sheath"]);
+        let mut empty = Table::new();
+        empty.add_row(row!["Name", "Due Date"]);
+
+        Document::new("Open Loans", NaiveDate::from_ymd_opt(2024, 6, 1).unwrap())
+            .table("1st Company", table)
+            .table("Overdue", empty)
+            .signatures("Signed", &["Quartermaster"])
+    }
+
+    #[test]
+    fn test_html() {
+        let html = document().render(DocumentFormat::Html);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<th>Name</th><th>Product Name</th>"));
+        assert!(html.contains("<td>Knife &lt;M9&gt; &amp; sheath</td>"));
+        assert!(html.contains("<p class=\"empty\">Nothing to report.</p>"));
+        assert!(html.contains("<span>Quartermaster</span>"));
+    }
+
+    #[test]
+    fn test_markdown() {
+        let markdown = document().render(DocumentFormat::Markdown);
+        assert!(markdown.starts_with("# Open Loans\n\n_2024-06-01_\n\n## 1st Company\n"));
+        assert!(markdown.contains("| Liam Smith | Knife <M9> & sheath |"));
+        assert!(markdown.contains("## Overdue\n\nNothing to report.\n"));
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(
+            DocumentFormat::from_path(Path::new("stock.HTML")).unwrap(),
+            DocumentFormat::Html
+        );
+        assert_eq!(
+            DocumentFormat::from_path(Path::new("stock.md")).unwrap(),
+            DocumentFormat::Markdown
+        );
+        assert!(DocumentFormat::from_path(Path::new("stock.pdf")).is_err());
+    }
+}
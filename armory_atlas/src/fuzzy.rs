@@ -0,0 +1,152 @@
+use std::collections::HashSet;
+
+/// Candidates scoring below this are not considered a match
+pub const MIN_SCORE: f64 = 0.5;
+
+/// A value together with how well it matched the search
+#[derive(Debug, Clone)]
+pub struct Scored<T> {
+    pub value: T,
+    pub score: f64,
+}
+
+/// Scores how well `query` matches `candidate` between 0.0 and 1.0
+///
+/// Every word of the query is compared to the closest word of the candidate using the
+/// Damerau-Levenshtein distance, so typos like "goggels" and "nite" still match. The trigram
+/// similarity of the whole strings is used when that scores higher, which helps with missing
+/// spaces and partial IDs. A candidate containing the query as is always scores 1.0.
+///
+/// # Example
+///
+/// ```
+/// # use armory_atlas_lib::fuzzy::{similarity, MIN_SCORE};
+/// assert!(similarity("nite vision", "Night Vision Goggles") > MIN_SCORE);
+/// assert!(similarity("goggels", "Night Vision Goggles") > MIN_SCORE);
+/// ```
+///
+pub fn similarity(query: &str, candidate: &str) -> f64 {
+    let query = normalize(query);
+    let candidate = normalize(candidate);
+
+    if query.is_empty() || candidate.is_empty() {
+        return 0.0;
+    }
+    if candidate.contains(&query) {
+        return 1.0;
+    }
+
+    let candidate_words: Vec<&str> = candidate.split(' ').collect();
+    let query_words: Vec<&str> = query.split(' ').collect();
+
+    let word_score = query_words
+        .iter()
+        .map(|query_word| {
+            candidate_words
+                .iter()
+                .map(|candidate_word| word_similarity(query_word, candidate_word))
+                .fold(0.0, f64::max)
+        })
+        .sum::<f64>()
+        / query_words.len() as f64;
+
+    word_score.max(trigram_similarity(&query, &candidate))
+}
+
+/// Scores and sorts the candidates by the best matching of their fields
+///
+/// Candidates below [`MIN_SCORE`] are dropped, the rest are returned with the best match first.
+///
+/// # Arguments
+///
+/// * `query`: What the user searched for.
+/// * `candidates`: The values to search through.
+/// * `fields`: Returns the fields of a candidate to match against, e.g. name, type and ID.
+///
+pub fn rank<T, F>(query: &str, candidates: Vec<T>, fields: F) -> Vec<Scored<T>>
+where
+    F: Fn(&T) -> Vec<&str>,
+{
+    let mut ranked: Vec<Scored<T>> = candidates
+        .into_iter()
+        .map(|value| {
+            let score = fields(&value)
+                .into_iter()
+                .map(|field| similarity(query, field))
+                .fold(0.0, f64::max);
+            Scored { value, score }
+        })
+        .filter(|scored| scored.score >= MIN_SCORE)
+        .collect();
+
+    ranked.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+    ranked
+}
+
+fn normalize(s: &str) -> String {
+    s.to_lowercase()
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+fn word_similarity(query_word: &str, candidate_word: &str) -> f64 {
+    // Someone still typing "gog" is looking for "goggles"
+    if query_word.chars().count() >= 3 && candidate_word.starts_with(query_word) {
+        return 0.9;
+    }
+
+    strsim::normalized_damerau_levenshtein(query_word, candidate_word)
+}
+
+fn trigrams(s: &str) -> HashSet<String> {
+    let padded: Vec<char> = format!("  {s} ").chars().collect();
+    padded
+        .windows(3)
+        .map(|window| window.iter().collect())
+        .collect()
+}
+
+fn trigram_similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (trigrams(a), trigrams(b));
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 0.0;
+    }
+
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_typos_match() {
+        assert!(similarity("nite vision", "Night Vision Goggles") >= MIN_SCORE);
+        assert!(similarity("goggels", "Night Vision Goggles") >= MIN_SCORE);
+        assert!(similarity("snipr rifle", "Sniper Rifle") >= MIN_SCORE);
+    }
+
+    #[test]
+    fn test_unrelated_does_not_match() {
+        assert!(similarity("rifle", "Field Cap") < MIN_SCORE);
+        assert!(similarity("goggles", "Combat Gloves") < MIN_SCORE);
+    }
+
+    #[test]
+    fn test_substring_scores_highest() {
+        assert_eq!(similarity("3708457", "M240003-3708457"), 1.0);
+        assert_eq!(similarity("vision", "Night Vision Goggles"), 1.0);
+    }
+
+    #[test]
+    fn test_rank_orders_by_score() {
+        let products = vec!["Field Cap", "Night Vision Goggles", "Night Sight"];
+        let ranked = rank("nite vision", products, |product| vec![*product]);
+
+        assert_eq!(ranked[0].value, "Night Vision Goggles");
+        assert!(ranked.iter().all(|scored| scored.value != "Field Cap"));
+    }
+}
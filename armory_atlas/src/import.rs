@@ -0,0 +1,766 @@
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use chrono::NaiveDate;
+use clap::ValueEnum;
+use prettytable::{row, Row, Table};
+use serde::Serialize;
+use uuid::Uuid;
+
+#[cfg(feature = "python-db")]
+use crate::cli::ImportArgs;
+use crate::items::{Item, SIZES};
+use crate::leandings::Loans;
+use crate::output::Records;
+#[cfg(feature = "python-db")]
+use crate::output::{render, OutputFormat};
+use crate::products::Product;
+#[cfg(feature = "python-db")]
+use crate::python_db_handler::DBHandlerPy as DBHandler;
+use crate::users::{ssn::SSN, User};
+
+/// The longest product ID the `Products` table can hold
+pub const MAX_PRODUCT_ID_LEN: usize = 16;
+
+/// What an import file contains
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImportKind {
+    Products,
+    Items,
+    Users,
+    Loans,
+}
+
+/// The file formats that can be imported
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImportFormat {
+    /// Comma separated values with a header row
+    Csv,
+    /// An array of JSON objects
+    Json,
+}
+
+impl ImportFormat {
+    /// Guesses the format from the file extension
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_lowercase);
+
+        match extension.as_deref() {
+            Some("csv") => Ok(Self::Csv),
+            Some("json") => Ok(Self::Json),
+            _ => bail!(
+                "Can't tell the format of {} from its extension, use --input-format",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl ImportKind {
+    /// The fields of a record and whether they are required
+    fn fields(self) -> &'static [(&'static str, bool)] {
+        match self {
+            ImportKind::Products => &[
+                ("product_id", true),
+                ("product_name", true),
+                ("product_type", true),
+            ],
+            ImportKind::Items => &[
+                ("item_id", false),
+                ("product_id", true),
+                ("size", true),
+                ("quality", true),
+                ("serial_number", false),
+            ],
+            ImportKind::Users => &[
+                ("ssn", true),
+                ("name", true),
+                ("unit", false),
+                ("rank", false),
+                ("phone", false),
+                ("email", false),
+                ("active", false),
+                ("end_of_service", false),
+            ],
+            ImportKind::Loans => &[
+                ("ssn", true),
+                ("item_id", true),
+                ("borrow_date", true),
+                ("return_date", false),
+            ],
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            ImportKind::Products => "products",
+            ImportKind::Items => "items",
+            ImportKind::Users => "users",
+            ImportKind::Loans => "loans",
+        }
+    }
+}
+
+/// Column names that are understood without a mapping, e.g. the names used in `products.json`
+const ALIASES: [(&str, &str); 6] = [
+    ("productid", "product_id"),
+    ("nameofproduct", "product_name"),
+    ("type", "product_type"),
+    ("itemid", "item_id"),
+    ("serial", "serial_number"),
+    ("borrowing_date", "borrow_date"),
+];
+
+/// One row of the file, keyed by field name once the columns have been mapped
+pub type ImportRow = BTreeMap<String, String>;
+
+/// Parses a `column=field` mapping given on the command line
+pub fn parse_mapping(s: &str) -> Result<(String, String)> {
+    let Some((column, field)) = s.split_once('=') else {
+        bail!("Expected a mapping like column=field, got {s}");
+    };
+    if column.trim().is_empty() {
+        bail!("The column of the mapping {s} is empty");
+    }
+
+    Ok((column.trim().to_string(), field.trim().to_lowercase()))
+}
+
+/// Reads the rows of a file keyed by the column names of the file
+///
+/// Every value is read as a string, numbers in JSON files are written out as they are and `null`
+/// becomes an empty value.
+pub fn read_rows(path: &Path, format: ImportFormat) -> Result<Vec<ImportRow>> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Could not read {}", path.display()))?;
+
+    match format {
+        ImportFormat::Csv => read_csv(&contents),
+        ImportFormat::Json => read_json(&contents),
+    }
+}
+
+fn read_csv(contents: &str) -> Result<Vec<ImportRow>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(contents.as_bytes());
+    let headers = reader.headers()?.clone();
+
+    reader
+        .records()
+        .map(|record| {
+            let record = record?;
+            Ok(headers
+                .iter()
+                .zip(record.iter())
+                .map(|(column, value)| (column.to_string(), value.to_string()))
+                .collect())
+        })
+        .collect()
+}
+
+fn read_json(contents: &str) -> Result<Vec<ImportRow>> {
+    let rows: Vec<serde_json::Map<String, serde_json::Value>> =
+        serde_json::from_str(contents).context("Expected a JSON array of objects")?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            row.into_iter()
+                .map(|(column, value)| {
+                    let value = match value {
+                        serde_json::Value::String(value) => value,
+                        serde_json::Value::Null => String::new(),
+                        value => value.to_string(),
+                    };
+                    (column, value.trim().to_string())
+                })
+                .collect()
+        })
+        .collect())
+}
+
+/// Renames the columns of the rows to the fields of `kind`
+///
+/// Columns are matched by name ignoring case, with spaces and dashes read as underscores. The
+/// mappings take precedence, a mapping to an empty field drops the column. Unknown columns and
+/// missing required fields are errors so nothing is silently left out.
+pub fn map_columns(
+    kind: ImportKind,
+    rows: Vec<ImportRow>,
+    mappings: &[(String, String)],
+) -> Result<Vec<ImportRow>> {
+    let fields = kind.fields();
+    let columns: HashSet<&String> = rows.iter().flat_map(|row| row.keys()).collect();
+
+    let mut renames = BTreeMap::new();
+    for column in columns {
+        let field = match mappings
+            .iter()
+            .find(|(mapped, _)| mapped.eq_ignore_ascii_case(column))
+        {
+            Some((_, field)) if field.is_empty() => continue,
+            Some((_, field)) => field.clone(),
+            None => normalize_column(column),
+        };
+
+        if !fields.iter().any(|(name, _)| *name == field) {
+            bail!(
+                "Unknown column {column} for {}, the fields are {}. Use --map {column}=<field> to map it or --map {column}= to skip it",
+                kind.name(),
+                field_names(kind)
+            );
+        }
+        if let Some(previous) = renames.values().find(|mapped| **mapped == field) {
+            bail!("More than one column is mapped to {previous}");
+        }
+        renames.insert(column.clone(), field);
+    }
+
+    for (field, required) in fields {
+        if *required && !renames.values().any(|mapped| mapped == field) {
+            bail!("The file has no column for the required field {field}");
+        }
+    }
+
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            row.into_iter()
+                .filter_map(|(column, value)| {
+                    renames.get(&column).map(|field| (field.clone(), value))
+                })
+                .collect()
+        })
+        .collect())
+}
+
+fn normalize_column(column: &str) -> String {
+    let column = column.trim().to_lowercase().replace([' ', '-'], "_");
+
+    ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == column)
+        .map(|(_, field)| field.to_string())
+        .unwrap_or(column)
+}
+
+fn field_names(kind: ImportKind) -> String {
+    kind.fields()
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<&str>>()
+        .join(", ")
+}
+
+/// A problem with a value in the file
+#[derive(Clone, Debug, Serialize)]
+pub struct RowError {
+    /// The number of the row in the file, not counting the header and starting at 1
+    pub row: usize,
+    pub field: String,
+    pub message: String,
+}
+
+/// Every problem found while validating a file
+#[derive(Debug, Default)]
+pub struct ImportReport(Vec<RowError>);
+
+impl ImportReport {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The number of rows with at least one error
+    pub fn failed_rows(&self) -> usize {
+        self.0
+            .iter()
+            .map(|error| error.row)
+            .collect::<HashSet<usize>>()
+            .len()
+    }
+}
+
+impl Records for ImportReport {
+    type Record = RowError;
+
+    fn records(&self) -> &[Self::Record] {
+        &self.0
+    }
+}
+
+impl From<&RowError> for Row {
+    fn from(value: &RowError) -> Self {
+        row![value.row, value.field, value.message]
+    }
+}
+
+impl From<ImportReport> for Table {
+    fn from(report: ImportReport) -> Self {
+        let mut table = Table::new();
+        table.add_row(row!["Row", "Field", "Error"]);
+        for error in report.0 {
+            table.add_row((&error).into());
+        }
+        table
+    }
+}
+
+/// The validated records of a file, ready to be inserted
+#[derive(Debug)]
+pub enum ImportRecords {
+    Products(Vec<Product>),
+    Items(Vec<Item>),
+    Users(Vec<User>),
+    Loans(Vec<Loans>),
+}
+
+impl ImportRecords {
+    pub fn len(&self) -> usize {
+        match self {
+            ImportRecords::Products(products) => products.len(),
+            ImportRecords::Items(items) => items.len(),
+            ImportRecords::Users(users) => users.len(),
+            ImportRecords::Loans(loans) => loans.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Reads the values of a single row and collects the problems with them
+struct RowReader<'a> {
+    row: usize,
+    values: &'a ImportRow,
+    report: &'a mut ImportReport,
+}
+
+impl RowReader<'_> {
+    fn error(&mut self, field: &str, message: impl Into<String>) {
+        self.report.0.push(RowError {
+            row: self.row,
+            field: field.to_string(),
+            message: message.into(),
+        });
+    }
+
+    fn optional(&self, field: &str) -> Option<String> {
+        self.values
+            .get(field)
+            .filter(|value| !value.is_empty())
+            .cloned()
+    }
+
+    fn required(&mut self, field: &str) -> Option<String> {
+        let value = self.optional(field);
+        if value.is_none() {
+            self.error(field, "A value is required");
+        }
+        value
+    }
+
+    fn product_id(&mut self) -> Option<String> {
+        let product_id = self.required("product_id")?;
+        if product_id.chars().count() > MAX_PRODUCT_ID_LEN {
+            self.error(
+                "product_id",
+                format!("{product_id} is longer than {MAX_PRODUCT_ID_LEN} characters"),
+            );
+            return None;
+        }
+        Some(product_id)
+    }
+
+    fn ssn(&mut self) -> Option<String> {
+        let ssn = self.required("ssn")?;
+        match ssn.parse::<SSN>() {
+            Ok(ssn) => Some(ssn.into()),
+            Err(err) => {
+                self.error("ssn", err.to_string());
+                None
+            }
+        }
+    }
+
+    fn item_id(&mut self, required: bool) -> Option<String> {
+        let item_id = if required {
+            self.required("item_id")?
+        } else {
+            self.optional("item_id")?
+        };
+        match Uuid::parse_str(&item_id) {
+            Ok(uuid) => Some(uuid.hyphenated().to_string()),
+            Err(_) => {
+                self.error("item_id", format!("{item_id} is not a valid item ID"));
+                None
+            }
+        }
+    }
+
+    fn size(&mut self) -> Option<String> {
+        let size = self.required("size")?;
+        let known = SIZES.iter().find(|known| known.eq_ignore_ascii_case(&size));
+        if known.is_none() {
+            self.error(
+                "size",
+                format!(
+                    "{size} is not a known size, expected one of {}",
+                    SIZES.join(", ")
+                ),
+            );
+        }
+        known.map(|size| size.to_string())
+    }
+
+    fn quality(&mut self) -> Option<f32> {
+        let quality = self.required("quality")?;
+        match quality.parse::<f32>() {
+            Ok(value) if (0.0..=1.0).contains(&value) => Some(value),
+            _ => {
+                self.error(
+                    "quality",
+                    format!("{quality} is not a number between 0 and 1"),
+                );
+                None
+            }
+        }
+    }
+
+    /// Reads a yes/no value, `default` when it is left out
+    fn flag(&mut self, field: &str, default: bool) -> Option<bool> {
+        let Some(value) = self.optional(field) else {
+            return Some(default);
+        };
+        match value.to_lowercase().as_str() {
+            "yes" | "y" | "true" | "1" => Some(true),
+            "no" | "n" | "false" | "0" => Some(false),
+            _ => {
+                self.error(field, format!("{value} is not yes or no"));
+                None
+            }
+        }
+    }
+
+    fn date(&mut self, field: &str, required: bool) -> Option<Option<NaiveDate>> {
+        let value = if required {
+            self.required(field)?
+        } else {
+            match self.optional(field) {
+                Some(value) => value,
+                None => return Some(None),
+            }
+        };
+        match NaiveDate::parse_from_str(&value, "%Y-%m-%d") {
+            Ok(date) => Some(Some(date)),
+            Err(_) => {
+                self.error(field, format!("{value} is not a date like 2024-01-31"));
+                None
+            }
+        }
+    }
+}
+
+/// Validates every row and turns the valid ones into records
+///
+/// All problems are collected instead of stopping at the first one, so a single run shows
+/// everything that has to be fixed in the file. Besides the values themselves, product IDs, item
+/// IDs and SSNs must be unique within the file, as must serial numbers within a product, and an
+/// item can only have one open loan.
+pub fn validate(kind: ImportKind, rows: &[ImportRow]) -> (ImportRecords, ImportReport) {
+    let mut report = ImportReport::default();
+    let mut seen = HashSet::new();
+
+    let mut products = Vec::new();
+    let mut items = Vec::new();
+    let mut users = Vec::new();
+    let mut loans = Vec::new();
+
+    for (idx, values) in rows.iter().enumerate() {
+        let mut reader = RowReader {
+            row: idx + 1,
+            values,
+            report: &mut report,
+        };
+
+        match kind {
+            ImportKind::Products => {
+                let product_id = reader.product_id();
+                let product_name = reader.required("product_name");
+                let product_type = reader.required("product_type");
+                if let Some(product_id) = &product_id {
+                    if !seen.insert(product_id.clone()) {
+                        reader.error("product_id", format!("{product_id} is already in the file"));
+                        continue;
+                    }
+                }
+                if let (Some(product_id), Some(product_name), Some(product_type)) =
+                    (product_id, product_name, product_type)
+                {
+                    products.push(Product::new(product_id, product_name, product_type));
+                }
+            }
+            ImportKind::Items => {
+                let item_id = reader.item_id(false);
+                let product_id = reader.product_id();
+                let size = reader.size();
+                let quality = reader.quality();
+                let serial_number = reader.optional("serial_number");
+                if let Some(item_id) = &item_id {
+                    if !seen.insert(item_id.clone()) {
+                        reader.error("item_id", format!("{item_id} is already in the file"));
+                        continue;
+                    }
+                }
+                if let (Some(product_id), Some(serial_number)) = (&product_id, &serial_number) {
+                    if !seen.insert(format!("{product_id}/{serial_number}")) {
+                        reader.error(
+                            "serial_number",
+                            format!("{product_id} already has serial number {serial_number} in the file"),
+                        );
+                        continue;
+                    }
+                }
+                if let (Some(product_id), Some(size), Some(quality)) = (product_id, size, quality) {
+                    items.push(Item::new(
+                        item_id.unwrap_or_default(),
+                        product_id,
+                        size,
+                        quality,
+                        serial_number,
+                    ));
+                }
+            }
+            ImportKind::Users => {
+                let ssn = reader.ssn();
+                let name = reader.required("name");
+                let active = reader.flag("active", true);
+                let end_of_service = reader.date("end_of_service", false);
+                if let Some(ssn) = &ssn {
+                    if !seen.insert(ssn.clone()) {
+                        reader.error("ssn", format!("{ssn} is already in the file"));
+                        continue;
+                    }
+                }
+                if let (Some(ssn), Some(name), Some(active), Some(end_of_service)) =
+                    (ssn, name, active, end_of_service)
+                {
+                    users.push(User {
+                        unit: reader.optional("unit"),
+                        rank: reader.optional("rank"),
+                        phone: reader.optional("phone"),
+                        email: reader.optional("email"),
+                        active,
+                        end_of_service,
+                        ..User::new(ssn, name)
+                    });
+                }
+            }
+            ImportKind::Loans => {
+                let ssn = reader.ssn();
+                let item_id = reader.item_id(true);
+                let borrow_date = reader.date("borrow_date", true).flatten();
+                let return_date = reader.date("return_date", false);
+                let (Some(ssn), Some(item_id), Some(borrow_date), Some(return_date)) =
+                    (ssn, item_id, borrow_date, return_date)
+                else {
+                    continue;
+                };
+
+                if return_date.is_some_and(|return_date| return_date < borrow_date) {
+                    reader.error("return_date", "The item is returned before it was borrowed");
+                    continue;
+                }
+                if return_date.is_none() && !seen.insert(item_id.clone()) {
+                    reader.error(
+                        "item_id",
+                        format!("{item_id} already has an open loan in the file"),
+                    );
+                    continue;
+                }
+                loans.push(Loans::new(ssn, item_id, borrow_date, return_date));
+            }
+        }
+    }
+
+    let records = match kind {
+        ImportKind::Products => ImportRecords::Products(products),
+        ImportKind::Items => ImportRecords::Items(items),
+        ImportKind::Users => ImportRecords::Users(users),
+        ImportKind::Loans => ImportRecords::Loans(loans),
+    };
+
+    (records, report)
+}
+
+/// Imports a file into the database
+///
+/// The file is validated first. If any row has an error the report is printed and nothing is
+/// imported, otherwise every row is inserted in a single transaction. With `--dry-run` the file
+/// is only validated.
+///
+/// # Arguments
+///
+/// * `db_handler`: The database handler to insert the records with.
+/// * `args`: The arguments of the import command.
+/// * `format`: The format to print the error report in.
+///
+#[cfg(feature = "python-db")]
+pub fn import(db_handler: &DBHandler, args: ImportArgs, format: OutputFormat) -> Result<()> {
+    let input_format = match args.input_format {
+        Some(input_format) => input_format,
+        None => ImportFormat::from_path(&args.file)?,
+    };
+
+    let rows = read_rows(&args.file, input_format)?;
+    let rows = map_columns(args.kind, rows, &args.mappings)?;
+    let (records, report) = validate(args.kind, &rows);
+
+    if !report.is_empty() {
+        let failed_rows = report.failed_rows();
+        println!("{}", render(report, format)?);
+        if !args.dry_run {
+            bail!(
+                "{failed_rows} of {} rows have errors, nothing was imported",
+                rows.len()
+            );
+        }
+        println!("{failed_rows} of {} rows have errors", rows.len());
+        return Ok(());
+    }
+
+    if args.dry_run {
+        println!(
+            "All {} {} are valid, nothing was imported",
+            records.len(),
+            args.kind.name()
+        );
+        return Ok(());
+    }
+
+    let imported = records.len();
+    db_handler.import_records(records)?;
+    println!("Imported {imported} {}", args.kind.name());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(csv: &str) -> Vec<ImportRow> {
+        read_csv(csv).unwrap()
+    }
+
+    #[test]
+    fn test_map_columns() {
+        let mapped = map_columns(
+            ImportKind::Products,
+            rows("ProductID,Namn,Type,Comment\nM240003,Sniper Rifle,Weapon,old\n"),
+            &[
+                ("namn".to_string(), "product_name".to_string()),
+                ("Comment".to_string(), String::new()),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(mapped[0]["product_id"], "M240003");
+        assert_eq!(mapped[0]["product_name"], "Sniper Rifle");
+        assert_eq!(mapped[0]["product_type"], "Weapon");
+        assert_eq!(mapped[0].len(), 3);
+    }
+
+    #[test]
+    fn test_map_columns_errors() {
+        let users = "ssn,name,age\n811218-9876,Liam Smith,42\n";
+        assert!(map_columns(ImportKind::Users, rows(users), &[]).is_err());
+        assert!(map_columns(ImportKind::Users, rows("ssn\n811218-9876\n"), &[]).is_err());
+    }
+
+    #[test]
+    fn test_validate_items() {
+        let rows = map_columns(
+            ImportKind::Items,
+            rows(
+                "product_id,size,quality,serial\nM240003,m,0.5,SR-001\nM240003-3708457-12,XXXL,2,\nM240003,L,0.5,SR-001\n",
+            ),
+            &[],
+        )
+        .unwrap();
+        let (records, report) = validate(ImportKind::Items, &rows);
+
+        assert_eq!(records.len(), 1);
+        let fields: Vec<(usize, &str)> = report
+            .records()
+            .iter()
+            .map(|error| (error.row, error.field.as_str()))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                (2, "product_id"),
+                (2, "size"),
+                (2, "quality"),
+                (3, "serial_number")
+            ]
+        );
+        assert_eq!(report.failed_rows(), 2);
+    }
+
+    #[test]
+    fn test_validate_users() {
+        let rows = map_columns(
+            ImportKind::Users,
+            rows(
+                "ssn,name,unit,active,end_of_service\n811218-9876,Liam Smith,1st Company,no,2024-01-31\n900101-1239,Emma Jones,,maybe,\n",
+            ),
+            &[],
+        )
+        .unwrap();
+        let (records, report) = validate(ImportKind::Users, &rows);
+
+        let ImportRecords::Users(users) = records else {
+            panic!("expected users");
+        };
+        assert_eq!(users.len(), 1);
+        assert_eq!(users[0].unit.as_deref(), Some("1st Company"));
+        assert!(!users[0].active);
+        assert_eq!(
+            report
+                .records()
+                .iter()
+                .map(|error| (error.row, error.field.as_str()))
+                .collect::<Vec<_>>(),
+            vec![(2, "active")]
+        );
+    }
+
+    #[test]
+    fn test_validate_loans() {
+        let item = "0b9f6a8e-3c1d-4e0a-9f8b-2a6c1e4d5f70";
+        let json = format!(
+            r#"[
+                {{"ssn": "811218-9876", "item_id": "{item}", "borrow_date": "2023-01-10", "return_date": "2023-02-01"}},
+                {{"ssn": "8112189876", "item_id": "{item}", "borrow_date": "2023-03-10", "return_date": null}},
+                {{"ssn": "811218-9876", "item_id": "{item}", "borrow_date": "2023-04-10"}},
+                {{"ssn": "811218-9875", "item_id": "{item}", "borrow_date": "2023-05-10", "return_date": "2023-05-01"}}
+            ]"#
+        );
+        let rows = map_columns(ImportKind::Loans, read_json(&json).unwrap(), &[]).unwrap();
+        let (records, report) = validate(ImportKind::Loans, &rows);
+
+        assert_eq!(records.len(), 2);
+        let fields: Vec<(usize, &str)> = report
+            .records()
+            .iter()
+            .map(|error| (error.row, error.field.as_str()))
+            .collect();
+        assert_eq!(fields, vec![(3, "item_id"), (4, "ssn")]);
+    }
+}
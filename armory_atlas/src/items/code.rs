@@ -0,0 +1,150 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use anyhow::{bail, Result};
+
+/// A short item code like `FC-0042-5`, given to every item when it is inserted
+///
+/// The prefix is made from the initials of the product name, the number counts the items with the
+/// same prefix and the last digit is a check digit. The check digit is computed like the one of an
+/// ISIN: every letter is replaced by its value as a base 36 digit, `A` is 10 and `Z` is 35, and the
+/// Luhn algorithm is run over the resulting digits. It catches a mistyped character and most
+/// swapped neighbours. Codes are parsed case-insensitively and always written in upper case.
+///
+/// The codes are generated by the database handler, [`ItemCode::check_digit`] has to match
+/// `item_code_check_digit` there.
+///
+/// # Example
+///
+/// ```
+/// # use armory_atlas_lib::items::code::ItemCode;
+/// let code: ItemCode = "fc-0042-5".parse().unwrap();
+/// assert_eq!(code.to_string(), "FC-0042-5");
+///
+/// assert!("FC-0042-7".parse::<ItemCode>().is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemCode {
+    prefix: String,
+    number: u32,
+}
+
+impl ItemCode {
+    /// The code of the item with the number among the items of the prefix
+    pub fn new(prefix: &str, number: u32) -> Result<Self> {
+        let prefix = prefix.to_ascii_uppercase();
+        if !(1..=3).contains(&prefix.len()) || !prefix.chars().all(|c| c.is_ascii_uppercase()) {
+            bail!("The prefix of an item code is one to three letters, not '{prefix}'");
+        }
+        Ok(Self { prefix, number })
+    }
+
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    pub fn number(&self) -> u32 {
+        self.number
+    }
+
+    /// The check digit of the code, see [`ItemCode`]
+    pub fn check_digit(&self) -> u32 {
+        let digits: Vec<u32> = format!("{}{:04}", self.prefix, self.number)
+            .chars()
+            .filter_map(|c| c.to_digit(36))
+            .flat_map(|value| match value {
+                10.. => vec![value / 10, value % 10],
+                _ => vec![value],
+            })
+            .collect();
+        let sum: u32 = digits
+            .iter()
+            .rev()
+            .enumerate()
+            .map(|(position, digit)| match position % 2 {
+                0 if *digit > 4 => digit * 2 - 9,
+                0 => digit * 2,
+                _ => *digit,
+            })
+            .sum();
+        (10 - sum % 10) % 10
+    }
+}
+
+impl Display for ItemCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}-{:04}-{}",
+            self.prefix,
+            self.number,
+            self.check_digit()
+        )
+    }
+}
+
+impl FromStr for ItemCode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let parts: Vec<&str> = s.trim().split('-').collect();
+        let [prefix, number, check] = parts[..] else {
+            bail!("'{s}' is neither an item ID nor an item code like FC-0042-5");
+        };
+        let is_number = |part: &str| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit());
+        if !is_number(number) || check.len() != 1 || !is_number(check) {
+            bail!("'{s}' is neither an item ID nor an item code like FC-0042-5");
+        }
+        let Ok(number) = number.parse() else {
+            bail!("The number of the item code '{s}' is too large");
+        };
+
+        let code = Self::new(prefix, number)?;
+        if check.parse::<u32>()? != code.check_digit() {
+            bail!("The check digit of the item code '{s}' doesn't match, check it for typos");
+        }
+        Ok(code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_digit() {
+        // F and C are 15 and 12, the Luhn sum of 15120042 is 15
+        assert_eq!(ItemCode::new("FC", 42).unwrap().check_digit(), 5);
+        assert_eq!(ItemCode::new("HE", 1).unwrap().to_string(), "HE-0001-3");
+        assert_eq!(ItemCode::new("x", 12345).unwrap().to_string(), "X-12345-6");
+    }
+
+    #[test]
+    fn test_parse() {
+        let code: ItemCode = " fc-0042-5 ".parse().unwrap();
+        assert_eq!(code, ItemCode::new("FC", 42).unwrap());
+        assert_eq!("FC-42-5".parse::<ItemCode>().unwrap(), code);
+
+        // A mistyped digit and swapped neighbours
+        assert!("FC-0043-5".parse::<ItemCode>().is_err());
+        assert!("FC-0024-5".parse::<ItemCode>().is_err());
+
+        assert!("FCAB-0042-5".parse::<ItemCode>().is_err());
+        assert!("FC-0042".parse::<ItemCode>().is_err());
+        assert!("0b6c5ff4-22c1-11ef-a5a3-0242ac120002"
+            .parse::<ItemCode>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_matches_the_database_handler() {
+        // The check digits item_code_check_digit in ArmoryAtlasDBHandler.py gives for these codes
+        for (prefix, number, check) in [("FC", 42, 5), ("CK", 7, 8), ("AMP", 130, 5)] {
+            assert_eq!(
+                ItemCode::new(prefix, number).unwrap().check_digit(),
+                check,
+                "{prefix}-{number}"
+            );
+        }
+    }
+}
@@ -1,3 +1,4 @@
+pub mod code;
 #[cfg(feature = "python-db")]
 mod python_impl;
 
@@ -6,11 +7,16 @@ use crate::cli::InsertItemArgs;
 use crate::python_db_handler::DBHandlerPy as DBHandler;
 use anyhow::Result;
 use prettytable::{row, Row, Table};
-use rand::Rng;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 use rayon::prelude::*;
+use std::collections::HashMap;
 use std::fmt::Display;
+use uuid::Builder;
 
-use crate::products;
+use crate::consumables;
+use crate::products::{self, Product};
+use crate::search::SearchQuery;
 
 pub const SIZES: [&str; 6] = ["XS", "S", "M", "L", "XL", "XXL"];
 
@@ -23,6 +29,8 @@ pub struct Item {
     pub product_id: String,
     pub size: String,
     pub quality: f32,
+    /// The serial number of the item, unique among the items of its product
+    pub serial_number: Option<String>,
 }
 
 #[derive(Clone)]
@@ -31,15 +39,23 @@ pub struct TmpItem {
     pub product_id: String,
     pub size: String,
     pub quality: f32,
+    pub serial_number: Option<String>,
 }
 
 impl Item {
-    pub fn new(item_id: String, product_id: String, size: String, quality: f32) -> Self {
+    pub fn new(
+        item_id: String,
+        product_id: String,
+        size: String,
+        quality: f32,
+        serial_number: Option<String>,
+    ) -> Self {
         Self {
             item_id,
             product_id,
             size,
             quality,
+            serial_number,
         }
     }
 }
@@ -51,6 +67,7 @@ impl From<&Item> for TmpItem {
             product_id: item.product_id.clone(),
             size: item.size.clone(),
             quality: item.quality,
+            serial_number: item.serial_number.clone(),
         }
     }
 }
@@ -62,21 +79,30 @@ impl From<TmpItem> for Item {
             product_id: tmp_item.product_id,
             size: tmp_item.size,
             quality: tmp_item.quality,
+            serial_number: tmp_item.serial_number,
         }
     }
 }
 
 impl From<Item> for Row {
     fn from(item: Item) -> Self {
+        let serial_number = item.serial_number.unwrap_or_default();
         if item.item_id.is_empty() {
             row![
                 "WILL_BE_GENERATED",
                 item.product_id,
                 item.size,
-                item.quality
+                item.quality,
+                serial_number
             ]
         } else {
-            row![item.item_id, item.product_id, item.size, item.quality]
+            row![
+                item.item_id,
+                item.product_id,
+                item.size,
+                item.quality,
+                serial_number
+            ]
         }
     }
 }
@@ -92,7 +118,7 @@ impl From<Vec<Item>> for Items {
 impl From<Items> for Table {
     fn from(items: Items) -> Self {
         let mut table = Table::new();
-        table.add_row(row!["ItemID", "ProductID", "Size", "Quality"]);
+        table.add_row(row!["ItemID", "ProductID", "Size", "Quality", "Serial"]);
         for item in items.0 {
             table.add_row(item.into());
         }
@@ -105,64 +131,113 @@ impl Display for Item {
         if self.item_id.is_empty() {
             write!(
                 f,
-                "Item(item_id=\"WILL_BE_GENERATED\", product_id={}, size={}, quality={})",
+                "Item(item_id=\"WILL_BE_GENERATED\", product_id={}, size={}, quality={}",
                 self.product_id, self.size, self.quality
-            )
+            )?;
         } else {
             write!(
                 f,
-                "Item(item_id={}, product_id={}, size={}, quality={})",
+                "Item(item_id={}, product_id={}, size={}, quality={}",
                 self.item_id, self.product_id, self.size, self.quality
-            )
+            )?;
+        }
+        match &self.serial_number {
+            Some(serial_number) => write!(f, ", serial_number={serial_number})"),
+            None => write!(f, ")"),
         }
     }
 }
 
-pub fn generate_items(num_items: usize) -> Result<Vec<Item>> {
-    let products = products::get_products()?;
-
-    println!(
-        "Generating {} items for {} diffrent products!",
-        num_items,
-        products.len()
-    );
+/// Generates items for every product in the catalog that is not a consumable
+///
+/// Products with a stock target get as many items as they are short of it, counting the items in
+/// `in_stock`. The rest get `num_items` items spread evenly over their sizes. The quality of each
+/// item is drawn from the quality distribution of its product. Consumables have no items, see
+/// [`restock`](crate::consumables::restock).
+///
+/// Each product gets its own generator seeded from `rng` before the work is split up, so the
+/// items only depend on `rng` and not on how rayon schedules the products. The item IDs are
+/// generated here as well for the same reason.
+///
+/// # Arguments
+///
+/// * `products`: The products from the catalog.
+/// * `num_items`: The number of items for products without a stock target.
+/// * `in_stock`: The number of items there already are, by product ID and size. The size is empty
+///   for products without sizes.
+/// * `rng`: The random number generator to generate the items with.
+///
+pub fn generate_items(
+    products: &[Product],
+    num_items: usize,
+    in_stock: &HashMap<(String, String), usize>,
+    rng: &mut impl Rng,
+) -> Result<Vec<Item>> {
+    for product in products {
+        product.validate_catalog_entry()?;
+    }
 
-    let mut items = Vec::new();
+    let seeds: Vec<u64> = products.iter().map(|_| rng.gen()).collect();
 
     let items_iter = products
         .par_iter()
-        .map(|product| {
+        .zip(seeds)
+        .map(|(product, seed)| {
             let mut product_items = Vec::new();
-            let mut rng = rand::thread_rng();
-
-            let mut next_idx = 0;
-
-            for _ in 0..num_items {
-                let item = Item {
-                    item_id: String::new(),
-                    product_id: product.product_id.clone(),
-                    size: SIZES[next_idx].to_string(),
-                    quality: rng.gen_range(0.0..1.0),
+            if product.consumable {
+                return product_items;
+            }
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            let quality = product.quality.clone().unwrap_or_default();
+            let sizes = product.generated_sizes();
+
+            for (idx, size) in sizes.iter().enumerate() {
+                let count = match &product.stock {
+                    Some(stock) => {
+                        let key = (product.product_id.clone(), size.unwrap_or_default().into());
+                        let have = in_stock.get(&key).copied().unwrap_or(0);
+                        stock.target(*size).saturating_sub(have)
+                    }
+                    None => num_items / sizes.len() + usize::from(idx < num_items % sizes.len()),
                 };
-
-                product_items.push(item);
-
-                next_idx = (next_idx + 1) % SIZES.len();
+                let size = size.unwrap_or_default();
+
+                for _ in 0..count {
+                    product_items.push(Item {
+                        item_id: Builder::from_random_bytes(rng.gen())
+                            .into_uuid()
+                            .to_string(),
+                        product_id: product.product_id.clone(),
+                        size: size.to_string(),
+                        quality: quality.sample(&mut rng),
+                        serial_number: None,
+                    });
+                }
             }
 
             product_items
         })
         .collect::<Vec<Vec<Item>>>();
 
-    for product_items in items_iter {
-        items.extend(product_items);
-    }
-
-    Ok(items)
+    Ok(items_iter.into_iter().flatten().collect())
 }
 
-pub fn insert_items(db_handler: &DBHandler, num_items: usize) -> Result<()> {
-    let items = generate_items(num_items)?;
+pub fn insert_items(db_handler: &DBHandler, num_items: usize, rng: &mut impl Rng) -> Result<()> {
+    let products = products::get_products()?;
+
+    let mut in_stock = HashMap::new();
+    for item in db_handler.find_items(SearchQuery::default())? {
+        *in_stock
+            .entry((item.product_id, item.size.unwrap_or_default()))
+            .or_insert(0) += 1;
+    }
+
+    let items = generate_items(&products, num_items, &in_stock, rng)?;
+    println!(
+        "Generated {} items for {} diffrent products!",
+        items.len(),
+        products.len()
+    );
 
     println!("Inserting these items:");
     let mut table_vec = Vec::new();
@@ -184,6 +259,8 @@ pub fn insert_items(db_handler: &DBHandler, num_items: usize) -> Result<()> {
         db_handler.insert_item(item)?;
     }
 
+    consumables::restock(db_handler, &products, num_items)?;
+
     Ok(())
 }
 
@@ -194,6 +271,64 @@ impl From<InsertItemArgs> for Item {
             product_id: insert_item_args.product_id,
             size: insert_item_args.size,
             quality: insert_item_args.quality,
+            serial_number: insert_item_args.serial,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catalog() -> Vec<Product> {
+        serde_json::from_str(
+            r#"[
+                {"ProductID": "M1", "NameOfProduct": "Signal Flare", "Type": "Equipment", "Sizes": [], "Stock": 5},
+                {"ProductID": "M2", "NameOfProduct": "Tactical Boots", "Type": "Footwear", "Sizes": ["M", "L"], "Stock": {"M": 3, "L": 2}},
+                {"ProductID": "M3", "NameOfProduct": "Field Cap", "Type": "Hat", "Sizes": ["S", "M"]},
+                {"ProductID": "M4", "NameOfProduct": "Water Purification Tablets", "Type": "Supplies", "Sizes": [], "Stock": 50, "Consumable": true}
+            ]"#,
+        )
+        .unwrap()
+    }
+
+    fn count(items: &[Item], product_id: &str, size: &str) -> usize {
+        items
+            .iter()
+            .filter(|item| item.product_id == product_id && item.size == size)
+            .count()
+    }
+
+    #[test]
+    fn test_generate_items_follows_catalog() {
+        let in_stock = HashMap::from([(("M2".to_string(), "M".to_string()), 1)]);
+        let items =
+            generate_items(&catalog(), 3, &in_stock, &mut crate::seeded_rng(Some(1))).unwrap();
+
+        assert_eq!(count(&items, "M1", ""), 5);
+        assert_eq!(count(&items, "M2", "M"), 2);
+        assert_eq!(count(&items, "M2", "L"), 2);
+        assert_eq!(count(&items, "M3", "S"), 2);
+        assert_eq!(count(&items, "M3", "M"), 1);
+        assert_eq!(count(&items, "M4", ""), 0);
+        assert_eq!(items.len(), 12);
+    }
+
+    #[test]
+    fn test_generate_items_is_reproducible() {
+        let generate = || {
+            generate_items(
+                &catalog(),
+                3,
+                &HashMap::new(),
+                &mut crate::seeded_rng(Some(9)),
+            )
+            .unwrap()
+            .into_iter()
+            .map(|item| (item.item_id, item.size, item.quality.to_bits()))
+            .collect::<Vec<_>>()
+        };
+
+        assert_eq!(generate(), generate());
+    }
+}
@@ -3,12 +3,20 @@ use crate::items::Item;
 #[pyo3::pymethods]
 impl Item {
     #[new]
-    pub fn py_new(item_id: String, product_id: String, size: String, quality: f32) -> Self {
+    #[pyo3(signature = (item_id, product_id, size, quality, serial_number=None))]
+    pub fn py_new(
+        item_id: String,
+        product_id: String,
+        size: String,
+        quality: f32,
+        serial_number: Option<String>,
+    ) -> Self {
         Self {
             item_id,
             product_id,
             size,
             quality,
+            serial_number,
         }
     }
 
@@ -32,6 +40,11 @@ impl Item {
         self.quality
     }
 
+    #[getter(serial_number)]
+    pub fn py_get_serial_number(&self) -> Option<String> {
+        self.serial_number.clone()
+    }
+
     #[pyo3(name = "__repr__")]
     pub fn py_repr(&self) -> String {
         self.to_string()
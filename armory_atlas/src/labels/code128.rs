@@ -0,0 +1,152 @@
+//! Code 128 barcodes
+//!
+//! Text is encoded with code set B, runs of digits switch to code set C which packs two digits
+//! into each symbol. That keeps the barcodes of item IDs, which are mostly digits, short enough to
+//! fit on a label.
+
+use anyhow::{bail, Result};
+
+/// The widths of the bars and spaces of every symbol, starting with a bar
+const PATTERNS: [&str; 107] = [
+    "212222", "222122", "222221", "121223", "121322", "131222", "122213", "122312", "132212",
+    "221213", "221312", "231212", "112232", "122132", "122231", "113222", "123122", "123221",
+    "223211", "221132", "221231", "213212", "223112", "312131", "311222", "321122", "321221",
+    "312212", "322112", "322211", "212123", "212321", "232121", "111323", "131123", "131321",
+    "112313", "132113", "132311", "211313", "231113", "231311", "112133", "112331", "132131",
+    "113123", "113321", "133121", "313121", "211331", "231131", "213113", "213311", "213131",
+    "311123", "311321", "331121", "312113", "312311", "332111", "314111", "221411", "431111",
+    "111224", "111422", "121124", "121421", "141122", "141221", "112214", "112412", "122114",
+    "122411", "142112", "142211", "241211", "221114", "413111", "241112", "134111", "111242",
+    "121142", "121241", "114212", "124112", "124211", "411212", "421112", "421211", "212141",
+    "214121", "412121", "111143", "111341", "131141", "114113", "114311", "411113", "411311",
+    "113141", "114131", "311141", "411131", "211412", "211214", "211232", "2331112",
+];
+
+const CODE_C: u8 = 99;
+const CODE_B: u8 = 100;
+const START_B: u8 = 104;
+const START_C: u8 = 105;
+const STOP: u8 = 106;
+
+/// The number of modules of white space needed on each side of the barcode
+pub const QUIET_ZONE: usize = 10;
+
+/// Encodes the text as the modules of a Code 128 barcode, `true` for a bar
+///
+/// The quiet zones are not included.
+pub fn encode(text: &str) -> Result<Vec<bool>> {
+    let mut modules = Vec::new();
+    for symbol in symbols(text)? {
+        for (index, width) in PATTERNS[symbol as usize].bytes().enumerate() {
+            let bar = index % 2 == 0;
+            modules.extend(std::iter::repeat_n(bar, (width - b'0') as usize));
+        }
+    }
+    Ok(modules)
+}
+
+/// The symbol values of the barcode, from the start symbol to the stop symbol
+fn symbols(text: &str) -> Result<Vec<u8>> {
+    if let Some(c) = text.chars().find(|c| !(' '..='~').contains(c)) {
+        bail!("'{c}' can't be put in a barcode, only printable ASCII can");
+    }
+    let bytes = text.as_bytes();
+    let digits = |from: usize| {
+        bytes[from..]
+            .iter()
+            .take_while(|byte| byte.is_ascii_digit())
+            .count()
+    };
+
+    let mut code_c = digits(0) >= 4 || (digits(0) >= 2 && digits(0) == bytes.len());
+    let mut values = vec![if code_c { START_C } else { START_B }];
+    let mut index = 0;
+    while index < bytes.len() {
+        let run = digits(index);
+        if code_c {
+            if run >= 2 {
+                values.push((bytes[index] - b'0') * 10 + bytes[index + 1] - b'0');
+                index += 2;
+            } else {
+                values.push(CODE_B);
+                code_c = false;
+            }
+        } else if run >= 6 || (run >= 4 && index + run == bytes.len()) {
+            // An odd digit goes in code set B so the rest pairs up
+            if run % 2 == 1 {
+                values.push(bytes[index] - b' ');
+                index += 1;
+            }
+            values.push(CODE_C);
+            code_c = true;
+        } else {
+            values.push(bytes[index] - b' ');
+            index += 1;
+        }
+    }
+
+    let checksum = values
+        .iter()
+        .enumerate()
+        .map(|(position, value)| position.max(1) * *value as usize)
+        .sum::<usize>()
+        % 103;
+    values.push(checksum as u8);
+    values.push(STOP);
+
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_patterns() {
+        let widths = |pattern: &str| {
+            pattern
+                .bytes()
+                .map(|width| (width - b'0') as usize)
+                .collect::<Vec<_>>()
+        };
+        for pattern in &PATTERNS[..106] {
+            let widths = widths(pattern);
+            assert_eq!(widths.iter().sum::<usize>(), 11, "{pattern}");
+            // The bars of every symbol add up to an even number of modules
+            assert_eq!(widths.iter().step_by(2).sum::<usize>() % 2, 0, "{pattern}");
+        }
+        assert_eq!(widths(PATTERNS[STOP as usize]).iter().sum::<usize>(), 13);
+        assert_eq!(
+            PATTERNS.iter().collect::<HashSet<_>>().len(),
+            PATTERNS.len()
+        );
+    }
+
+    #[test]
+    fn test_symbols() {
+        // 104 + 48 + 42*2 + 42*3 + 17*4 + 18*5 + 19*6 + 35*7 = 879, which is 55 modulo 103
+        assert_eq!(
+            symbols("PJJ123C").unwrap(),
+            vec![104, 48, 42, 42, 17, 18, 19, 35, 55, 106]
+        );
+        assert_eq!(
+            symbols("12345678").unwrap(),
+            vec![105, 12, 34, 56, 78, 47, 106]
+        );
+        assert_eq!(
+            symbols("AB1234567").unwrap()[..8],
+            [104, 33, 34, 17, 99, 23, 45, 67]
+        );
+        assert_eq!(symbols("12345a").unwrap()[..6], [105, 12, 34, 100, 21, 65]);
+        assert!(symbols("Åsa").is_err());
+    }
+
+    #[test]
+    fn test_encode() {
+        let modules = encode("12").unwrap();
+        // Start C, 12, the checksum 5 and the stop symbol
+        assert_eq!(modules.len(), 3 * 11 + 13);
+        assert!(modules.first().unwrap() & modules.last().unwrap());
+    }
+}
@@ -0,0 +1,393 @@
+//! Printable labels for items
+//!
+//! Every label has a QR code with the item and product ID, the product name, size and serial
+//! number, and a Code 128 barcode of the item ID that handheld scanners can read. The labels are
+//! laid out on a sheet with one label printer label per cell, written as SVG or PNG.
+
+pub mod code128;
+mod font;
+pub mod sheet;
+
+use std::path::Path;
+
+use anyhow::{bail, ensure, Context, Result};
+use clap::ValueEnum;
+use qrcode::{Color, EcLevel, QrCode};
+
+use crate::labels::sheet::{Sheet, MM_PER_INCH};
+#[cfg(feature = "python-db")]
+use crate::{
+    cli::LabelsArgs,
+    python_db_handler::{item_records::ItemRecord, DBHandlerPy as DBHandler},
+    search::{Qualifier, SearchQuery},
+};
+
+/// Separates the item ID from the product ID in the QR codes
+pub const PAYLOAD_SEPARATOR: char = '|';
+
+/// The white space around every label, label printers don't place the paper exactly
+const MARGIN: f32 = 2.0;
+
+/// The number of modules of white space needed around a QR code
+const QR_QUIET_ZONE: usize = 4;
+
+/// The file formats a label sheet can be written in
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LabelFormat {
+    /// A vector image, scales to any printer
+    Svg,
+    /// A black and white image at the resolution given by --dpi
+    Png,
+}
+
+impl LabelFormat {
+    /// Guesses the format from the file extension
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_lowercase);
+
+        match extension.as_deref() {
+            Some("svg") => Ok(Self::Svg),
+            Some("png") => Ok(Self::Png),
+            _ => bail!(
+                "Can't tell the format of {} from its extension, use --label-format",
+                path.display()
+            ),
+        }
+    }
+}
+
+/// The size of a single label in millimetres
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LabelSize {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Default for LabelSize {
+    fn default() -> Self {
+        Self {
+            width: 100.0,
+            height: 50.0,
+        }
+    }
+}
+
+/// Parses a label size like `100x50`, the width and height in millimetres
+pub fn parse_label_size(s: &str) -> Result<LabelSize> {
+    let Some((width, height)) = s
+        .to_lowercase()
+        .split_once('x')
+        .map(|(width, height)| (width.trim().parse::<f32>(), height.trim().parse::<f32>()))
+    else {
+        bail!("Expected a label size like WIDTHxHEIGHT in millimetres, got {s}");
+    };
+    let (Ok(width), Ok(height)) = (width, height) else {
+        bail!("Expected a label size like WIDTHxHEIGHT in millimetres, got {s}");
+    };
+    ensure!(
+        width >= 25.0 && height >= 15.0,
+        "Labels have to be at least 25x15 mm, got {s}"
+    );
+
+    Ok(LabelSize { width, height })
+}
+
+/// What is printed on the label of an item
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Label {
+    pub item_id: String,
+    pub product_id: String,
+    pub product_name: String,
+    pub size: Option<String>,
+    pub serial_number: Option<String>,
+    pub item_code: Option<String>,
+}
+
+#[cfg(feature = "python-db")]
+impl From<&ItemRecord> for Label {
+    fn from(item: &ItemRecord) -> Self {
+        Self {
+            item_id: item.item_id.clone(),
+            product_id: item.product_id.clone(),
+            product_name: item.product_name.clone(),
+            size: item.size.clone(),
+            serial_number: item.serial_number.clone(),
+            item_code: item.item_code.clone(),
+        }
+    }
+}
+
+impl Label {
+    /// The text in the QR code, the item ID and the product ID separated by [`PAYLOAD_SEPARATOR`]
+    pub fn qr_payload(&self) -> String {
+        format!("{}{PAYLOAD_SEPARATOR}{}", self.item_id, self.product_id)
+    }
+
+    /// Draws the label with its top left corner at `x`, `y`
+    ///
+    /// Barcode and QR modules are a whole number of dots wide at `dpi`, so every bar prints with
+    /// the same width.
+    fn draw(&self, sheet: &mut Sheet, x: f32, y: f32, size: LabelSize, dpi: u32) -> Result<()> {
+        let scale = dpi as f32 / MM_PER_INCH;
+        let snap = |length: f32| (length * scale).round() / scale;
+        // The widest module that fits `count` modules in `length`, rounded down to whole dots
+        let module = |length: f32, count: usize| (length * scale / count as f32).floor() / scale;
+        let inner_width = size.width - 2.0 * MARGIN;
+
+        // The item ID under the barcode, as large as fits up to 3 mm
+        let caption = (size.height * 0.07)
+            .clamp(1.5, 3.0)
+            .min(inner_width / (0.6 * self.item_id.chars().count() as f32));
+        let caption_baseline = y + size.height - MARGIN;
+        let caption_text = fit(&self.item_id, caption, inner_width);
+        let caption_width = 0.6 * caption * caption_text.chars().count() as f32;
+        sheet.text(
+            x + (size.width - caption_width) / 2.0,
+            caption_baseline,
+            caption,
+            &caption_text,
+        );
+
+        let bars = code128::encode(&self.item_id)?;
+        let bar_module = module(inner_width, bars.len() + 2 * code128::QUIET_ZONE);
+        if bar_module <= 0.0 {
+            bail!(
+                "The barcode of {} doesn't fit on a {} mm wide label at {dpi} dpi, use a wider label or a higher --dpi",
+                self.item_id,
+                size.width
+            );
+        }
+        let bar_height = size.height * 0.3;
+        let bar_top = snap(caption_baseline - caption - bar_height);
+        let bar_left = snap(x + (size.width - bars.len() as f32 * bar_module) / 2.0);
+        for (start, length) in runs(&bars) {
+            sheet.rect(
+                bar_left + start as f32 * bar_module,
+                bar_top,
+                length as f32 * bar_module,
+                bar_height,
+            );
+        }
+
+        let top = y + MARGIN;
+        let top_height = bar_top - 1.0 - top;
+        let qr = QrCode::with_error_correction_level(self.qr_payload(), EcLevel::M)
+            .context("Could not make the QR code")?;
+        let qr_size = qr.width() + 2 * QR_QUIET_ZONE;
+        let qr_module = module(top_height.min(inner_width * 0.45), qr_size);
+        if qr_module <= 0.0 {
+            bail!(
+                "The QR code of {} doesn't fit on a {} mm high label at {dpi} dpi, use a higher label or a higher --dpi",
+                self.item_id,
+                size.height
+            );
+        }
+        let qr_left = snap(x + MARGIN) + QR_QUIET_ZONE as f32 * qr_module;
+        let qr_top = snap(top) + QR_QUIET_ZONE as f32 * qr_module;
+        for (row, colors) in qr.to_colors().chunks(qr.width()).enumerate() {
+            let dark: Vec<bool> = colors.iter().map(|color| *color == Color::Dark).collect();
+            for (start, length) in runs(&dark) {
+                sheet.rect(
+                    qr_left + start as f32 * qr_module,
+                    qr_top + row as f32 * qr_module,
+                    length as f32 * qr_module,
+                    qr_module,
+                );
+            }
+        }
+
+        let text_left = snap(x + MARGIN) + qr_size as f32 * qr_module;
+        let text_width = x + size.width - MARGIN - text_left;
+        let heading = (top_height / 5.5).min(6.0);
+        let line = heading * 0.75;
+        let mut baseline = top + heading * 0.7;
+        sheet.text(
+            text_left,
+            baseline,
+            heading,
+            &fit(&self.product_name, heading, text_width),
+        );
+        let details = [
+            self.item_code.as_ref().map(|code| format!("Code: {code}")),
+            Some(format!("Product: {}", self.product_id)),
+            self.size.as_ref().map(|size| format!("Size: {size}")),
+            self.serial_number
+                .as_ref()
+                .map(|serial| format!("S/N: {serial}")),
+        ];
+        for detail in details.into_iter().flatten() {
+            baseline += line * 1.4;
+            sheet.text(text_left, baseline, line, &fit(&detail, line, text_width));
+        }
+
+        Ok(())
+    }
+}
+
+/// Lays the labels out on a sheet, `columns` labels side by side
+pub fn sheet(labels: &[Label], size: LabelSize, columns: usize, dpi: u32) -> Result<Sheet> {
+    ensure!(!labels.is_empty(), "There are no labels to print");
+    ensure!(columns > 0, "A sheet needs at least one column");
+    ensure!(dpi > 0, "The resolution has to be at least 1 dpi");
+
+    let columns = columns.min(labels.len());
+    let rows = labels.len().div_ceil(columns);
+    let mut sheet = Sheet::new(columns as f32 * size.width, rows as f32 * size.height);
+    for (index, label) in labels.iter().enumerate() {
+        let x = (index % columns) as f32 * size.width;
+        let y = (index / columns) as f32 * size.height;
+        label.draw(&mut sheet, x, y, size, dpi)?;
+    }
+
+    Ok(sheet)
+}
+
+/// The start and length of every run of `true`
+fn runs(modules: &[bool]) -> Vec<(usize, usize)> {
+    let mut runs: Vec<(usize, usize)> = Vec::new();
+    for (index, _) in modules.iter().enumerate().filter(|(_, dark)| **dark) {
+        match runs.last_mut() {
+            Some((start, length)) if *start + *length == index => *length += 1,
+            _ => runs.push((index, 1)),
+        }
+    }
+    runs
+}
+
+/// Cuts the text to the characters that fit in `width` at font size `size`
+fn fit(text: &str, size: f32, width: f32) -> String {
+    let characters = (width / (0.6 * size)).floor().max(0.0) as usize;
+    text.chars().take(characters).collect()
+}
+
+/// Writes labels for the items matching the query
+///
+/// Every item gets a label when the query is empty.
+#[cfg(feature = "python-db")]
+pub fn write_labels(db_handler: &DBHandler, args: LabelsArgs) -> Result<()> {
+    let mut query = SearchQuery::from_args(&args.query)?;
+    if let Some(product_id) = args.product {
+        query.qualifiers.push(Qualifier {
+            field: "product".to_string(),
+            op: ":".to_string(),
+            value: product_id,
+        });
+    }
+    let labels: Vec<Label> = db_handler
+        .find_items(query)?
+        .iter()
+        .map(Label::from)
+        .collect();
+    if labels.is_empty() {
+        bail!("No items match the query, there are no labels to print");
+    }
+
+    let sheet = sheet(&labels, args.size, args.columns as usize, args.dpi)?;
+    let format = match args.label_format {
+        Some(format) => format,
+        None => LabelFormat::from_path(&args.file)?,
+    };
+    let contents = match format {
+        LabelFormat::Svg => sheet.to_svg().into_bytes(),
+        LabelFormat::Png => sheet.to_png(args.dpi)?,
+    };
+    std::fs::write(&args.file, contents)
+        .with_context(|| format!("Could not write {}", args.file.display()))?;
+    println!("Wrote {} labels to {}", labels.len(), args.file.display());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn label(item_id: &str) -> Label {
+        Label {
+            item_id: item_id.to_string(),
+            product_id: "FC-KNF".to_string(),
+            product_name: "Combat Knife".to_string(),
+            size: Some("M".to_string()),
+            serial_number: None,
+            item_code: None,
+        }
+    }
+
+    const ITEM_ID: &str = "0b6c5ff4-22c1-11ef-a5a3-0242ac120002";
+
+    #[test]
+    fn test_parse_label_size() {
+        assert_eq!(
+            parse_label_size("62X29").unwrap(),
+            LabelSize {
+                width: 62.0,
+                height: 29.0
+            }
+        );
+        assert!(parse_label_size("62").is_err());
+        assert!(parse_label_size("62xwide").is_err());
+        assert!(parse_label_size("20x10").is_err());
+    }
+
+    #[test]
+    fn test_svg() {
+        let sheet = sheet(
+            &[label(ITEM_ID), label(ITEM_ID), label(ITEM_ID)],
+            LabelSize::default(),
+            2,
+            300,
+        )
+        .unwrap();
+        let svg = sheet.to_svg();
+
+        assert!(svg.starts_with(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"200mm\" height=\"100mm\""
+        ));
+        assert!(svg.contains(&format!(">{ITEM_ID}</text>")));
+        assert!(svg.contains(">Combat Knife</text>"));
+        assert!(svg.contains(">Size: M</text>"));
+        assert!(!svg.contains("S/N"));
+    }
+
+    #[test]
+    fn test_png_bars_are_whole_dots() {
+        let size = LabelSize {
+            width: 62.0,
+            height: 29.0,
+        };
+        let image = sheet(&[label(ITEM_ID)], size, 1, 203)
+            .unwrap()
+            .to_image(203);
+        assert_eq!((image.width, image.height), (496, 232));
+
+        // A row through the middle of the barcode
+        let y = (image.height as f32 * 0.7) as usize;
+        let row: Vec<bool> = (0..image.width).map(|x| image.pixel(x, y) == 0).collect();
+        let bars = code128::encode(ITEM_ID).unwrap();
+        let module = runs(&row)[0].1 / runs(&bars)[0].1;
+        assert!(module >= 1);
+        let expected: Vec<(usize, usize)> = runs(&bars)
+            .into_iter()
+            .map(|(start, length)| (start * module, length * module))
+            .collect();
+        let first = runs(&row)[0].0;
+        let found: Vec<(usize, usize)> = runs(&row)
+            .into_iter()
+            .map(|(start, length)| (start - first, length))
+            .collect();
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn test_label_too_small() {
+        let size = LabelSize {
+            width: 25.0,
+            height: 15.0,
+        };
+        assert!(sheet(&[label(ITEM_ID)], size, 1, 100).is_err());
+        assert!(sheet(&[], LabelSize::default(), 1, 300).is_err());
+    }
+}
@@ -0,0 +1,162 @@
+use anyhow::Result;
+
+use crate::labels::font;
+use crate::png::GrayImage;
+
+/// The number of millimetres in an inch, printer resolutions are given in dots per inch
+pub const MM_PER_INCH: f32 = 25.4;
+
+/// A page of labels in millimetres from the top left corner, drawn in black on white
+#[derive(Debug, Clone)]
+pub struct Sheet {
+    pub width: f32,
+    pub height: f32,
+    shapes: Vec<Shape>,
+}
+
+#[derive(Debug, Clone)]
+enum Shape {
+    Rect {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+    },
+    /// A line of monospaced text, every character is 0.6 times `size` wide
+    Text {
+        x: f32,
+        baseline: f32,
+        size: f32,
+        text: String,
+    },
+}
+
+impl Sheet {
+    pub fn new(width: f32, height: f32) -> Self {
+        Self {
+            width,
+            height,
+            shapes: Vec::new(),
+        }
+    }
+
+    /// Fills a rectangle
+    pub fn rect(&mut self, x: f32, y: f32, width: f32, height: f32) {
+        self.shapes.push(Shape::Rect {
+            x,
+            y,
+            width,
+            height,
+        });
+    }
+
+    /// Draws a line of text with its baseline starting at `x`, `baseline`
+    pub fn text(&mut self, x: f32, baseline: f32, size: f32, text: &str) {
+        self.shapes.push(Shape::Text {
+            x,
+            baseline,
+            size,
+            text: text.to_string(),
+        });
+    }
+
+    pub fn to_svg(&self) -> String {
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}mm\" height=\"{1}mm\" viewBox=\"0 0 {0} {1}\">\n<rect width=\"{0}\" height=\"{1}\" fill=\"#fff\"/>\n",
+            number(self.width),
+            number(self.height)
+        );
+        for shape in &self.shapes {
+            match shape {
+                Shape::Rect {
+                    x,
+                    y,
+                    width,
+                    height,
+                } => svg.push_str(&format!(
+                    "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>\n",
+                    number(*x),
+                    number(*y),
+                    number(*width),
+                    number(*height)
+                )),
+                Shape::Text {
+                    x,
+                    baseline,
+                    size,
+                    text,
+                } => svg.push_str(&format!(
+                    "<text x=\"{}\" y=\"{}\" font-family=\"monospace\" font-size=\"{}\">{}</text>\n",
+                    number(*x),
+                    number(*baseline),
+                    number(*size),
+                    escape(text)
+                )),
+            }
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Rasterizes the sheet at `dpi` dots per inch
+    ///
+    /// Edges are rounded to the nearest pixel, so shapes that are a whole number of pixels wide
+    /// keep their exact width wherever they are placed.
+    pub fn to_image(&self, dpi: u32) -> GrayImage {
+        let scale = dpi as f32 / MM_PER_INCH;
+        let pixel = |mm: f32| (mm * scale).round().max(0.0) as usize;
+
+        let mut image = GrayImage::new(pixel(self.width), pixel(self.height));
+        for shape in &self.shapes {
+            match shape {
+                Shape::Rect {
+                    x,
+                    y,
+                    width,
+                    height,
+                } => image.fill(pixel(*x), pixel(*y), pixel(x + width), pixel(y + height)),
+                Shape::Text {
+                    x,
+                    baseline,
+                    size,
+                    text,
+                } => {
+                    // A glyph cell is a tenth of the font size, which makes capitals 0.7 high
+                    let cell = size / 10.0;
+                    let top = baseline - font::ROWS as f32 * cell;
+                    for (index, c) in text.chars().enumerate() {
+                        let left = x + index as f32 * (font::COLUMNS + 1) as f32 * cell;
+                        for (row, bits) in font::glyph(c).into_iter().enumerate() {
+                            for column in 0..font::COLUMNS {
+                                if bits & (1 << (font::COLUMNS - 1 - column)) == 0 {
+                                    continue;
+                                }
+                                let x = left + column as f32 * cell;
+                                let y = top + row as f32 * cell;
+                                image.fill(pixel(x), pixel(y), pixel(x + cell), pixel(y + cell));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        image
+    }
+
+    pub fn to_png(&self, dpi: u32) -> Result<Vec<u8>> {
+        self.to_image(dpi).to_png()
+    }
+}
+
+/// Formats a length without trailing zeros
+fn number(value: f32) -> String {
+    let text = format!("{value:.3}");
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -1,22 +1,284 @@
 #[cfg(feature = "python-db")]
 mod python_impl;
+#[cfg(feature = "python-db")]
+pub mod receipt;
+pub mod rules;
+pub mod scan;
+pub mod simulation;
 
-use crate::cli::InsertLoanArgs;
-use chrono::{Datelike, NaiveDate};
+use crate::cli::{InsertLoanArgs, SimulationArgs};
+use crate::import::ImportRecords;
+use crate::leandings::rules::{get_checkout_rules, CheckoutRules, RuleViolation};
+use crate::leandings::simulation::Simulation;
+use crate::operators::SignOff;
+use crate::query::QueryOptions;
+use crate::search::SearchQuery;
+use crate::users::User;
+use anyhow::bail;
+use chrono::{Days, Local, NaiveDate};
 use rand::Rng;
+use uuid::Builder;
 
 #[cfg(feature = "python-db")]
 use crate::python_db_handler::DBHandlerPy as DBHandler;
 
-pub fn insert_leandings(db_handler: &DBHandler, num_leandings: usize) -> anyhow::Result<()> {
+/// Inserts random loans of the items that are not lent out
+///
+/// The users and available items are read once, in a fixed order, and picked with `rng`. Running
+/// this with the same seed and `today` against the same data therefore gives the same loans.
+/// Open loans that would break the checkout rules are left out.
+pub fn insert_leandings(
+    db_handler: &DBHandler,
+    num_leandings: usize,
+    today: NaiveDate,
+    rng: &mut impl Rng,
+) -> anyhow::Result<()> {
+    let users: Vec<User> = db_handler
+        .get_users(QueryOptions::default())?
+        .into_iter()
+        .filter(|user| user.may_borrow_on(today))
+        .collect();
+    let mut items: Vec<String> = available_items(db_handler)?
+        .into_iter()
+        .map(|(item_id, _)| item_id)
+        .collect();
+
+    if users.is_empty() {
+        bail!("There are no users to lend items to!");
+    }
+
+    let mut refused = 0;
     for _ in 0..num_leandings {
-        let leanding = Loans::new_random()?;
+        if items.is_empty() {
+            bail!("No item available to borrow!");
+        }
+        let idx = rng.gen_range(0..items.len());
+        let ssn = users[rng.gen_range(0..users.len())].ssn.clone();
+
+        let leanding = Loans::new_random(ssn, items[idx].clone(), today, rng);
+        if db_handler
+            .check_checkout_rules([&leanding])?
+            .iter()
+            .any(|violations| !violations.is_empty())
+        {
+            refused += 1;
+            continue;
+        }
+        if leanding.return_date.is_none() {
+            items.swap_remove(idx);
+        }
         db_handler.insert_loan(leanding)?;
     }
 
+    if refused > 0 {
+        println!("Left out {refused} loans that would break the checkout rules");
+    }
     Ok(())
 }
 
+/// Lends out an item, refusing if the loan breaks any of the checkout rules
+///
+/// Items of regulated types also need `witness`, a second operator who signs off the checkout.
+/// The rules are checked as of today whatever borrowing date the loan has, so backdating a loan
+/// doesn't get around them. Loans returned by today are records of the past and are inserted
+/// without checking the rules, but regulated items still need the sign-off.
+pub fn checkout(
+    db_handler: &DBHandler,
+    loan: Loans,
+    witness: Option<String>,
+) -> anyhow::Result<()> {
+    let today = Local::now().date_naive();
+    let rules = get_checkout_rules()?;
+    let facts = db_handler.get_checkout_facts(&loan.ssn, &loan.item_id)?;
+
+    let returned = loan
+        .return_date
+        .is_some_and(|return_date| return_date <= today);
+    let violations = if returned {
+        Vec::new()
+    } else {
+        rules.check(&facts, today, db_handler.session())
+    };
+    if !violations.is_empty() {
+        bail!(
+            "The loan breaks the checkout rules:\n{}",
+            violations
+                .iter()
+                .map(RuleViolation::to_string)
+                .map(|violation| format!("  - {violation}"))
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+    }
+
+    match sign_off(db_handler, &rules, &facts.product_type, witness)? {
+        Some(sign_off) => db_handler.insert_signed_loan(loan, &sign_off),
+        None => db_handler.insert_loan(loan),
+    }
+}
+
+/// Returns an item, items of regulated types need `witness` to sign off the return
+///
+/// The item can be given by its ID or by its item code.
+pub fn return_item(
+    db_handler: &DBHandler,
+    item_id: String,
+    witness: Option<String>,
+) -> anyhow::Result<()> {
+    let item_id = db_handler.resolve_item_id(&item_id)?;
+    let Some(item) = db_handler
+        .find_items(SearchQuery::by("id", &item_id))?
+        .into_iter()
+        .next()
+    else {
+        bail!("No item with ID {item_id} exists!");
+    };
+
+    match sign_off(
+        db_handler,
+        &get_checkout_rules()?,
+        &item.product_type,
+        witness,
+    )? {
+        Some(sign_off) => db_handler.return_signed_item(item_id, &sign_off),
+        None => db_handler.return_item(item_id),
+    }
+}
+
+/// Changes the dates of a loan
+///
+/// Giving an open loan of a regulated item a return date returns the item, so `witness` has to
+/// sign it off like with [`return_item`].
+pub fn update_loan(
+    db_handler: &DBHandler,
+    lending_id: String,
+    borrow_date: Option<NaiveDate>,
+    return_date: Option<NaiveDate>,
+    witness: Option<String>,
+) -> anyhow::Result<()> {
+    let Some(return_date) = return_date else {
+        return db_handler.update_loan(lending_id, borrow_date, None);
+    };
+    let Some(loan) = db_handler
+        .find_loans(SearchQuery::by("id", &lending_id))?
+        .into_iter()
+        .next()
+    else {
+        bail!("No lending with ID {lending_id} exists!");
+    };
+    if loan.return_date.is_some() {
+        return db_handler.update_loan(lending_id, borrow_date, Some(return_date));
+    }
+
+    let Some(item) = db_handler
+        .find_items(SearchQuery::by("id", &loan.item_id))?
+        .into_iter()
+        .next()
+    else {
+        bail!("No item with ID {} exists!", loan.item_id);
+    };
+    match sign_off(
+        db_handler,
+        &get_checkout_rules()?,
+        &item.product_type,
+        witness,
+    )? {
+        Some(sign_off) => {
+            db_handler.update_signed_loan(lending_id, borrow_date, return_date, &sign_off)
+        }
+        None => db_handler.update_loan(lending_id, borrow_date, Some(return_date)),
+    }
+}
+
+/// Asks the witness for their password if items of the product type are regulated
+fn sign_off(
+    db_handler: &DBHandler,
+    rules: &CheckoutRules,
+    product_type: &str,
+    witness: Option<String>,
+) -> anyhow::Result<Option<SignOff>> {
+    if !rules.is_regulated(product_type) {
+        return Ok(None);
+    }
+    let Some(witness) = witness else {
+        bail!("{product_type} items are regulated, a second operator has to sign off with --witness <USERNAME>");
+    };
+
+    let password = rpassword::prompt_password(format!("Password for the witness {witness}: "))?;
+    Ok(Some(db_handler.witness(&witness, &password)?))
+}
+
+/// Inserts a simulated loan history ending on `today`, see [`Simulation`]
+///
+/// Only items that are not lent out take part, so the history never conflicts with the loans
+/// already in the database. Open loans that would break the checkout rules are left out, the
+/// others are inserted in a single transaction.
+pub fn insert_simulated_leandings(
+    db_handler: &DBHandler,
+    num_leandings: usize,
+    args: &SimulationArgs,
+    today: NaiveDate,
+    rng: &mut impl Rng,
+) -> anyhow::Result<()> {
+    let simulation = Simulation::new(num_leandings, args, today)?;
+    // Open loans cannot be made for users that may not borrow, so they are left out entirely
+    let users: Vec<String> = db_handler
+        .get_users(QueryOptions::default())?
+        .into_iter()
+        .filter(|user| user.may_borrow_on(today))
+        .map(|user| user.ssn)
+        .collect();
+    let items = available_items(db_handler)?;
+
+    let leandings = simulation.run(&users, &items, rng)?;
+    let violations = db_handler.check_checkout_rules(&leandings)?;
+    let (leandings, refused): (Vec<_>, Vec<_>) = leandings
+        .into_iter()
+        .zip(violations)
+        .partition(|(_, violations)| violations.is_empty());
+    let leandings: Vec<Loans> = leandings
+        .into_iter()
+        .map(|(leanding, _)| leanding)
+        .collect();
+    let open = leandings
+        .iter()
+        .filter(|leanding| leanding.return_date.is_none())
+        .count();
+
+    println!(
+        "Simulated {} loans from {} to {}, {} of them are still open",
+        leandings.len(),
+        simulation.start,
+        simulation.end(),
+        open
+    );
+    if !refused.is_empty() {
+        println!(
+            "Left out {} open loans that would break the checkout rules",
+            refused.len()
+        );
+    }
+    db_handler.import_records(ImportRecords::Loans(leandings))?;
+
+    Ok(())
+}
+
+/// The item and product IDs of every item that is not lent out, sorted by item ID
+///
+/// Regulated items are left out, generated loans have no sign-off.
+fn available_items(db_handler: &DBHandler) -> anyhow::Result<Vec<(String, String)>> {
+    let rules = get_checkout_rules()?;
+    let mut items: Vec<(String, String)> = db_handler
+        .find_items(SearchQuery::by("available", "yes"))?
+        .into_iter()
+        .filter(|item| !rules.is_regulated(&item.product_type))
+        .map(|item| (item.item_id, item.product_id))
+        .collect();
+    items.sort();
+
+    Ok(items)
+}
+
 #[derive(Debug)]
 #[cfg_attr(feature = "python-db", derive(pyo3::FromPyObject))]
 #[cfg_attr(feature = "mysql-db", derive(sqlx::FromRow))]
@@ -45,49 +307,48 @@ impl Loans {
         }
     }
 
-    pub fn generate_random_date(start_date: Option<NaiveDate>) -> NaiveDate {
-        let mut rng = rand::thread_rng();
-        let start_year = start_date.map(|d| d.year()).unwrap_or(1950);
-
-        // Define the range of years, for example, from start_year to 2024
-        let year = rng.gen_range(start_year..2024);
-        let month = rng.gen_range(1..=12);
-        let day = match month {
-            4 | 6 | 9 | 11 => rng.gen_range(1..=30),
-            2 => {
-                if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) {
-                    rng.gen_range(1..=29)
-                } else {
-                    rng.gen_range(1..=28)
-                }
-            }
-            _ => rng.gen_range(1..=31),
-        };
+    /// A random date from `start_date`, or 1950 if there is none, up to and including `today`
+    pub fn generate_random_date(
+        start_date: Option<NaiveDate>,
+        today: NaiveDate,
+        rng: &mut impl Rng,
+    ) -> NaiveDate {
+        let start_date = start_date
+            .unwrap_or_else(|| NaiveDate::from_ymd_opt(1950, 1, 1).expect("a valid date"))
+            .min(today);
+        let days = (today - start_date).num_days() as u64;
 
-        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+        start_date + Days::new(rng.gen_range(0..=days))
     }
 
-    pub fn new_random() -> anyhow::Result<Self> {
-        let db = DBHandler::new()?;
-        let product = db.get_rand_item()?;
-        let borrowing_date = Loans::generate_random_date(None);
-        let user = db.get_rand_user()?;
+    /// A loan of the item to the user with random dates up to `today`, the ID is generated from
+    /// `rng` as well
+    ///
+    /// The item is returned on or after the day it was borrowed, if it is returned at all.
+    pub fn new_random(ssn: String, item_id: String, today: NaiveDate, rng: &mut impl Rng) -> Self {
+        let leanding_id = Builder::from_random_bytes(rng.gen())
+            .into_uuid()
+            .to_string();
+        let borrowing_date = Loans::generate_random_date(None, today, rng);
 
         // randomly choose if the item is reutrned or not
-        let mut rng = rand::thread_rng();
         let return_date = if rng.gen_bool(0.2) {
-            Some(Loans::generate_random_date(Some(borrowing_date)))
+            Some(Loans::generate_random_date(
+                Some(borrowing_date),
+                today,
+                rng,
+            ))
         } else {
             None
         };
 
-        Ok(Self {
-            leanding_id: String::new(),
-            ssn: user.ssn,
-            item_id: product.item_id,
+        Self {
+            leanding_id,
+            ssn,
+            item_id,
             borrowing_date,
             return_date,
-        })
+        }
     }
 }
 
@@ -102,3 +363,29 @@ impl From<InsertLoanArgs> for Loans {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::seeded_rng;
+
+    #[test]
+    fn test_random_loans_end_by_today() {
+        let today = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        let mut rng = seeded_rng(Some(7));
+
+        for _ in 0..500 {
+            let loan = Loans::new_random(
+                "811218-9876".to_string(),
+                "item".to_string(),
+                today,
+                &mut rng,
+            );
+
+            assert!(loan.borrowing_date <= today);
+            if let Some(return_date) = loan.return_date {
+                assert!(loan.borrowing_date <= return_date && return_date <= today);
+            }
+        }
+    }
+}
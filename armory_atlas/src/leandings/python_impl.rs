@@ -1,7 +1,8 @@
-use chrono::NaiveDate;
+use chrono::{Local, NaiveDate};
 use pyo3::pymethods;
 
 use crate::leandings::Loans;
+use crate::python_db_handler::DBHandlerPy as DBHandler;
 
 #[pymethods]
 impl Loans {
@@ -23,12 +24,25 @@ impl Loans {
 
     #[staticmethod]
     pub fn py_generate_random_date(start_date: Option<NaiveDate>) -> NaiveDate {
-        Self::generate_random_date(start_date)
+        Self::generate_random_date(
+            start_date,
+            Local::now().date_naive(),
+            &mut rand::thread_rng(),
+        )
     }
 
     #[staticmethod]
     pub fn py_new_random() -> anyhow::Result<Self> {
-        Self::new_random()
+        let db = DBHandler::new()?;
+        let item = db.get_rand_item()?;
+        let user = db.get_rand_user()?;
+
+        Ok(Self::new_random(
+            user.ssn,
+            item.item_id,
+            Local::now().date_naive(),
+            &mut rand::thread_rng(),
+        ))
     }
 
     #[pyo3(name = "__repr__")]
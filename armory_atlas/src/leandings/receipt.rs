@@ -0,0 +1,364 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use chrono::{Local, NaiveDate};
+use clap::ValueEnum;
+
+use crate::cli::HandOverDocumentArgs;
+use crate::leandings::rules::get_checkout_rules;
+use crate::pdf::{self, Font, Page, PAGE_HEIGHT, PAGE_WIDTH};
+use crate::products;
+use crate::python_db_handler::loans::{DetailedLoan, DetailedLoans};
+use crate::python_db_handler::DBHandlerPy as DBHandler;
+use crate::query::QueryOptions;
+use crate::users::ssn::{mask_ssn, mask_unless};
+
+/// The file formats a hand-over receipt can be written in
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReceiptFormat {
+    /// Plain text that fits on 90 columns
+    Text,
+    /// An A4 PDF
+    Pdf,
+}
+
+impl ReceiptFormat {
+    /// Guesses the format from the file extension
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_lowercase);
+
+        match extension.as_deref() {
+            Some("txt" | "text") => Ok(Self::Text),
+            Some("pdf") => Ok(Self::Pdf),
+            _ => bail!(
+                "Can't tell the format of {} from its extension, use --receipt-format",
+                path.display()
+            ),
+        }
+    }
+}
+
+/// An item on a hand-over receipt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReceiptLine {
+    /// The item code, or the item ID of items without a code
+    pub item: String,
+    pub product_name: String,
+    pub size: String,
+    pub borrow_date: String,
+    pub due_date: Option<NaiveDate>,
+}
+
+/// The form a borrower signs when items are handed over
+///
+/// Made from the loans of one user, usually the ones made in a single checkout session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Receipt {
+    pub ssn: String,
+    pub name: String,
+    pub date: NaiveDate,
+    /// The operator that handed the items over
+    pub operator: Option<String>,
+    pub lines: Vec<ReceiptLine>,
+}
+
+const ACKNOWLEDGEMENT: [&str; 2] = [
+    "By signing, the borrower confirms receiving the items above in working order",
+    "and agrees to return them by their due date.",
+];
+
+const TITLE: &str = "Armory Atlas - Hand-over Receipt";
+
+const LINES_PER_PAGE: usize = 60;
+
+impl Receipt {
+    /// Builds the receipt for the loans, which all have to belong to the same user
+    ///
+    /// # Arguments
+    ///
+    /// * `loans`: The loans to list on the receipt.
+    /// * `date`: The day the items are handed over.
+    /// * `operator`: The operator handing the items over.
+    /// * `due_date`: The day a loan has to be returned by, if it has one.
+    ///
+    pub fn new(
+        loans: &[DetailedLoan],
+        date: NaiveDate,
+        operator: Option<&str>,
+        due_date: impl Fn(&DetailedLoan) -> Option<NaiveDate>,
+    ) -> Result<Self> {
+        let Some(first) = loans.first() else {
+            bail!("There are no loans to make a receipt for");
+        };
+        if loans.iter().any(|loan| loan.ssn != first.ssn) {
+            bail!("A receipt can only list the loans of one user");
+        }
+
+        Ok(Self {
+            ssn: first.ssn.clone(),
+            name: first.name.clone(),
+            date,
+            operator: operator.map(str::to_string),
+            lines: loans
+                .iter()
+                .map(|loan| ReceiptLine {
+                    item: loan
+                        .item_code
+                        .clone()
+                        .unwrap_or_else(|| loan.item_id.clone()),
+                    product_name: loan.product_name.clone(),
+                    size: loan.size.clone(),
+                    borrow_date: loan.borrow_date.clone(),
+                    due_date: due_date(loan),
+                })
+                .collect(),
+        })
+    }
+
+    /// Everything below the title, one line per entry
+    fn body(&self) -> Vec<String> {
+        let mut body = vec![
+            format!("Borrower:   {}", self.name),
+            format!("SSN:        {}", self.ssn),
+            format!("Date:       {}", self.date),
+            format!("Issued by:  {}", self.operator.as_deref().unwrap_or("")),
+            String::new(),
+        ];
+
+        let rows: Vec<[String; 5]> = self
+            .lines
+            .iter()
+            .map(|line| {
+                [
+                    line.item.clone(),
+                    line.product_name.clone(),
+                    line.size.clone(),
+                    line.borrow_date.clone(),
+                    line.due_date
+                        .map(|date| date.to_string())
+                        .unwrap_or_else(|| "-".to_string()),
+                ]
+            })
+            .collect();
+        let header = ["Item", "Product", "Size", "Borrowed", "Due"].map(str::to_string);
+        let widths: Vec<usize> = (0..header.len())
+            .map(|column| {
+                std::iter::once(&header)
+                    .chain(&rows)
+                    .map(|row| row[column].chars().count())
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect();
+        let format_row = |row: &[String; 5]| {
+            row.iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{cell:<width$}"))
+                .collect::<Vec<String>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        };
+
+        let dashes: [String; 5] = std::array::from_fn(|column| "-".repeat(widths[column]));
+        body.push(format_row(&header));
+        body.push(format_row(&dashes));
+        body.extend(rows.iter().map(format_row));
+        body.push(String::new());
+        body.push(format!("Items: {}", self.lines.len()));
+        body.push(String::new());
+        body.extend(ACKNOWLEDGEMENT.iter().map(|line| line.to_string()));
+        for signer in ["Borrower", "Operator"] {
+            body.push(String::new());
+            body.push(String::new());
+            body.push(format!(
+                "{:<20}______________________________   Date: ____________",
+                format!("{signer} signature:")
+            ));
+        }
+        body
+    }
+
+    /// The receipt as plain text
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{TITLE}\n{}\n\n", "=".repeat(TITLE.len()));
+        for line in self.body() {
+            text.push_str(&line);
+            text.push('\n');
+        }
+        text
+    }
+
+    /// The receipt as a PDF, continued on more pages if it lists many items
+    pub fn to_pdf(&self) -> Vec<u8> {
+        const MARGIN: f32 = 50.0;
+        const FONT_SIZE: f32 = 9.0;
+        const LEADING: f32 = 12.0;
+
+        let body = self.body();
+        let pages: Vec<Page> = body
+            .chunks(LINES_PER_PAGE)
+            .enumerate()
+            .map(|(number, lines)| {
+                let mut page = Page::new();
+                let mut y = PAGE_HEIGHT - MARGIN;
+                if number == 0 {
+                    page.text(Font::HelveticaBold, 16.0, MARGIN, y, TITLE);
+                    y -= 10.0;
+                    page.line((MARGIN, y), (PAGE_WIDTH - MARGIN, y));
+                    y -= 2.0 * LEADING;
+                }
+                for line in lines {
+                    page.text(Font::Courier, FONT_SIZE, MARGIN, y, line);
+                    y -= LEADING;
+                }
+                page.text(
+                    Font::Helvetica,
+                    8.0,
+                    MARGIN,
+                    MARGIN / 2.0,
+                    &format!("{} - {} - page {}", self.name, self.date, number + 1),
+                );
+                page
+            })
+            .collect();
+
+        pdf::write(&pages)
+    }
+
+    /// Renders the receipt and writes it to `path`, replacing the file if it exists
+    pub fn write(&self, path: &Path, format: ReceiptFormat) -> Result<()> {
+        let contents = match format {
+            ReceiptFormat::Text => self.to_text().into_bytes(),
+            ReceiptFormat::Pdf => self.to_pdf(),
+        };
+        std::fs::write(path, contents)
+            .with_context(|| format!("Could not write {}", path.display()))
+    }
+}
+
+/// Writes the hand-over receipt for the open loans of a user
+///
+/// Lists the items given with `--item`, or every item the user borrowed on the day. The due dates
+/// come from the loan periods of the checkout rules.
+pub fn hand_over(db_handler: &DBHandler, args: HandOverDocumentArgs, show_ssn: bool) -> Result<()> {
+    let date = args.date.unwrap_or_else(|| Local::now().date_naive());
+    let borrowed_on = date.to_string();
+    let items = args
+        .items
+        .iter()
+        .map(|item| db_handler.resolve_item_id(item))
+        .collect::<Result<Vec<_>>>()?;
+    let loans: Vec<DetailedLoan> = db_handler
+        .user_all_borrowed(args.ssn.clone(), QueryOptions::default())?
+        .into_iter()
+        .filter(|loan| loan.return_date.is_none())
+        .filter(|loan| match items.is_empty() {
+            true => loan.borrow_date == borrowed_on,
+            false => items.contains(&loan.item_id),
+        })
+        .collect();
+    if loans.is_empty() {
+        bail!(
+            "{} has no open loans to put on a receipt",
+            mask_ssn(&args.ssn)
+        );
+    }
+    let loans: Vec<DetailedLoan> = mask_unless(DetailedLoans::from(loans), show_ssn).into();
+
+    let rules = get_checkout_rules()?;
+    let products = products::get_products()?;
+    let types: HashMap<&str, &str> = products
+        .iter()
+        .map(|product| (product.product_name.as_str(), product.product_type.as_str()))
+        .collect();
+
+    let receipt = Receipt::new(&loans, date, db_handler.session().username(), |loan| {
+        let borrowed = loan.borrow_date.parse().ok()?;
+        let product_type = types.get(loan.product_name.as_str()).copied();
+        rules.due_date(product_type.unwrap_or_default(), borrowed)
+    })?;
+
+    let format = match args.receipt_format {
+        Some(format) => format,
+        None => ReceiptFormat::from_path(&args.file)?,
+    };
+    receipt.write(&args.file, format)?;
+    println!("Wrote {}", args.file.display());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn loan(item_id: &str, ssn: &str, product_name: &str) -> DetailedLoan {
+        DetailedLoan {
+            lending_id: format!("loan-{item_id}"),
+            ssn: ssn.to_string(),
+            name: "Åsa Öberg".to_string(),
+            item_id: item_id.to_string(),
+            product_name: product_name.to_string(),
+            size: "M".to_string(),
+            borrow_date: "2024-06-01".to_string(),
+            return_date: None,
+            item_code: None,
+        }
+    }
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 6, day).unwrap()
+    }
+
+    #[test]
+    fn test_text() {
+        let mut loans = [
+            loan("item-1", "19811218-9876", "Combat Knife"),
+            loan("item-2", "19811218-9876", "Tactical Boots"),
+        ];
+        loans[1].item_code = Some("TB-0002-2".to_string());
+        let receipt = Receipt::new(&loans, date(1), Some("anna"), |loan| {
+            (loan.product_name == "Combat Knife").then(|| date(8))
+        })
+        .unwrap();
+        let text = receipt.to_text();
+
+        assert!(text.starts_with("Armory Atlas - Hand-over Receipt\n====="));
+        assert!(text.contains("Borrower:   Åsa Öberg\n"));
+        assert!(text.contains("Issued by:  anna\n"));
+        assert!(text.contains("Item       Product         Size  Borrowed    Due\n"));
+        assert!(text.contains("item-1     Combat Knife    M     2024-06-01  2024-06-08\n"));
+        assert!(text.contains("TB-0002-2  Tactical Boots  M     2024-06-01  -\n"));
+        assert!(text.contains("Borrower signature: ______"));
+    }
+
+    #[test]
+    fn test_one_user_per_receipt() {
+        assert!(Receipt::new(&[], date(1), None, |_| None).is_err());
+
+        let loans = [
+            loan("item-1", "19811218-9876", "Combat Knife"),
+            loan("item-2", "19900101-1234", "Combat Knife"),
+        ];
+        assert!(Receipt::new(&loans, date(1), None, |_| None).is_err());
+    }
+
+    #[test]
+    fn test_pdf_pages() {
+        let loans: Vec<DetailedLoan> = (0..80)
+            .map(|item| loan(&format!("item-{item}"), "19811218-9876", "Combat Knife"))
+            .collect();
+        let pdf = Receipt::new(&loans, date(1), None, |_| None)
+            .unwrap()
+            .to_pdf();
+        let text = String::from_utf8_lossy(&pdf);
+
+        assert!(text.contains("/Count 2 "));
+        assert!(text.contains("(item-79  Combat Knife  M     2024-06-01  -) Tj"));
+    }
+}
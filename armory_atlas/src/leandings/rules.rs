@@ -0,0 +1,371 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::io::Write;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use chrono::{Days, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::operators::{Role, Session};
+use crate::users::User;
+use crate::{CONFIG_DIR, DEFAULT_CHECKOUT_RULES, RULES_FILE};
+
+/// The rules a checkout has to pass, read from `checkout-rules.json` in the config directory
+///
+/// Every rule is optional, an empty object allows everything except lending to inactive users.
+///
+/// # Example
+///
+/// ```
+/// # use armory_atlas_lib::leandings::rules::CheckoutRules;
+/// let rules: CheckoutRules = serde_json::from_str(r#"{
+///     "MaxConcurrentLoans": 5,
+///     "TypeLimits": {"Weapon": 1},
+///     "RestrictedTypes": [{"Type": "Weapon", "Units": ["1st Company"], "OperatorRole": "quartermaster"}],
+///     "LoanPeriodDays": 30,
+///     "BlockOverdue": true
+/// }"#).unwrap();
+///
+/// assert_eq!(rules.type_limits["Weapon"], 1);
+/// ```
+///
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct CheckoutRules {
+    /// The most items a user may hold at once
+    #[serde(default)]
+    pub max_concurrent_loans: Option<usize>,
+    /// The most items of a product type a user may hold at once
+    #[serde(default)]
+    pub type_limits: BTreeMap<String, usize>,
+    /// Product types only some users may borrow
+    #[serde(default)]
+    pub restricted_types: Vec<RestrictedType>,
+    /// How many days a loan lasts before it is overdue
+    #[serde(default)]
+    pub loan_period_days: Option<u64>,
+    /// Loan periods for product types that differ from `LoanPeriodDays`
+    #[serde(default)]
+    pub loan_periods: BTreeMap<String, u64>,
+    /// Whether users with overdue items are blocked from borrowing more
+    #[serde(default)]
+    pub block_overdue: bool,
+    /// Product types whose items need a second operator to sign off every checkout and return
+    #[serde(default)]
+    pub regulated_types: Vec<String>,
+}
+
+/// A product type that only users of some units or ranks may borrow
+///
+/// Lists that are left empty do not restrict anything. `OperatorRole` is the least role the
+/// operator making the checkout needs.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct RestrictedType {
+    pub r#type: String,
+    #[serde(default)]
+    pub units: Vec<String>,
+    #[serde(default)]
+    pub ranks: Vec<String>,
+    #[serde(default)]
+    pub operator_role: Option<Role>,
+}
+
+/// What the rules are checked against, read from the database at checkout
+#[derive(Debug, Clone)]
+pub struct CheckoutFacts {
+    pub user: User,
+    /// The product type of the item that is being borrowed
+    pub product_type: String,
+    /// The items the user holds, as their product type and the date they were borrowed
+    pub held: Vec<(String, NaiveDate)>,
+}
+
+/// A rule a checkout breaks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleViolation {
+    Inactive,
+    ServiceEnded(NaiveDate),
+    TooManyLoans { limit: usize },
+    TypeLimit { product_type: String, limit: usize },
+    WrongUnit { product_type: String },
+    WrongRank { product_type: String },
+    OperatorRole { product_type: String, role: Role },
+    Overdue { items: usize },
+}
+
+impl Display for RuleViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuleViolation::Inactive => write!(f, "The user is inactive"),
+            RuleViolation::ServiceEnded(date) => write!(f, "The user's service ended {date}"),
+            RuleViolation::TooManyLoans { limit } => {
+                write!(f, "The user already holds the maximum of {limit} items")
+            }
+            RuleViolation::TypeLimit {
+                product_type,
+                limit,
+            } => write!(
+                f,
+                "The user already holds the maximum of {limit} {product_type} items"
+            ),
+            RuleViolation::WrongUnit { product_type } => {
+                write!(f, "{product_type} items may not be lent to the user's unit")
+            }
+            RuleViolation::WrongRank { product_type } => {
+                write!(f, "{product_type} items may not be lent to the user's rank")
+            }
+            RuleViolation::OperatorRole { product_type, role } => write!(
+                f,
+                "{product_type} items may only be lent out by a {role} or above"
+            ),
+            RuleViolation::Overdue { items } => {
+                write!(f, "The user has {items} overdue item(s) to return first")
+            }
+        }
+    }
+}
+
+impl CheckoutRules {
+    /// The number of days a loan of the product type lasts, `None` if loans never become overdue
+    pub fn loan_period(&self, product_type: &str) -> Option<u64> {
+        self.loan_periods
+            .get(product_type)
+            .copied()
+            .or(self.loan_period_days)
+    }
+
+    /// The day a loan of the product type borrowed on `borrowed` has to be returned by, `None` if
+    /// loans never become overdue
+    pub fn due_date(&self, product_type: &str, borrowed: NaiveDate) -> Option<NaiveDate> {
+        self.loan_period(product_type)
+            .and_then(|days| borrowed.checked_add_days(Days::new(days)))
+    }
+
+    /// Whether items of the product type need a two-person sign-off
+    pub fn is_regulated(&self, product_type: &str) -> bool {
+        self.regulated_types
+            .iter()
+            .any(|regulated| regulated == product_type)
+    }
+
+    /// Checks a checkout made on `date` by the operator of `session` against every rule
+    ///
+    /// Returns every rule that is broken, an empty list means the checkout is allowed.
+    pub fn check(
+        &self,
+        facts: &CheckoutFacts,
+        date: NaiveDate,
+        session: &Session,
+    ) -> Vec<RuleViolation> {
+        let mut violations = Vec::new();
+        let user = &facts.user;
+        let product_type = &facts.product_type;
+
+        if !user.active {
+            violations.push(RuleViolation::Inactive);
+        }
+        if let Some(end) = user.end_of_service.filter(|end| *end < date) {
+            violations.push(RuleViolation::ServiceEnded(end));
+        }
+
+        if let Some(limit) = self.max_concurrent_loans {
+            if facts.held.len() >= limit {
+                violations.push(RuleViolation::TooManyLoans { limit });
+            }
+        }
+        if let Some(&limit) = self.type_limits.get(product_type) {
+            let held = facts
+                .held
+                .iter()
+                .filter(|(held_type, _)| held_type == product_type)
+                .count();
+            if held >= limit {
+                violations.push(RuleViolation::TypeLimit {
+                    product_type: product_type.clone(),
+                    limit,
+                });
+            }
+        }
+
+        for restriction in self
+            .restricted_types
+            .iter()
+            .filter(|restriction| &restriction.r#type == product_type)
+        {
+            if !allowed(&restriction.units, user.unit.as_deref()) {
+                violations.push(RuleViolation::WrongUnit {
+                    product_type: product_type.clone(),
+                });
+            }
+            if !allowed(&restriction.ranks, user.rank.as_deref()) {
+                violations.push(RuleViolation::WrongRank {
+                    product_type: product_type.clone(),
+                });
+            }
+            if let Some(role) = restriction.operator_role {
+                if !session.has_role(role) {
+                    violations.push(RuleViolation::OperatorRole {
+                        product_type: product_type.clone(),
+                        role,
+                    });
+                }
+            }
+        }
+
+        if self.block_overdue {
+            let overdue = facts
+                .held
+                .iter()
+                .filter(|(held_type, borrowed)| self.is_overdue(held_type, *borrowed, date))
+                .count();
+            if overdue > 0 {
+                violations.push(RuleViolation::Overdue { items: overdue });
+            }
+        }
+
+        violations
+    }
+
+    fn is_overdue(&self, product_type: &str, borrowed: NaiveDate, date: NaiveDate) -> bool {
+        self.due_date(product_type, borrowed)
+            .is_some_and(|due| due < date)
+    }
+}
+
+fn allowed(allowed: &[String], value: Option<&str>) -> bool {
+    allowed.is_empty()
+        || value.is_some_and(|value| {
+            allowed
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(value))
+        })
+}
+
+/// Reads the checkout rules, creating the file with the default rules if it does not exist
+pub fn get_checkout_rules() -> Result<CheckoutRules> {
+    let rules_file_path = format!("{CONFIG_DIR}/{RULES_FILE}");
+
+    #[cfg(not(target_os = "windows"))]
+    let path = PathBuf::new().join(env!("HOME")).join(rules_file_path);
+
+    #[cfg(target_os = "windows")]
+    let path = PathBuf::new()
+        .join(env!("USERPROFILE"))
+        .join(rules_file_path);
+
+    if !path.exists() {
+        println!("Checkout rules file not found, creating it...");
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        let mut file = std::fs::File::create(&path)?;
+        file.write_all(DEFAULT_CHECKOUT_RULES.as_ref())?;
+    }
+
+    let contents = std::fs::read_to_string(&path)?;
+
+    Ok(serde_json::from_str(&contents)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 6, day).unwrap()
+    }
+
+    fn rules() -> CheckoutRules {
+        serde_json::from_str(crate::DEFAULT_CHECKOUT_RULES).unwrap()
+    }
+
+    fn facts(product_type: &str, held: &[(&str, u32)]) -> CheckoutFacts {
+        CheckoutFacts {
+            user: User {
+                unit: Some("1st Company".to_string()),
+                rank: Some("Sergeant".to_string()),
+                ..User::new("19811218-9876".to_string(), "Liam Smith".to_string())
+            },
+            product_type: product_type.to_string(),
+            held: held
+                .iter()
+                .map(|(product_type, day)| (product_type.to_string(), date(*day)))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_default_rules_allow_a_first_loan() {
+        let violations = rules().check(&facts("Weapon", &[]), date(30), &Session::Setup);
+        assert_eq!(violations, vec![]);
+    }
+
+    #[test]
+    fn test_type_limit() {
+        let violations = rules().check(
+            &facts("Weapon", &[("Weapon", 29)]),
+            date(30),
+            &Session::Setup,
+        );
+        assert_eq!(
+            violations,
+            vec![RuleViolation::TypeLimit {
+                product_type: "Weapon".to_string(),
+                limit: 1
+            }]
+        );
+    }
+
+    #[test]
+    fn test_restricted_type() {
+        let mut facts = facts("Weapon", &[]);
+        facts.user.unit = Some("Staff Company".to_string());
+        let rules = CheckoutRules {
+            restricted_types: vec![RestrictedType {
+                r#type: "Weapon".to_string(),
+                units: vec!["1st company".to_string()],
+                ranks: vec!["Sergeant".to_string()],
+                operator_role: Some(Role::Quartermaster),
+            }],
+            ..Default::default()
+        };
+        let clerk = Session::Operator {
+            username: "anna".to_string(),
+            role: Role::Clerk,
+        };
+
+        assert_eq!(
+            rules.check(&facts, date(30), &clerk),
+            vec![
+                RuleViolation::WrongUnit {
+                    product_type: "Weapon".to_string()
+                },
+                RuleViolation::OperatorRole {
+                    product_type: "Weapon".to_string(),
+                    role: Role::Quartermaster
+                }
+            ]
+        );
+
+        facts.user.unit = Some("1st Company".to_string());
+        assert_eq!(rules.check(&facts, date(30), &Session::Setup), vec![]);
+    }
+
+    #[test]
+    fn test_overdue_and_inactive() {
+        let mut facts = facts("Clothing", &[("Weapon", 1)]);
+        facts.user.active = false;
+        let rules = CheckoutRules {
+            loan_period_days: Some(30),
+            loan_periods: BTreeMap::from([("Weapon".to_string(), 7)]),
+            block_overdue: true,
+            ..Default::default()
+        };
+
+        let violations = rules.check(&facts, date(10), &Session::Setup);
+        assert_eq!(
+            violations,
+            vec![RuleViolation::Inactive, RuleViolation::Overdue { items: 1 }]
+        );
+    }
+}
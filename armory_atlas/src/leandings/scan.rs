@@ -0,0 +1,721 @@
+//! Checkouts and returns driven by a barcode scanner
+//!
+//! USB scanners type what they scan followed by Enter, so every line read is one scan. Scanning a
+//! user starts a checkout, the items scanned after it are collected and lent out together when
+//! Enter is pressed on an empty line. Items scanned without a user are returned. Every scan is
+//! answered right away, so the operator hears or sees a mistake before scanning the next item.
+
+use anyhow::Result;
+
+use crate::labels::PAYLOAD_SEPARATOR;
+use crate::users::ssn::{mask_ssn, parse_ssn};
+
+/// What a scanned or typed line means
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Scan {
+    /// The SSN of a user, in its canonical form
+    User(String),
+    /// The code of an item, as it was scanned
+    Item(String),
+    /// Lends out the scanned items, sent by pressing Enter on an empty line
+    Done,
+    /// Drops the scanned items without lending them out
+    Cancel,
+    Quit,
+}
+
+impl Scan {
+    /// Reads a line typed by the scanner or the operator
+    ///
+    /// QR codes from the item labels hold the item ID and the product ID, only the item ID is
+    /// kept.
+    pub fn parse(line: &str) -> Self {
+        let line = line.trim();
+        match line.to_lowercase().as_str() {
+            "" | "done" => return Scan::Done,
+            "cancel" => return Scan::Cancel,
+            "quit" | "exit" => return Scan::Quit,
+            _ => {}
+        }
+        if let Ok(ssn) = parse_ssn(line) {
+            return Scan::User(ssn);
+        }
+
+        let item = line
+            .split_once(PAYLOAD_SEPARATOR)
+            .map_or(line, |(item_id, _)| item_id);
+        Scan::Item(item.trim().to_string())
+    }
+}
+
+/// An item as the scan mode needs it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScannedItem {
+    pub item_id: String,
+    pub product_name: String,
+    pub product_type: String,
+    pub size: Option<String>,
+    /// The name of the user that has the item, `None` if it is in storage
+    pub holder: Option<String>,
+}
+
+impl ScannedItem {
+    fn describe(&self) -> String {
+        match &self.size {
+            Some(size) => format!("{} {size} ({})", self.product_name, self.item_id),
+            None => format!("{} ({})", self.product_name, self.item_id),
+        }
+    }
+}
+
+/// Where the scans are carried out, the database outside of tests
+pub trait Counter {
+    /// The name of the user, `None` if there is no such user
+    fn user_name(&self, ssn: &str) -> Result<Option<String>>;
+    /// Looks up a scanned item code, `None` if no item has it
+    fn item(&self, code: &str) -> Result<Option<ScannedItem>>;
+    /// The reasons the item may not be lent to the user on top of the items in `basket`
+    fn refusals(
+        &self,
+        ssn: &str,
+        item: &ScannedItem,
+        basket: &[ScannedItem],
+    ) -> Result<Vec<String>>;
+    /// Lends all items of the basket together, none of them if one can't be lent
+    fn lend_all(&self, ssn: &str, basket: &[ScannedItem]) -> Result<()>;
+    fn take_back(&self, item: &ScannedItem) -> Result<()>;
+}
+
+/// The answer to a scan
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Feedback {
+    Success(String),
+    /// Something the operator has to act on, signalled with a beep
+    Error(String),
+    Info(String),
+}
+
+impl Feedback {
+    pub fn is_error(&self) -> bool {
+        matches!(self, Feedback::Error(_))
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            Feedback::Success(message) | Feedback::Error(message) | Feedback::Info(message) => {
+                message
+            }
+        }
+    }
+}
+
+/// The user being checked out to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Borrower {
+    pub ssn: String,
+    pub name: String,
+}
+
+/// The state of a scanning counter between scans
+#[derive(Debug, Clone, Default)]
+pub struct ScanSession {
+    borrower: Option<Borrower>,
+    basket: Vec<ScannedItem>,
+    quit: bool,
+}
+
+impl ScanSession {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn borrower(&self) -> Option<&Borrower> {
+        self.borrower.as_ref()
+    }
+
+    /// The items scanned for the current checkout
+    pub fn basket(&self) -> &[ScannedItem] {
+        &self.basket
+    }
+
+    /// Whether the operator has asked to leave the scan mode
+    pub fn is_finished(&self) -> bool {
+        self.quit
+    }
+
+    /// A short description of what the next scan does, for the prompt
+    pub fn status(&self) -> String {
+        match &self.borrower {
+            Some(borrower) => format!("{} ({} items)", borrower.name, self.basket.len()),
+            None => "return".to_string(),
+        }
+    }
+
+    /// Carries out a scan, errors from the counter are turned into feedback as well
+    pub fn handle(&mut self, counter: &impl Counter, scan: Scan) -> Vec<Feedback> {
+        let result = match scan {
+            Scan::User(ssn) => self.start(counter, ssn).map(|feedback| vec![feedback]),
+            Scan::Item(code) => self.item(counter, &code).map(|feedback| vec![feedback]),
+            Scan::Done => Ok(self.done(counter)),
+            Scan::Cancel => Ok(vec![self.cancel()]),
+            Scan::Quit => Ok(vec![self.quit()]),
+        };
+        result.unwrap_or_else(|err| vec![Feedback::Error(err.to_string())])
+    }
+
+    fn start(&mut self, counter: &impl Counter, ssn: String) -> Result<Feedback> {
+        if let Some(borrower) = &self.borrower {
+            if borrower.ssn == ssn {
+                return Ok(Feedback::Info(format!(
+                    "Already checking out to {}",
+                    borrower.name
+                )));
+            }
+            if !self.basket.is_empty() {
+                return Ok(Feedback::Error(format!(
+                    "Finish the checkout of {} first, press Enter to lend the {} scanned items or scan CANCEL",
+                    borrower.name,
+                    self.basket.len()
+                )));
+            }
+        }
+
+        let Some(name) = counter.user_name(&ssn)? else {
+            return Ok(Feedback::Error(format!(
+                "There is no user with SSN {}",
+                mask_ssn(&ssn)
+            )));
+        };
+        let feedback = Feedback::Info(format!(
+            "Checking out to {name}, scan the items and press Enter when done"
+        ));
+        self.borrower = Some(Borrower { ssn, name });
+        Ok(feedback)
+    }
+
+    fn item(&mut self, counter: &impl Counter, code: &str) -> Result<Feedback> {
+        let Some(item) = counter.item(code)? else {
+            return Ok(Feedback::Error(format!("There is no item {code}")));
+        };
+
+        let Some(borrower) = &self.borrower else {
+            let Some(holder) = &item.holder else {
+                return Ok(Feedback::Error(format!(
+                    "{} is not borrowed, scan a user first to lend it out",
+                    item.describe()
+                )));
+            };
+            counter.take_back(&item)?;
+            return Ok(Feedback::Success(format!(
+                "Returned {} from {holder}",
+                item.describe()
+            )));
+        };
+
+        if let Some(holder) = &item.holder {
+            return Ok(Feedback::Error(format!(
+                "{} is already borrowed by {holder}",
+                item.describe()
+            )));
+        }
+        if self
+            .basket
+            .iter()
+            .any(|scanned| scanned.item_id == item.item_id)
+        {
+            return Ok(Feedback::Error(format!(
+                "{} is already scanned",
+                item.describe()
+            )));
+        }
+        let refusals = counter.refusals(&borrower.ssn, &item, &self.basket)?;
+        if !refusals.is_empty() {
+            return Ok(Feedback::Error(format!(
+                "{} can't be lent to {}: {}",
+                item.describe(),
+                borrower.name,
+                refusals.join(", ")
+            )));
+        }
+
+        let feedback = Feedback::Success(format!(
+            "Added {}, {} items scanned",
+            item.describe(),
+            self.basket.len() + 1
+        ));
+        self.basket.push(item);
+        Ok(feedback)
+    }
+
+    fn done(&mut self, counter: &impl Counter) -> Vec<Feedback> {
+        let Some(borrower) = self.borrower.take() else {
+            return vec![Feedback::Info(
+                "Scan a user to check out to, or items to return them".to_string(),
+            )];
+        };
+        let basket = std::mem::take(&mut self.basket);
+        if basket.is_empty() {
+            return vec![Feedback::Info(format!(
+                "No items scanned, closed the checkout of {}",
+                borrower.name
+            ))];
+        }
+
+        match counter.lend_all(&borrower.ssn, &basket) {
+            Ok(()) => vec![Feedback::Success(format!(
+                "Lent {} items to {}",
+                basket.len(),
+                borrower.name
+            ))],
+            Err(err) => {
+                // The checkout stays open, so it can be tried again or cancelled
+                let feedback = Feedback::Error(format!(
+                    "Lent none of the {} items to {}: {err}. Press Enter to try again or scan CANCEL",
+                    basket.len(),
+                    borrower.name
+                ));
+                self.borrower = Some(borrower);
+                self.basket = basket;
+                vec![feedback]
+            }
+        }
+    }
+
+    fn cancel(&mut self) -> Feedback {
+        let basket = std::mem::take(&mut self.basket);
+        match self.borrower.take() {
+            Some(borrower) => Feedback::Info(format!(
+                "Cancelled the checkout of {}, {} items were not lent out",
+                borrower.name,
+                basket.len()
+            )),
+            None => Feedback::Info("There is no checkout to cancel".to_string()),
+        }
+    }
+
+    fn quit(&mut self) -> Feedback {
+        if let Some(borrower) = self.borrower.as_ref().filter(|_| !self.basket.is_empty()) {
+            return Feedback::Error(format!(
+                "Finish or cancel the checkout of {} before leaving",
+                borrower.name
+            ));
+        }
+        self.quit = true;
+        Feedback::Info("Left the scan mode".to_string())
+    }
+}
+
+#[cfg(feature = "python-db")]
+pub use database::{run, DatabaseCounter};
+
+#[cfg(feature = "python-db")]
+mod database {
+    use std::io::{BufRead, IsTerminal, Write};
+
+    use anyhow::{bail, Result};
+    use chrono::{Local, NaiveDate};
+
+    use super::{Counter, Feedback, Scan, ScanSession, ScannedItem};
+    use crate::cli::ScanArgs;
+    use crate::leandings::rules::{get_checkout_rules, CheckoutRules, RuleViolation};
+    use crate::leandings::Loans;
+    use crate::operators::SignOff;
+    use crate::python_db_handler::DBHandlerPy as DBHandler;
+    use crate::search::{Qualifier, SearchQuery};
+
+    /// Carries out the scans against the database, checking the checkout rules
+    ///
+    /// Regulated items need a sign-off by `witness`, whose password is asked for every checkout
+    /// and return with regulated items.
+    pub struct DatabaseCounter<'a> {
+        db_handler: &'a DBHandler,
+        rules: CheckoutRules,
+        witness: Option<String>,
+        today: NaiveDate,
+    }
+
+    impl<'a> DatabaseCounter<'a> {
+        pub fn new(db_handler: &'a DBHandler, witness: Option<String>) -> Result<Self> {
+            Ok(Self {
+                db_handler,
+                rules: get_checkout_rules()?,
+                witness,
+                today: Local::now().date_naive(),
+            })
+        }
+
+        fn is_regulated(&self, item: &ScannedItem) -> Result<bool> {
+            if !self.rules.is_regulated(&item.product_type) {
+                return Ok(false);
+            }
+            match &self.witness {
+                Some(_) => Ok(true),
+                None => bail!(
+                    "{} items are regulated, start the scan mode with --witness <USERNAME>",
+                    item.product_type
+                ),
+            }
+        }
+
+        /// Asks the witness for their password and signs off with it
+        fn sign_off(&self) -> Result<SignOff> {
+            let Some(witness) = &self.witness else {
+                bail!(
+                    "Regulated items need a witness, start the scan mode with --witness <USERNAME>"
+                );
+            };
+            let password =
+                rpassword::prompt_password(format!("Password for the witness {witness}: "))?;
+            self.db_handler.witness(witness, &password)
+        }
+    }
+
+    fn query(field: &str, value: &str) -> SearchQuery {
+        SearchQuery {
+            terms: Vec::new(),
+            qualifiers: vec![Qualifier {
+                field: field.to_string(),
+                op: ":".to_string(),
+                value: value.to_string(),
+            }],
+        }
+    }
+
+    impl Counter for DatabaseCounter<'_> {
+        fn user_name(&self, ssn: &str) -> Result<Option<String>> {
+            let users = self.db_handler.find_users(query("ssn", ssn))?;
+            Ok(users.into_iter().next().map(|user| user.name))
+        }
+
+        fn item(&self, code: &str) -> Result<Option<ScannedItem>> {
+            let Some(item_id) = self.db_handler.find_item_id(code)? else {
+                return Ok(None);
+            };
+            let Some(item) = self
+                .db_handler
+                .find_items(query("id", &item_id))?
+                .into_iter()
+                .next()
+            else {
+                return Ok(None);
+            };
+
+            let holder = match item.available {
+                true => None,
+                false => {
+                    let mut open = query("item", &item.item_id);
+                    open.qualifiers.push(Qualifier {
+                        field: "returned".to_string(),
+                        op: ":".to_string(),
+                        value: "no".to_string(),
+                    });
+                    let loan = self.db_handler.find_loans(open)?.into_iter().next();
+                    Some(loan.map_or_else(|| "someone".to_string(), |loan| loan.name))
+                }
+            };
+
+            Ok(Some(ScannedItem {
+                item_id: item.item_id,
+                product_name: item.product_name,
+                product_type: item.product_type,
+                size: item.size,
+                holder,
+            }))
+        }
+
+        fn refusals(
+            &self,
+            ssn: &str,
+            item: &ScannedItem,
+            basket: &[ScannedItem],
+        ) -> Result<Vec<String>> {
+            let mut facts = self.db_handler.get_checkout_facts(ssn, &item.item_id)?;
+            // The scanned items are lent out together at the end, but count towards the limits
+            facts.held.extend(
+                basket
+                    .iter()
+                    .map(|scanned| (scanned.product_type.clone(), self.today)),
+            );
+            let mut refusals: Vec<String> = self
+                .rules
+                .check(&facts, self.today, self.db_handler.session())
+                .iter()
+                .map(RuleViolation::to_string)
+                .collect();
+            if let Err(err) = self.is_regulated(item) {
+                refusals.push(err.to_string());
+            }
+            Ok(refusals)
+        }
+
+        fn lend_all(&self, ssn: &str, basket: &[ScannedItem]) -> Result<()> {
+            let regulated = basket
+                .iter()
+                .map(|item| self.is_regulated(item))
+                .collect::<Result<Vec<bool>>>()?;
+            // One sign-off covers all regulated items of the basket
+            let sign_off = match regulated.contains(&true) {
+                true => Some(self.sign_off()?),
+                false => None,
+            };
+
+            let checkout = basket
+                .iter()
+                .zip(regulated)
+                .map(|(item, regulated)| {
+                    let loan = Loans::new(ssn.to_string(), item.item_id.clone(), self.today, None);
+                    (loan, sign_off.as_ref().filter(|_| regulated))
+                })
+                .collect();
+            self.db_handler.insert_checkout(checkout)
+        }
+
+        fn take_back(&self, item: &ScannedItem) -> Result<()> {
+            match self.is_regulated(item)? {
+                true => self
+                    .db_handler
+                    .return_signed_item(item.item_id.clone(), &self.sign_off()?),
+                false => self.db_handler.return_item(item.item_id.clone()),
+            }
+        }
+    }
+
+    /// Reads scans from standard input until the operator quits or the input ends
+    pub fn run(db_handler: &DBHandler, args: ScanArgs) -> Result<()> {
+        let counter = DatabaseCounter::new(db_handler, args.witness)?;
+        let colors = std::io::stdout().is_terminal();
+
+        println!("Scan a user to check out to, or items to return them. Type QUIT to leave.");
+        let mut session = ScanSession::new();
+        let mut lines = std::io::stdin().lock().lines();
+        while !session.is_finished() {
+            print!("{}> ", session.status());
+            std::io::stdout().flush()?;
+            let Some(line) = lines.next() else {
+                break;
+            };
+
+            for feedback in session.handle(&counter, Scan::parse(&line?)) {
+                print_feedback(&feedback, colors, !args.silent);
+            }
+        }
+
+        if let Some(borrower) = session.borrower().filter(|_| !session.basket().is_empty()) {
+            bail!(
+                "The input ended before the {} items scanned for {} were lent out",
+                session.basket().len(),
+                borrower.name
+            );
+        }
+        Ok(())
+    }
+
+    fn print_feedback(feedback: &Feedback, colors: bool, bell: bool) {
+        let (mark, color) = match feedback {
+            Feedback::Success(_) => ("OK ", "\x1b[32m"),
+            Feedback::Error(_) => ("ERR", "\x1b[1;31m"),
+            Feedback::Info(_) => ("   ", ""),
+        };
+        let bell = match bell && feedback.is_error() {
+            true => "\x07",
+            false => "",
+        };
+        match colors && !color.is_empty() {
+            true => println!("{bell}{color}{mark} {}\x1b[0m", feedback.message()),
+            false => println!("{bell}{mark} {}", feedback.message()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    const SSN: &str = "19811218-9876";
+    const OTHER_SSN: &str = "19900101-0017";
+
+    /// Two items, `item-2` is lent to Liam, and at most two items per user
+    #[derive(Default)]
+    struct FakeCounter {
+        lent: RefCell<Vec<(String, String)>>,
+        returned: RefCell<Vec<String>>,
+        /// Refuses every checkout, like a wrong witness password
+        refuse: bool,
+    }
+
+    impl Counter for FakeCounter {
+        fn user_name(&self, ssn: &str) -> Result<Option<String>> {
+            Ok(match ssn {
+                SSN => Some("Åsa Öberg".to_string()),
+                OTHER_SSN => Some("Liam Smith".to_string()),
+                _ => None,
+            })
+        }
+
+        fn item(&self, code: &str) -> Result<Option<ScannedItem>> {
+            let holder = match code {
+                "item-1" | "item-3" => None,
+                "item-2" => Some("Liam Smith".to_string()),
+                _ => return Ok(None),
+            };
+            Ok(Some(ScannedItem {
+                item_id: code.to_string(),
+                product_name: "Combat Knife".to_string(),
+                product_type: "Weapon".to_string(),
+                size: None,
+                holder,
+            }))
+        }
+
+        fn refusals(
+            &self,
+            _: &str,
+            _: &ScannedItem,
+            basket: &[ScannedItem],
+        ) -> Result<Vec<String>> {
+            Ok(match !basket.is_empty() {
+                true => vec!["The user already holds the maximum of 1 items".to_string()],
+                false => Vec::new(),
+            })
+        }
+
+        fn lend_all(&self, ssn: &str, basket: &[ScannedItem]) -> Result<()> {
+            if self.refuse {
+                anyhow::bail!("Wrong username or password for the witness");
+            }
+            self.lent.borrow_mut().extend(
+                basket
+                    .iter()
+                    .map(|item| (ssn.to_string(), item.item_id.clone())),
+            );
+            Ok(())
+        }
+
+        fn take_back(&self, item: &ScannedItem) -> Result<()> {
+            self.returned.borrow_mut().push(item.item_id.clone());
+            Ok(())
+        }
+    }
+
+    fn scan(session: &mut ScanSession, counter: &FakeCounter, line: &str) -> Vec<Feedback> {
+        session.handle(counter, Scan::parse(line))
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Scan::parse("811218-9876\r"), Scan::User(SSN.to_string()));
+        assert_eq!(
+            Scan::parse("0b6c5ff4-22c1-11ef-a5a3-0242ac120002|FC-KNF"),
+            Scan::Item("0b6c5ff4-22c1-11ef-a5a3-0242ac120002".to_string())
+        );
+        assert_eq!(Scan::parse("item-1"), Scan::Item("item-1".to_string()));
+        assert_eq!(Scan::parse(""), Scan::Done);
+        assert_eq!(Scan::parse("Cancel"), Scan::Cancel);
+        assert_eq!(Scan::parse("QUIT"), Scan::Quit);
+    }
+
+    #[test]
+    fn test_checkout() {
+        let counter = FakeCounter::default();
+        let mut session = ScanSession::new();
+
+        assert!(matches!(
+            scan(&mut session, &counter, SSN)[..],
+            [Feedback::Info(_)]
+        ));
+        assert_eq!(session.status(), "Åsa Öberg (0 items)");
+        assert!(matches!(
+            scan(&mut session, &counter, "item-1|FC-KNF")[..],
+            [Feedback::Success(_)]
+        ));
+
+        let feedback = scan(&mut session, &counter, "item-1");
+        assert_eq!(
+            feedback,
+            vec![Feedback::Error(
+                "Combat Knife (item-1) is already scanned".to_string()
+            )]
+        );
+        let feedback = scan(&mut session, &counter, "item-2");
+        assert_eq!(
+            feedback,
+            vec![Feedback::Error(
+                "Combat Knife (item-2) is already borrowed by Liam Smith".to_string()
+            )]
+        );
+        // The scanned item counts towards the limit before it is lent out
+        assert!(scan(&mut session, &counter, "item-3")[0].is_error());
+        assert!(scan(&mut session, &counter, "unknown")[0].is_error());
+        // Another user can't be started while items are scanned
+        assert!(scan(&mut session, &counter, OTHER_SSN)[0].is_error());
+        assert!(scan(&mut session, &counter, "quit")[0].is_error());
+        assert!(!session.is_finished());
+
+        assert_eq!(
+            scan(&mut session, &counter, ""),
+            vec![Feedback::Success("Lent 1 items to Åsa Öberg".to_string())]
+        );
+        assert_eq!(
+            *counter.lent.borrow(),
+            vec![(SSN.to_string(), "item-1".to_string())]
+        );
+        assert_eq!(session.status(), "return");
+    }
+
+    #[test]
+    fn test_return() {
+        let counter = FakeCounter::default();
+        let mut session = ScanSession::new();
+
+        assert_eq!(
+            scan(&mut session, &counter, "item-2"),
+            vec![Feedback::Success(
+                "Returned Combat Knife (item-2) from Liam Smith".to_string()
+            )]
+        );
+        assert!(scan(&mut session, &counter, "item-1")[0].is_error());
+        assert_eq!(*counter.returned.borrow(), vec!["item-2".to_string()]);
+
+        scan(&mut session, &counter, "quit");
+        assert!(session.is_finished());
+    }
+
+    #[test]
+    fn test_refused_checkout() {
+        let counter = FakeCounter {
+            refuse: true,
+            ..Default::default()
+        };
+        let mut session = ScanSession::new();
+
+        scan(&mut session, &counter, SSN);
+        scan(&mut session, &counter, "item-1");
+        assert_eq!(
+            scan(&mut session, &counter, ""),
+            vec![Feedback::Error(
+                "Lent none of the 1 items to Åsa Öberg: Wrong username or password for the witness. Press Enter to try again or scan CANCEL"
+                    .to_string()
+            )]
+        );
+        // Nothing is lent and the checkout is kept for another try
+        assert!(counter.lent.borrow().is_empty());
+        assert_eq!(session.status(), "Åsa Öberg (1 items)");
+    }
+
+    #[test]
+    fn test_cancel() {
+        let counter = FakeCounter::default();
+        let mut session = ScanSession::new();
+
+        scan(&mut session, &counter, SSN);
+        scan(&mut session, &counter, "item-1");
+        scan(&mut session, &counter, "cancel");
+        assert!(session.borrower().is_none());
+        assert!(session.basket().is_empty());
+
+        // Without a user the next item is a return again
+        assert!(scan(&mut session, &counter, "item-1")[0].is_error());
+        assert!(counter.lent.borrow().is_empty());
+    }
+}
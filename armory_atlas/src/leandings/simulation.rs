@@ -0,0 +1,312 @@
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{bail, Result};
+use chrono::{Days, NaiveDate};
+use clap::ValueEnum;
+use rand::distributions::WeightedIndex;
+use rand::Rng;
+use rand_distr::{Distribution, Exp, Normal, Poisson};
+use uuid::Builder;
+
+use crate::cli::SimulationArgs;
+use crate::leandings::Loans;
+
+/// How long a simulated loan lasts
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DurationDistribution {
+    /// Every loan lasts exactly the mean duration
+    Fixed,
+    /// Anything from a day up to twice the mean duration is equally likely
+    Uniform,
+    /// Most loans are short with a few long ones, like a real depot
+    #[default]
+    Exponential,
+    /// Loans cluster around the mean duration
+    Normal,
+}
+
+impl DurationDistribution {
+    /// Draws a loan duration in whole days, at least one
+    pub fn sample(self, mean: f64, rng: &mut impl Rng) -> u64 {
+        let days = match self {
+            DurationDistribution::Fixed => mean,
+            DurationDistribution::Uniform => rng.gen_range(1.0..=(2.0 * mean - 1.0).max(1.0)),
+            DurationDistribution::Exponential => Exp::new(1.0 / mean)
+                .expect("the mean duration is positive")
+                .sample(rng),
+            DurationDistribution::Normal => Normal::new(mean, mean / 3.0)
+                .expect("the mean duration is positive")
+                .sample(rng),
+        };
+
+        days.round().max(1.0) as u64
+    }
+}
+
+/// Parses a `PRODUCT_ID=WEIGHT` popularity given on the command line
+pub fn parse_popularity(s: &str) -> Result<(String, f64)> {
+    let Some((product_id, weight)) = s.split_once('=') else {
+        bail!("Expected a popularity like PRODUCT_ID=WEIGHT, got {s}");
+    };
+    let weight: f64 = weight.trim().parse()?;
+    if !weight.is_finite() || weight < 0.0 {
+        bail!("The popularity of {product_id} must be zero or more");
+    }
+
+    Ok((product_id.trim().to_string(), weight))
+}
+
+/// A loan history simulated day by day over a virtual calendar
+///
+/// Every day the loans that are due are returned first, then a Poisson distributed number of new
+/// loans is made so that about `num_loans` are made in total. The product of a new loan is picked
+/// by popularity among the products that have an item in stock and the item is taken from that
+/// stock, so an item is never lent to two users at once. Loans that are not due by the last day
+/// are left open.
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    pub start: NaiveDate,
+    pub days: u64,
+    pub num_loans: usize,
+    pub duration: DurationDistribution,
+    /// The mean duration of a loan in days
+    pub mean_duration: f64,
+    /// The relative popularity of each product, products that are not listed have a weight of 1
+    pub popularity: HashMap<String, f64>,
+}
+
+struct OpenLoan {
+    leanding_id: String,
+    ssn: String,
+    item_id: String,
+    product_id: String,
+    borrowing_date: NaiveDate,
+    due: NaiveDate,
+}
+
+impl From<OpenLoan> for Loans {
+    fn from(loan: OpenLoan) -> Self {
+        Self {
+            leanding_id: loan.leanding_id,
+            ssn: loan.ssn,
+            item_id: loan.item_id,
+            borrowing_date: loan.borrowing_date,
+            return_date: None,
+        }
+    }
+}
+
+impl Simulation {
+    /// A simulation of `args`, which ends on `today` unless it has a first day
+    pub fn new(num_loans: usize, args: &SimulationArgs, today: NaiveDate) -> Result<Self> {
+        if args.days == 0 {
+            bail!("The simulation needs at least one day");
+        }
+        if !args.mean_duration.is_finite() || args.mean_duration <= 0.0 {
+            bail!("The mean loan duration must be more than zero days");
+        }
+
+        let start = args
+            .from
+            .unwrap_or_else(|| today - Days::new(args.days - 1));
+
+        Ok(Self {
+            start,
+            days: args.days,
+            num_loans,
+            duration: args.duration,
+            mean_duration: args.mean_duration,
+            popularity: args.popularity.iter().cloned().collect(),
+        })
+    }
+
+    /// The last day of the simulation
+    pub fn end(&self) -> NaiveDate {
+        self.start + Days::new(self.days - 1)
+    }
+
+    /// Runs the simulation
+    ///
+    /// Returns the returned loans in the order they came back followed by the loans that are still
+    /// open, which is also the order they can be inserted in.
+    ///
+    /// # Arguments
+    ///
+    /// * `users`: The SSNs of the users that can borrow items.
+    /// * `items`: The item and product IDs of the items that can be lent out.
+    /// * `rng`: The random number generator to run the simulation with.
+    ///
+    pub fn run(
+        &self,
+        users: &[String],
+        items: &[(String, String)],
+        rng: &mut impl Rng,
+    ) -> Result<Vec<Loans>> {
+        if users.is_empty() {
+            bail!("There are no users to lend items to!");
+        }
+
+        let mut stock: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for (item_id, product_id) in items {
+            stock
+                .entry(product_id.clone())
+                .or_default()
+                .push(item_id.clone());
+        }
+
+        let per_day = Poisson::new(self.num_loans as f64 / self.days as f64).ok();
+        let mut open: Vec<OpenLoan> = Vec::new();
+        let mut returned: Vec<Loans> = Vec::new();
+
+        for day in 0..self.days {
+            let date = self.start + Days::new(day);
+
+            let (due, still_open): (Vec<OpenLoan>, Vec<OpenLoan>) =
+                open.into_iter().partition(|loan| loan.due <= date);
+            open = still_open;
+            for loan in due {
+                stock
+                    .entry(loan.product_id.clone())
+                    .or_default()
+                    .push(loan.item_id.clone());
+
+                let return_date = Some(loan.due);
+                returned.push(Loans {
+                    return_date,
+                    ..loan.into()
+                });
+            }
+
+            let new_loans = per_day.map_or(0, |per_day| per_day.sample(rng) as usize);
+            for _ in 0..new_loans {
+                let Some(product_id) = self.pick_product(&stock, rng) else {
+                    break;
+                };
+                let in_stock = stock.get_mut(&product_id).expect("picked from the stock");
+                let item_id = in_stock.swap_remove(rng.gen_range(0..in_stock.len()));
+                let duration = self.duration.sample(self.mean_duration, rng);
+
+                open.push(OpenLoan {
+                    leanding_id: Builder::from_random_bytes(rng.gen())
+                        .into_uuid()
+                        .to_string(),
+                    ssn: users[rng.gen_range(0..users.len())].clone(),
+                    item_id,
+                    product_id,
+                    borrowing_date: date,
+                    due: date + Days::new(duration),
+                });
+            }
+        }
+
+        returned.extend(open.into_iter().map(Loans::from));
+
+        Ok(returned)
+    }
+
+    fn pick_product(
+        &self,
+        stock: &BTreeMap<String, Vec<String>>,
+        rng: &mut impl Rng,
+    ) -> Option<String> {
+        let candidates: Vec<(&String, f64)> = stock
+            .iter()
+            .filter(|(_, items)| !items.is_empty())
+            .map(|(product_id, _)| {
+                let weight = self.popularity.get(product_id).copied().unwrap_or(1.0);
+                (product_id, weight)
+            })
+            .filter(|(_, weight)| *weight > 0.0)
+            .collect();
+
+        let weights = WeightedIndex::new(candidates.iter().map(|(_, weight)| weight)).ok()?;
+        Some(candidates[weights.sample(rng)].0.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::seeded_rng;
+
+    fn simulation() -> Simulation {
+        Simulation {
+            start: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            days: 120,
+            num_loans: 200,
+            duration: DurationDistribution::Exponential,
+            mean_duration: 10.0,
+            popularity: HashMap::from([("B".to_string(), 0.0)]),
+        }
+    }
+
+    fn items() -> Vec<(String, String)> {
+        (0..12)
+            .map(|i| (format!("item-{i:02}"), ["A", "B", "C"][i % 3].to_string()))
+            .collect()
+    }
+
+    fn users() -> Vec<String> {
+        vec!["811218-9876".to_string(), "900101-1233".to_string()]
+    }
+
+    #[test]
+    fn test_items_are_never_double_lent() {
+        let simulation = simulation();
+        let loans = simulation
+            .run(&users(), &items(), &mut seeded_rng(Some(7)))
+            .unwrap();
+
+        assert!(!loans.is_empty());
+        for (i, loan) in loans.iter().enumerate() {
+            let end = loan.return_date.unwrap_or(NaiveDate::MAX);
+            assert!(loan.borrowing_date >= simulation.start);
+            assert!(loan.return_date.is_none_or(|date| date <= simulation.end()));
+
+            for other in loans.iter().skip(i + 1) {
+                if other.item_id != loan.item_id {
+                    continue;
+                }
+                let other_end = other.return_date.unwrap_or(NaiveDate::MAX);
+                assert!(other.borrowing_date >= end || loan.borrowing_date >= other_end);
+            }
+        }
+
+        // Open loans come last so they can be inserted after the returned ones
+        let first_open = loans.iter().position(|loan| loan.return_date.is_none());
+        if let Some(first_open) = first_open {
+            assert!(loans[first_open..]
+                .iter()
+                .all(|loan| loan.return_date.is_none()));
+        }
+    }
+
+    #[test]
+    fn test_unpopular_products_are_not_lent() {
+        let loans = simulation()
+            .run(&users(), &items(), &mut seeded_rng(Some(7)))
+            .unwrap();
+        let product_b: Vec<String> = items()
+            .into_iter()
+            .filter(|(_, product_id)| product_id == "B")
+            .map(|(item_id, _)| item_id)
+            .collect();
+
+        assert!(loans.iter().all(|loan| !product_b.contains(&loan.item_id)));
+    }
+
+    #[test]
+    fn test_same_seed_same_history() {
+        let run = |seed| {
+            simulation()
+                .run(&users(), &items(), &mut seeded_rng(Some(seed)))
+                .unwrap()
+                .into_iter()
+                .map(|loan| (loan.leanding_id, loan.item_id, loan.borrowing_date))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(run(3), run(3));
+        assert_ne!(run(3), run(4));
+    }
+}
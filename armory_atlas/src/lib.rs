@@ -4,28 +4,52 @@ use std::fs;
 use std::fs::File;
 
 use crate::cli::{
-    Command, CommandType, GenerateArgs, GenerateSubCommands, GetArgs, GetSubCommands, InsertArgs,
-    InsertSubCommands, ReturnSubCommands,
+    Command, CommandType, DeleteArgs, DeleteSubCommands, DocumentArgs, DocumentSubCommands,
+    GenerateArgs, GenerateSubCommands, GetArgs, GetSubCommands, InsertArgs, InsertSubCommands,
+    ItemArgs, ItemSubCommands, ManageSubCommands, OperatorSubCommands, OperatorsArgs, ReportArgs,
+    ReportSubCommands, ReturnSubCommands, StockArgs, StockSubCommands, UpdateArgs,
+    UpdateSubCommands, UserDataSubCommands,
 };
+use crate::documents::{Document, DocumentFormat};
+use crate::fuzzy::Scored;
 use crate::items::{insert_items, Item};
+use crate::operators::Permission;
+use crate::output::{render, OutputFormat};
 use crate::products::insert_products;
-use anyhow::Result;
-use chrono::Local;
+use crate::products::reorder::{low_stock, stock_status};
+use crate::query::QueryOptions;
+use crate::search::{search, Qualifier, SearchQuery};
+use anyhow::{bail, Result};
+use chrono::{Days, Local, NaiveDate};
 use clap::Parser;
 use env_logger::{Builder, Env};
-use prettytable::Table;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
 use std::io::Write;
 
 use regex::Regex;
 
+pub mod archive;
 pub mod cli;
 pub mod config;
+pub mod consumables;
+pub mod documents;
+pub mod fuzzy;
+pub mod import;
 pub mod items;
+pub mod labels;
 pub mod leandings;
+pub mod operators;
+pub mod output;
 pub mod password_handler;
+pub mod pdf;
+pub mod png;
 pub mod products;
 #[cfg(feature = "python-db")]
 pub mod python_db_handler;
+pub mod query;
+pub mod reports;
+pub mod search;
 #[cfg(feature = "tui")]
 pub mod tui;
 pub mod users;
@@ -34,22 +58,33 @@ pub const CONFIG_DIR: &str = ".config/armoryatlas";
 pub const CONFIG_FILE: &str = "config.toml";
 pub const PRODUCTS_FILE: &str = "products.json";
 pub const DEFAULT_PRODUCTS: &str = include_str!("../../default-products.json");
+pub const RULES_FILE: &str = "checkout-rules.json";
+pub const DEFAULT_CHECKOUT_RULES: &str = include_str!("../../default-checkout-rules.json");
 pub const DEFAULT_CONFIG: &str = include_str!("../../default-config.toml");
 #[cfg(feature = "python-db")]
 pub const PYTHON_DATABASE_HANDLER: &str = include_str!("../ArmoryAtlasDBHandler.py");
 
-use crate::config::{get_config, write_config};
+use crate::config::{get_config, set_login, write_config};
 
 #[cfg(feature = "python-db")]
 use crate::python_db_handler::{
+    accountability::{AccountabilityEntry, AccountabilityReport},
+    consumables::{ConsumableStock, ConsumableStocks, Consumption, Consumptions},
     in_stock_size::{InStockSize, InStockSizes},
+    inactive_holdings::{InactiveHolding, InactiveHoldings},
+    item_history::{ItemHistory, ItemHistoryEvent},
+    item_records::{ItemRecord, ItemRecords},
     loans::{DetailedLoan, DetailedLoans},
+    open_loans::{OpenLoan, OpenLoans},
+    operators::{Operator, Operators},
+    products::{ProductMatch, ProductMatches, Products},
     users::Users,
     DBHandlerPy as DBHandler, DetailedItem, DetailedItems,
 };
 
 use crate::leandings::Loans;
 use crate::password_handler::get_db_pass;
+use crate::users::ssn::{mask_ssn, mask_unless, redact_ssns};
 use crate::users::User;
 
 #[derive(Debug, Clone)]
@@ -65,6 +100,9 @@ pub struct ItemProduct {
 
 /// Search for items in the database
 ///
+/// The items are matched fuzzily against their product name, type, ID and size so typos still
+/// find something. Every item comes with its relevance score, the best match first.
+///
 /// # Arguments
 ///
 /// * `search_param`: The search parameter to search for.
@@ -78,12 +116,47 @@ pub struct ItemProduct {
 /// ```
 ///
 ///
-pub async fn search_items(search_param: &str) -> Result<Vec<DetailedItem>> {
-    let items = DBHandler::new()?.search_items(search_param)?;
+pub async fn search_items(search_param: &str) -> Result<Vec<Scored<DetailedItem>>> {
+    let items = DBHandler::new()?.get_items(QueryOptions::default())?;
+    let items = fuzzy::rank(search_param, items, |item| {
+        vec![
+            item.product_name.as_str(),
+            item.product_type.as_str(),
+            item.product_id.as_str(),
+            item.size.as_str(),
+        ]
+    });
 
     Ok(items)
 }
 
+/// The random number generator used for generating test data
+///
+/// The same seed always gives the same sequence of numbers, also across platforms and versions of
+/// `rand`, so generated fixtures can be reproduced. Without a seed the generator is seeded from
+/// the operating system.
+pub fn seeded_rng(seed: Option<u64>) -> ChaCha8Rng {
+    match seed {
+        Some(seed) => ChaCha8Rng::seed_from_u64(seed),
+        None => ChaCha8Rng::from_entropy(),
+    }
+}
+
+/// The day seeded test data is generated as of when no other day is given
+pub const SEEDED_TODAY: NaiveDate = NaiveDate::from_ymd_opt(2024, 6, 1).expect("a valid date");
+
+/// The day test data is generated as of, which decides who is still serving and when simulations end
+///
+/// Seeded runs default to [`SEEDED_TODAY`] rather than the current day, so a seed gives the same
+/// data whenever it is run.
+pub fn reference_date(today: Option<NaiveDate>, seed: Option<u64>) -> NaiveDate {
+    match (today, seed) {
+        (Some(today), _) => today,
+        (None, Some(_)) => SEEDED_TODAY,
+        (None, None) => Local::now().date_naive(),
+    }
+}
+
 /// Generates test data
 ///
 /// This is the main function to generate test data for an Armory Atlas database.  
@@ -98,20 +171,33 @@ pub async fn search_items(search_param: &str) -> Result<Vec<DetailedItem>> {
 /// Its only meant to be used by the `run_cli` function!
 ///
 fn generate_test_data(args: GenerateArgs, db_handler: DBHandler) -> Result<()> {
+    let mut rng = seeded_rng(args.seed);
+    let today = reference_date(args.today, args.seed);
+
     match args.subcommands {
         Some(GenerateSubCommands::Products) => insert_products(&db_handler)?,
 
         Some(GenerateSubCommands::Items(sub_args)) => {
-            insert_items(&db_handler, sub_args.num_items)?
+            insert_items(&db_handler, sub_args.num_items, &mut rng)?
         }
 
         Some(GenerateSubCommands::Users(sub_args)) => {
-            users::insert_users(&db_handler, sub_args.num_users)?
+            users::insert_users(&db_handler, sub_args.num_users, today, &mut rng)?
         }
 
         Some(GenerateSubCommands::Loans(sub_args)) => {
-            println!("Inserting {} loans", sub_args.num_loans);
-            leandings::insert_leandings(&db_handler, sub_args.num_loans)?
+            if sub_args.simulation.simulate {
+                leandings::insert_simulated_leandings(
+                    &db_handler,
+                    sub_args.num_loans,
+                    &sub_args.simulation,
+                    today,
+                    &mut rng,
+                )?
+            } else {
+                println!("Inserting {} loans", sub_args.num_loans);
+                leandings::insert_leandings(&db_handler, sub_args.num_loans, today, &mut rng)?
+            }
         }
 
         _ => {
@@ -127,9 +213,14 @@ fn generate_test_data(args: GenerateArgs, db_handler: DBHandler) -> Result<()> {
                 }
             }
 
-            insert_items(&db_handler, args.num_to_generate.unwrap())?;
-            users::insert_users(&db_handler, args.num_to_generate.unwrap())?;
-            leandings::insert_leandings(&db_handler, args.num_to_generate.unwrap())?;
+            insert_items(&db_handler, args.num_to_generate.unwrap(), &mut rng)?;
+            users::insert_users(&db_handler, args.num_to_generate.unwrap(), today, &mut rng)?;
+            leandings::insert_leandings(
+                &db_handler,
+                args.num_to_generate.unwrap(),
+                today,
+                &mut rng,
+            )?;
         }
     }
 
@@ -205,7 +296,7 @@ pub fn setup_logger() -> Result<()> {
                     .file()
                     .unwrap_or(record.module_path().unwrap_or("unknown")),
                 record.line().unwrap_or(0),
-                record.args()
+                redact_ssns(&record.args().to_string())
             )
         })
         .target(env_logger::Target::Pipe(Box::new(file)))
@@ -213,84 +304,250 @@ pub fn setup_logger() -> Result<()> {
     Ok(())
 }
 
-fn get_subcommands(args: GetArgs, db_handler: DBHandler) -> Result<()> {
+fn get_subcommands(
+    args: GetArgs,
+    format: OutputFormat,
+    show_ssn: bool,
+    db_handler: DBHandler,
+) -> Result<()> {
     match args.subcommands {
         GetSubCommands::Items(args) => {
-            if args.limit.is_none() {
-                // if limit is not specified we get all
-                let items: DetailedItems = db_handler.get_items()?.into();
-                println!("{}", Table::from(items))
-            } else {
-                let items: DetailedItems = db_handler.get_items()?[..args.limit.unwrap()]
-                    .to_vec()
-                    .into();
-                println!("{}", Table::from(items))
-            }
+            let items: DetailedItems = db_handler.get_items((&args).into())?.into();
+            println!("{}", render(items, format)?);
         }
         GetSubCommands::InStock(args) => {
             let items = db_handler.get_in_stock_size(args.pruduct_id, args.size)?;
-            println!("{}", Table::from(items));
+            println!("{}", render(items, format)?);
         }
         GetSubCommands::Loans(args) => {
-            if args.limit.is_none() {
-                // if limit is not specified we get all
-                let loans: DetailedLoans = if args.ssn.is_some() {
-                    db_handler.user_all_borrowed(args.ssn.unwrap())?.into()
-                } else {
-                    db_handler.get_loans()?.into()
-                };
-                println!("{}", Table::from(loans))
-            } else {
-                let loans: DetailedLoans = if args.ssn.is_some() {
-                    db_handler.user_all_borrowed(args.ssn.unwrap())?[..args.limit.unwrap()]
-                        .to_vec()
-                        .into()
-                } else {
-                    db_handler.get_loans()?[..args.limit.unwrap()]
-                        .to_vec()
-                        .into()
-                };
-                println!("{}", Table::from(loans))
-            }
+            let options = QueryOptions::from(&args);
+            let loans: DetailedLoans = match args.ssn {
+                Some(ssn) => db_handler.user_all_borrowed(ssn, options)?.into(),
+                None => db_handler.get_loans(options)?.into(),
+            };
+            println!("{}", render(mask_unless(loans, show_ssn), format)?);
         }
         GetSubCommands::Users(args) => {
-            if args.limit.is_none() {
-                let users: Users = db_handler.get_users()?.into();
-                println!("{}", Table::from(users));
-            } else {
-                let users: Users = db_handler.get_users()?[..args.limit.unwrap()]
-                    .to_vec()
-                    .into();
-                println!("{}", Table::from(users));
+            let users: Users = db_handler.get_users((&args).into())?.into();
+            println!("{}", render(mask_unless(users, show_ssn), format)?);
+        }
+        GetSubCommands::NumberOfLoans(args) => {
+            let borrows: NumberBorrows = db_handler.number_of_borrowes((&args).into())?.into();
+            println!("{}", render(mask_unless(borrows, show_ssn), format)?);
+        }
+        GetSubCommands::ItemHistory(args) => {
+            let history = db_handler.get_item_history(args.item_id)?;
+            println!("{}", render(mask_unless(history, show_ssn), format)?);
+        }
+        GetSubCommands::InactiveHolders => {
+            let holdings = db_handler.get_inactive_holdings()?;
+            println!("{}", render(mask_unless(holdings, show_ssn), format)?);
+        }
+        GetSubCommands::Accountability(args) => {
+            let date = args.date.unwrap_or_else(|| Local::now().date_naive());
+            let rules = leandings::rules::get_checkout_rules()?;
+            let report = db_handler.get_accountability(rules.regulated_types, date)?;
+            println!("{}", render(mask_unless(report, show_ssn), format)?);
+        }
+        GetSubCommands::LowStock(args) => {
+            let since = Local::now().date_naive() - Days::new(args.days);
+            let levels = db_handler.get_stock_levels(since)?;
+            let report = low_stock(&products::get_products()?, &levels);
+            println!("{}", render(report, format)?);
+        }
+        GetSubCommands::OpenLoans(args) => {
+            let rules = leandings::rules::get_checkout_rules()?;
+            let mut loans = db_handler.get_open_loans()?.with_due_dates(&rules);
+            if args.overdue {
+                loans = loans.overdue(Local::now().date_naive());
             }
+            println!("{}", render(mask_unless(loans, show_ssn), format)?);
         }
+        GetSubCommands::Consumption(args) => {
+            let consumption = db_handler.get_consumption(args.by, args.since, args.until)?;
+            println!("{}", render(mask_unless(consumption, show_ssn), format)?);
+        }
+    }
 
-        GetSubCommands::NumberOfLoans(args) => {
-            if args.limit.is_none() {
-                let users: NumberBorrows = db_handler.number_of_borrowes()?.into();
-                println!("{}", Table::from(users));
-            } else {
-                let users: NumberBorrows = db_handler.number_of_borrowes()?[..args.limit.unwrap()]
-                    .to_vec()
-                    .into();
-                println!("{}", Table::from(users));
+    Ok(())
+}
+
+fn operator_subcommands(
+    args: OperatorsArgs,
+    format: OutputFormat,
+    db_handler: DBHandler,
+) -> Result<()> {
+    match args.subcommands {
+        OperatorSubCommands::Add(args) => {
+            let password = rpassword::prompt_password(format!("Password for {}: ", args.username))?;
+            let repeated = rpassword::prompt_password("Repeat the password: ")?;
+            if password != repeated {
+                bail!("The passwords do not match");
             }
+            db_handler.add_operator(&args.username, args.role, &password)?;
+            println!("Added {} as {}", args.username, args.role);
+        }
+        OperatorSubCommands::Remove(args) => {
+            db_handler.remove_operator(&args.username)?;
+            println!("Removed {}", args.username);
+        }
+        OperatorSubCommands::List => {
+            println!("{}", render(db_handler.get_operators()?, format)?);
+        }
+    }
+
+    Ok(())
+}
+
+fn item_subcommands(args: ItemArgs, db_handler: DBHandler) -> Result<()> {
+    match args.subcommands {
+        ItemSubCommands::Maintain(args) => {
+            db_handler.record_item_event(args.item_id, "Maintenance", args.details, args.date)?;
+        }
+        ItemSubCommands::Transfer(args) => {
+            let details = format!("Moved to {}", args.destination);
+            db_handler.record_item_event(args.item_id, "Transfer", details, args.date)?;
+        }
+        ItemSubCommands::Retire(args) => {
+            db_handler.retire_item(args.item_id, args.reason, args.date)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn stock_subcommands(args: StockArgs, format: OutputFormat, db_handler: DBHandler) -> Result<()> {
+    match args.subcommands {
+        StockSubCommands::Issue(args) => consumables::issue(&db_handler, args)?,
+        StockSubCommands::Receive(args) => consumables::receive(&db_handler, args)?,
+        StockSubCommands::List => {
+            let stock = ConsumableStocks::from(db_handler.get_consumable_stock()?);
+            println!("{}", render(stock, format)?);
         }
     }
 
     Ok(())
 }
 
+fn report_subcommands(args: ReportArgs, format: OutputFormat, db_handler: DBHandler) -> Result<()> {
+    let data = db_handler.get_report_data()?;
+    let period = data.period(args.from, args.to, Local::now().date_naive())?;
+
+    let report = match args.subcommands {
+        ReportSubCommands::Utilisation => render(data.utilisation(&period), format)?,
+        ReportSubCommands::LoanDuration => render(data.loan_durations(&period), format)?,
+        ReportSubCommands::BusiestSizes => render(data.busiest_sizes(&period), format)?,
+        ReportSubCommands::NeverBorrowed => render(data.never_borrowed(&period), format)?,
+        ReportSubCommands::Quality => render(data.quality_distribution(), format)?,
+        ReportSubCommands::LoansPerMonth => render(data.loans_per_month(&period), format)?,
+    };
+    println!("{report}");
+
+    Ok(())
+}
+
+fn document_subcommands(args: DocumentArgs, show_ssn: bool, db_handler: DBHandler) -> Result<()> {
+    let today = Local::now().date_naive();
+
+    let (document, file) = match args.subcommands {
+        DocumentSubCommands::StockStatus(args) => {
+            let products = products::get_products()?;
+            let levels = db_handler.get_stock_levels(today - Days::new(args.days))?;
+            let consumables: ConsumableStocks = db_handler.get_consumable_stock()?.into();
+            let document = Document::new("Stock Status", today)
+                .table("Stock levels", stock_status(&products, &levels))
+                .table("Below minimum stock", low_stock(&products, &levels))
+                .table("Consumables on hand", consumables);
+            (document, args.file)
+        }
+        DocumentSubCommands::OpenLoans(file) => {
+            let rules = leandings::rules::get_checkout_rules()?;
+            let loans = db_handler.get_open_loans()?.with_due_dates(&rules);
+            let document = mask_unless(loans, show_ssn).per_unit().into_iter().fold(
+                Document::new("Open Loans per Unit", today),
+                |document, (unit, loans)| {
+                    document.table(unit.unwrap_or_else(|| "No unit".to_string()), loans)
+                },
+            );
+            (document, file)
+        }
+        DocumentSubCommands::Overdue(file) => {
+            let rules = leandings::rules::get_checkout_rules()?;
+            let loans = db_handler
+                .get_open_loans()?
+                .with_due_dates(&rules)
+                .overdue(today);
+            let document = Document::new("Overdue Loans", today)
+                .table("Overdue items", mask_unless(loans, show_ssn));
+            (document, file)
+        }
+        DocumentSubCommands::Receipt(args) => {
+            let query = SearchQuery {
+                qualifiers: vec![Qualifier {
+                    field: "ssn".to_string(),
+                    op: ":".to_string(),
+                    value: args.ssn.clone(),
+                }],
+                ..Default::default()
+            };
+            let Some(user) = db_handler.find_users(query)?.into_iter().next() else {
+                bail!("There is no user with SSN {}", mask_ssn(&args.ssn));
+            };
+            let (held, returned): (Vec<DetailedLoan>, Vec<DetailedLoan>) = db_handler
+                .user_all_borrowed(args.ssn, QueryOptions::default())?
+                .into_iter()
+                .partition(|loan| loan.return_date.is_none());
+
+            let name = user.name.clone();
+            let users: Users = vec![user].into();
+            let held: DetailedLoans = held.into();
+            let returned: DetailedLoans = returned.into();
+            let document = Document::new(format!("Receipt for {name}"), today)
+                .table("User", mask_unless(users, show_ssn))
+                .table("Items held", mask_unless(held, show_ssn))
+                .table("Returned items", mask_unless(returned, show_ssn))
+                .text(
+                    "Acknowledgement",
+                    format!("{name} has received the items listed under \"Items held\" and is responsible for them until they are returned."),
+                )
+                .signatures("Signatures", &[&name, "Operator"]);
+            (document, args.file)
+        }
+        DocumentSubCommands::HandOver(args) => {
+            return leandings::receipt::hand_over(&db_handler, args, show_ssn);
+        }
+    };
+
+    let format = match file.document_format {
+        Some(format) => format,
+        None => DocumentFormat::from_path(&file.file)?,
+    };
+    document.write(&file.file, format)?;
+    println!("Wrote {}", file.file.display());
+
+    Ok(())
+}
+
 fn insert_subcommands(args: InsertArgs, db_handler: DBHandler) -> Result<()> {
     match args.subcommands {
         InsertSubCommands::Item(args) => {
+            if products::get_products()?
+                .iter()
+                .any(|product| product.product_id == args.product_id && product.consumable)
+            {
+                bail!(
+                    "{} is a consumable, receive it with `stock receive` instead",
+                    args.product_id
+                );
+            }
             db_handler.insert_item(args.into())?;
         }
         InsertSubCommands::User(args) => {
             db_handler.insert_user(args.into())?;
         }
         InsertSubCommands::Loan(args) => {
-            db_handler.insert_loan(args.into())?;
+            let witness = args.witness.clone();
+            leandings::checkout(&db_handler, args.into(), witness)?;
         }
         InsertSubCommands::Product(args) => {
             db_handler.insert_product(args.into())?;
@@ -300,6 +557,62 @@ fn insert_subcommands(args: InsertArgs, db_handler: DBHandler) -> Result<()> {
     Ok(())
 }
 
+fn update_subcommands(args: UpdateArgs, db_handler: DBHandler) -> Result<()> {
+    match args.subcommands {
+        UpdateSubCommands::Item(args) => {
+            db_handler.update_item(
+                args.item_id,
+                args.product_id,
+                args.size,
+                args.quality,
+                args.serial,
+            )?;
+        }
+        UpdateSubCommands::User(args) => {
+            db_handler.update_user(
+                args.ssn,
+                args.name,
+                args.unit,
+                args.rank,
+                args.phone,
+                args.email,
+                args.active,
+                args.end_of_service,
+            )?;
+        }
+        UpdateSubCommands::Loan(args) => {
+            leandings::update_loan(
+                &db_handler,
+                args.lending_id,
+                args.borrow_date,
+                args.return_date,
+                args.witness,
+            )?;
+        }
+        UpdateSubCommands::Product(args) => {
+            db_handler.update_product(args.product_id, args.product_name, args.product_type)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn delete_subcommands(args: DeleteArgs, db_handler: DBHandler) -> Result<()> {
+    match args.subcommands {
+        DeleteSubCommands::User(args) => {
+            db_handler.delete_user(args.ssn, args.force)?;
+        }
+        DeleteSubCommands::Loan(args) => {
+            db_handler.delete_loan(args.lending_id)?;
+        }
+        DeleteSubCommands::Product(args) => {
+            db_handler.delete_product(args.product_id, args.force)?;
+        }
+    }
+
+    Ok(())
+}
+
 #[cfg_attr(feature = "python-db", pyo3::pyfunction)]
 /// Executes the command-line interface for the Armory Atlas application.
 ///
@@ -351,6 +664,9 @@ pub fn run_cli(args: Option<Vec<String>>) -> Result<()> {
     let password = get_db_pass(&user, &host)?;
 
     let db_handler = DBHandler::new()?;
+    if cmd.show_ssn {
+        db_handler.session().require(Permission::FullSsns)?;
+    }
 
     match cmd.subcommands {
         CommandType::Config(args) => {
@@ -366,18 +682,66 @@ pub fn run_cli(args: Option<Vec<String>>) -> Result<()> {
             if args.create_all {
                 db_handler.create_all()?;
             }
+            match args.subcommands {
+                Some(ManageSubCommands::Export(args)) => archive::export(&db_handler, &args.file)?,
+                Some(ManageSubCommands::Import(args)) => archive::restore(&db_handler, &args.file)?,
+                None => {}
+            }
         }
         CommandType::Get(args) => {
-            get_subcommands(args, db_handler)?;
+            get_subcommands(args, cmd.format, cmd.show_ssn, db_handler)?;
         }
         CommandType::Return(args) => match args.subcommands {
             ReturnSubCommands::Item(args) => {
-                db_handler.return_item(args.item_id)?;
+                leandings::return_item(&db_handler, args.item_id, args.witness)?;
             }
         },
         CommandType::Insert(args) => {
             insert_subcommands(args, db_handler)?;
         }
+        CommandType::Update(args) => {
+            update_subcommands(args, db_handler)?;
+        }
+        CommandType::Delete(args) => {
+            delete_subcommands(args, db_handler)?;
+        }
+        CommandType::Users(args) => match args.subcommands {
+            UserDataSubCommands::Erase(args) => {
+                let pseudonym = db_handler.erase_user(args.ssn.clone())?;
+                println!(
+                    "Erased {}, the user's loans are kept as {pseudonym}",
+                    mask_ssn(&args.ssn)
+                );
+            }
+        },
+        CommandType::Login(args) => {
+            let password = rpassword::prompt_password(format!("Password for {}: ", args.username))?;
+            let Some(token) = db_handler.login_operator(&args.username, &password)? else {
+                bail!("Wrong username or password");
+            };
+            set_login(Some((&args.username, &token)))?;
+            println!("Logged in as {}", args.username);
+        }
+        CommandType::Logout => {
+            db_handler.logout_operator()?;
+            set_login(None)?;
+            println!("Logged out");
+        }
+        CommandType::Operators(args) => operator_subcommands(args, cmd.format, db_handler)?,
+        CommandType::Item(args) => item_subcommands(args, db_handler)?,
+        CommandType::Stock(args) => stock_subcommands(args, cmd.format, db_handler)?,
+        CommandType::Report(args) => report_subcommands(args, cmd.format, db_handler)?,
+        CommandType::Document(args) => document_subcommands(args, cmd.show_ssn, db_handler)?,
+        CommandType::Labels(args) => labels::write_labels(&db_handler, args)?,
+        CommandType::Scan(args) => leandings::scan::run(&db_handler, args)?,
+        CommandType::Search(args) => {
+            let query = SearchQuery::from_args(&args.query)?;
+            let results = search(&db_handler, args.scope, query)?;
+            println!("{}", mask_unless(results, cmd.show_ssn).render(cmd.format)?);
+        }
+        CommandType::Import(args) => {
+            import::import(&db_handler, args, cmd.format)?;
+        }
     };
 
     Ok(())
@@ -402,6 +766,28 @@ fn armory_atlas_lib(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_class::<User>()?;
     m.add_class::<Users>()?;
     m.add_class::<Loans>()?;
+    m.add_class::<ItemHistoryEvent>()?;
+    m.add_class::<ItemHistory>()?;
+    m.add_class::<QueryOptions>()?;
+    m.add_class::<SearchQuery>()?;
+    m.add_class::<Qualifier>()?;
+    m.add_class::<ItemRecord>()?;
+    m.add_class::<ItemRecords>()?;
+    m.add_class::<Products>()?;
+    m.add_class::<ProductMatch>()?;
+    m.add_class::<ProductMatches>()?;
+    m.add_class::<Operator>()?;
+    m.add_class::<Operators>()?;
+    m.add_class::<InactiveHolding>()?;
+    m.add_class::<InactiveHoldings>()?;
+    m.add_class::<OpenLoan>()?;
+    m.add_class::<OpenLoans>()?;
+    m.add_class::<AccountabilityEntry>()?;
+    m.add_class::<AccountabilityReport>()?;
+    m.add_class::<ConsumableStock>()?;
+    m.add_class::<ConsumableStocks>()?;
+    m.add_class::<Consumption>()?;
+    m.add_class::<Consumptions>()?;
 
     m.add_function(wrap_pyfunction!(run_cli, m)?)?;
     Ok(())
@@ -0,0 +1,233 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use anyhow::{bail, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+/// The role of an operator, each role may do everything the roles before it may
+#[derive(
+    ValueEnum, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// May only read from the database
+    Viewer,
+    /// May also lend out and take back items and register borrowers
+    Clerk,
+    /// May also edit the catalog of products, items and users
+    Quartermaster,
+    /// May also manage the database itself and the operator accounts
+    Admin,
+}
+
+/// The categories of operations a role gives access to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Permission {
+    /// Reading and searching anything in the database
+    Read,
+    /// Lending out and returning items, and registering borrowers
+    Checkout,
+    /// Adding, editing and removing products, items and users
+    Catalog,
+    /// Seeing SSNs in full instead of masked
+    FullSsns,
+    /// Creating and dropping the schema, archives, erasing users and managing operators
+    Manage,
+}
+
+impl Permission {
+    /// The least role that has the permission
+    pub fn minimum_role(self) -> Role {
+        match self {
+            Permission::Read => Role::Viewer,
+            Permission::Checkout => Role::Clerk,
+            Permission::Catalog | Permission::FullSsns => Role::Quartermaster,
+            Permission::Manage => Role::Admin,
+        }
+    }
+}
+
+impl Display for Permission {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Permission::Read => "read from the database",
+            Permission::Checkout => "lend out or return items",
+            Permission::Catalog => "edit the catalog",
+            Permission::FullSsns => "see full SSNs",
+            Permission::Manage => "manage the database",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl Role {
+    pub fn allows(self, permission: Permission) -> bool {
+        self >= permission.minimum_role()
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Clerk => "clerk",
+            Role::Quartermaster => "quartermaster",
+            Role::Admin => "admin",
+        }
+    }
+}
+
+impl Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for Role {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match <Role as ValueEnum>::from_str(s, true) {
+            Ok(role) => Ok(role),
+            Err(_) => bail!("{s} is not a role, expected viewer, clerk, quartermaster or admin"),
+        }
+    }
+}
+
+/// Who the database handler is working for, checked before every operation
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Session {
+    /// No operator accounts exist yet, everything is allowed so the first admin can be added
+    Setup,
+    /// A logged in operator
+    Operator { username: String, role: Role },
+    /// Operator accounts exist but nobody is logged in, or the operator no longer exists
+    Anonymous,
+}
+
+impl Session {
+    /// Fails unless the session has the permission
+    pub fn require(&self, permission: Permission) -> Result<()> {
+        match self {
+            Session::Setup => Ok(()),
+            Session::Operator { role, .. } if role.allows(permission) => Ok(()),
+            Session::Operator { username, role } => bail!(
+                "{username} is a {role} and may not {permission}, that requires the {} role",
+                permission.minimum_role()
+            ),
+            Session::Anonymous => {
+                bail!("No operator is logged in, run `login <USERNAME>` to {permission}")
+            }
+        }
+    }
+
+    /// The username of the logged in operator
+    pub fn username(&self) -> Option<&str> {
+        match self {
+            Session::Operator { username, .. } => Some(username),
+            _ => None,
+        }
+    }
+
+    /// Whether the session has at least the role, always true during setup
+    pub fn has_role(&self, role: Role) -> bool {
+        match self {
+            Session::Setup => true,
+            Session::Operator { role: own, .. } => *own >= role,
+            Session::Anonymous => false,
+        }
+    }
+}
+
+/// Two operators vouching for the checkout or return of a regulated item
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignOff {
+    /// The logged in operator handing out or taking back the item
+    pub operator: String,
+    /// The second operator witnessing it
+    pub witness: String,
+}
+
+impl SignOff {
+    /// A sign-off by the operator of the session and the witness, who has to be someone else
+    ///
+    /// The witness must already have proven who they are, this only checks who may take part.
+    pub fn new(session: &Session, witness: &str, witness_role: Role) -> Result<Self> {
+        let Session::Operator { username, .. } = session else {
+            bail!("Regulated items can only be handled by a logged in operator");
+        };
+        if username == witness {
+            bail!("{witness} cannot witness their own sign-off, another operator has to");
+        }
+        if !witness_role.allows(Permission::Checkout) {
+            bail!(
+                "{witness} is a {witness_role} and may not witness a sign-off, that requires the {} role",
+                Permission::Checkout.minimum_role()
+            );
+        }
+
+        Ok(Self {
+            operator: username.clone(),
+            witness: witness.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roles() {
+        assert!(Role::Viewer.allows(Permission::Read));
+        assert!(!Role::Viewer.allows(Permission::Checkout));
+        assert!(Role::Clerk.allows(Permission::Checkout));
+        assert!(!Role::Clerk.allows(Permission::Catalog));
+        assert!(Role::Quartermaster.allows(Permission::Catalog));
+        assert!(!Role::Quartermaster.allows(Permission::Manage));
+        assert!(!Role::Clerk.allows(Permission::FullSsns));
+        assert!(Role::Quartermaster.allows(Permission::FullSsns));
+        assert!(Role::Admin.allows(Permission::Manage));
+
+        assert_eq!(
+            "Quartermaster".parse::<Role>().unwrap(),
+            Role::Quartermaster
+        );
+        assert!("root".parse::<Role>().is_err());
+    }
+
+    #[test]
+    fn test_session() {
+        let clerk = Session::Operator {
+            username: "anna".to_string(),
+            role: Role::Clerk,
+        };
+
+        assert!(clerk.require(Permission::Checkout).is_ok());
+        assert!(clerk.require(Permission::Manage).is_err());
+        assert!(Session::Setup.require(Permission::Manage).is_ok());
+        assert!(Session::Anonymous.require(Permission::Read).is_err());
+
+        assert!(clerk.has_role(Role::Viewer));
+        assert!(!clerk.has_role(Role::Quartermaster));
+        assert!(Session::Setup.has_role(Role::Admin));
+    }
+
+    #[test]
+    fn test_sign_off() {
+        let clerk = Session::Operator {
+            username: "anna".to_string(),
+            role: Role::Clerk,
+        };
+
+        assert_eq!(
+            SignOff::new(&clerk, "erik", Role::Quartermaster).unwrap(),
+            SignOff {
+                operator: "anna".to_string(),
+                witness: "erik".to_string()
+            }
+        );
+        assert!(SignOff::new(&clerk, "anna", Role::Clerk).is_err());
+        assert!(SignOff::new(&clerk, "erik", Role::Viewer).is_err());
+        assert!(SignOff::new(&Session::Setup, "erik", Role::Admin).is_err());
+    }
+}
@@ -0,0 +1,177 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use prettytable::format::{FormatBuilder, LinePosition, LineSeparator};
+use prettytable::{Cell, Table};
+use serde::Serialize;
+
+/// The formats the `get` subcommands can print their results in
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// A human readable table
+    #[default]
+    Table,
+    /// A pretty printed JSON array
+    Json,
+    /// Comma separated values with a header row
+    Csv,
+    /// One JSON object per line
+    Ndjson,
+    /// A GitHub flavoured Markdown table
+    Markdown,
+}
+
+/// Collections of rows that can be serialized one record at a time.
+///
+/// Implemented for the wrapper types returned by the database handler, e.g. `DetailedItems`, so
+/// they can be printed in every [`OutputFormat`]. The field names of `Record` are the stable names
+/// used in the JSON and CSV output.
+pub trait Records {
+    type Record: Serialize;
+
+    fn records(&self) -> &[Self::Record];
+}
+
+/// Renders the data in the given format
+///
+/// The table and Markdown formats are built from the `Table` conversion of the data so they keep
+/// the same headers as before, the other formats serialize the records directly.
+///
+/// # Arguments
+///
+/// * `data`: The data to render.
+/// * `format`: The format to render the data in.
+///
+pub fn render<T>(data: T, format: OutputFormat) -> Result<String>
+where
+    T: Records + Into<Table>,
+{
+    let rendered = match format {
+        OutputFormat::Table => data.into().to_string(),
+        OutputFormat::Json => serde_json::to_string_pretty(data.records())?,
+        OutputFormat::Ndjson => data
+            .records()
+            .iter()
+            .map(serde_json::to_string)
+            .collect::<serde_json::Result<Vec<String>>>()?
+            .join("\n"),
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(Vec::new());
+            for record in data.records() {
+                writer.serialize(record)?;
+            }
+            String::from_utf8(writer.into_inner()?)?
+        }
+        OutputFormat::Markdown => to_markdown(data.into()),
+    };
+
+    Ok(rendered)
+}
+
+pub(crate) fn to_markdown(mut table: Table) -> String {
+    // A pipe in a cell would end the cell early
+    for row in table.row_iter_mut() {
+        for cell in row.iter_mut() {
+            if cell.get_content().contains('|') {
+                *cell = Cell::new(&cell.get_content().replace('|', "\\|"));
+            }
+        }
+    }
+    // The tables are built with the header as the first row, markdown needs it as the title
+    if let Some(header) = table.get_row(0).cloned() {
+        table.remove_row(0);
+        table.set_titles(header);
+    }
+
+    let format = FormatBuilder::new()
+        .column_separator('|')
+        .borders('|')
+        .separators(
+            &[LinePosition::Title],
+            LineSeparator::new('-', '|', '|', '|'),
+        )
+        .padding(1, 1)
+        .build();
+    table.set_format(format);
+
+    table.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prettytable::row;
+
+    #[derive(Serialize)]
+    struct Record {
+        id: String,
+        note: Option<String>,
+    }
+
+    struct TestRecords(Vec<Record>);
+
+    impl Records for TestRecords {
+        type Record = Record;
+
+        fn records(&self) -> &[Self::Record] {
+            &self.0
+        }
+    }
+
+    impl From<TestRecords> for Table {
+        fn from(records: TestRecords) -> Self {
+            let mut table = Table::new();
+            table.add_row(row!["ID", "Note"]);
+            for record in records.0 {
+                table.add_row(row![record.id, record.note.unwrap_or_default()]);
+            }
+            table
+        }
+    }
+
+    fn test_records() -> TestRecords {
+        TestRecords(vec![
+            Record {
+                id: "a".to_string(),
+                note: Some("x, y".to_string()),
+            },
+            Record {
+                id: "b".to_string(),
+                note: None,
+            },
+        ])
+    }
+
+    #[test]
+    fn test_csv() {
+        let csv = render(test_records(), OutputFormat::Csv).unwrap();
+        assert_eq!(csv, "id,note\na,\"x, y\"\nb,\n");
+    }
+
+    #[test]
+    fn test_ndjson() {
+        let ndjson = render(test_records(), OutputFormat::Ndjson).unwrap();
+        assert_eq!(
+            ndjson,
+            "{\"id\":\"a\",\"note\":\"x, y\"}\n{\"id\":\"b\",\"note\":null}"
+        );
+    }
+
+    #[test]
+    fn test_markdown() {
+        let markdown = render(test_records(), OutputFormat::Markdown).unwrap();
+        let lines: Vec<&str> = markdown.lines().collect();
+        assert_eq!(lines[0], "| ID | Note |");
+        assert_eq!(lines[1], "|----|------|");
+        assert_eq!(lines[3], "| b  |      |");
+    }
+
+    #[test]
+    fn test_markdown_escapes_pipes() {
+        let records = TestRecords(vec![Record {
+            id: "a|b".to_string(),
+            note: None,
+        }]);
+        let markdown = render(records, OutputFormat::Markdown).unwrap();
+        assert_eq!(markdown.lines().nth(2), Some("| a\\|b |      |"));
+    }
+}
@@ -0,0 +1,192 @@
+//! A minimal PDF writer for the printable forms
+//!
+//! Only what the forms need is supported: A4 pages with text in the standard PDF fonts and
+//! straight lines. The standard fonts need no embedding, so the files stay small and are
+//! readable by every viewer. Text is encoded as WinAnsi, which covers the Swedish letters.
+
+/// The width of an A4 page in points
+pub const PAGE_WIDTH: f32 = 595.0;
+/// The height of an A4 page in points
+pub const PAGE_HEIGHT: f32 = 842.0;
+
+/// The fonts every PDF viewer has built in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Font {
+    Courier,
+    Helvetica,
+    HelveticaBold,
+}
+
+impl Font {
+    const ALL: [Font; 3] = [Font::Courier, Font::Helvetica, Font::HelveticaBold];
+
+    fn base_font(self) -> &'static str {
+        match self {
+            Font::Courier => "Courier",
+            Font::Helvetica => "Helvetica",
+            Font::HelveticaBold => "Helvetica-Bold",
+        }
+    }
+
+    fn resource_name(self) -> &'static str {
+        match self {
+            Font::Courier => "F1",
+            Font::Helvetica => "F2",
+            Font::HelveticaBold => "F3",
+        }
+    }
+}
+
+/// A page being drawn, coordinates are in points from the bottom left corner
+#[derive(Debug, Default, Clone)]
+pub struct Page {
+    content: String,
+}
+
+impl Page {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Draws a line of text with its baseline starting at `x`, `y`
+    pub fn text(&mut self, font: Font, size: f32, x: f32, y: f32, text: &str) {
+        self.content.push_str(&format!(
+            "BT /{} {size} Tf {x} {y} Td ({}) Tj ET\n",
+            font.resource_name(),
+            escape(text)
+        ));
+    }
+
+    /// Draws a straight line
+    pub fn line(&mut self, from: (f32, f32), to: (f32, f32)) {
+        self.content.push_str(&format!(
+            "0.5 w {} {} m {} {} l S\n",
+            from.0, from.1, to.0, to.1
+        ));
+    }
+}
+
+/// Writes the pages to a complete PDF file
+pub fn write(pages: &[Page]) -> Vec<u8> {
+    // Objects 1 and 2 are the catalog and the page tree, then one per font and two per page
+    let first_page = 3 + Font::ALL.len();
+    let mut objects: Vec<Vec<u8>> = Vec::new();
+
+    objects.push(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+    let kids: Vec<String> = (0..pages.len())
+        .map(|page| format!("{} 0 R", first_page + 2 * page))
+        .collect();
+    objects.push(
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            pages.len()
+        )
+        .into_bytes(),
+    );
+
+    let fonts: Vec<String> = Font::ALL
+        .iter()
+        .enumerate()
+        .map(|(index, font)| format!("/{} {} 0 R", font.resource_name(), 3 + index))
+        .collect();
+    for font in Font::ALL {
+        objects.push(
+            format!(
+                "<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /WinAnsiEncoding >>",
+                font.base_font()
+            )
+            .into_bytes(),
+        );
+    }
+
+    for (index, page) in pages.iter().enumerate() {
+        let contents = first_page + 2 * index + 1;
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {PAGE_WIDTH} {PAGE_HEIGHT}] /Resources << /Font << {} >> >> /Contents {contents} 0 R >>",
+                fonts.join(" ")
+            )
+            .into_bytes(),
+        );
+
+        let stream = encode(&page.content);
+        let mut object = format!("<< /Length {} >>\nstream\n", stream.len()).into_bytes();
+        object.extend(stream);
+        object.extend(b"\nendstream");
+        objects.push(object);
+    }
+
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (index, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend(format!("{} 0 obj\n", index + 1).into_bytes());
+        pdf.extend(object);
+        pdf.extend(b"\nendobj\n");
+    }
+
+    let xref = pdf.len();
+    pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).into_bytes());
+    for offset in offsets {
+        pdf.extend(format!("{offset:010} 00000 n \n").into_bytes());
+    }
+    pdf.extend(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+            objects.len() + 1
+        )
+        .into_bytes(),
+    );
+
+    pdf
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('(', "\\(")
+        .replace(')', "\\)")
+}
+
+/// Encodes the content as WinAnsi, which matches Latin-1 for the letters used here
+fn encode(content: &str) -> Vec<u8> {
+    content
+        .chars()
+        .map(|c| match c as u32 {
+            code @ (0x0A | 0x20..=0x7E | 0xA0..=0xFF) => code as u8,
+            _ => b'?',
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write() {
+        let mut page = Page::new();
+        page.text(Font::HelveticaBold, 14.0, 50.0, 800.0, "Kvitto (Åsa Öberg)");
+        page.line((50.0, 100.0), (250.0, 100.0));
+        let pdf = write(&[page, Page::new()]);
+
+        assert!(pdf.starts_with(b"%PDF-1.4\n"));
+        assert!(pdf.ends_with(b"%%EOF\n"));
+        let expected = b"(Kvitto \\(\xC5sa \xD6berg\\)) Tj";
+        assert!(pdf.windows(expected.len()).any(|window| window == expected));
+
+        // Every offset in the cross-reference table has to point at the start of its object
+        let text = String::from_utf8_lossy(&pdf);
+        let xref = text.find("xref\n").unwrap();
+        let offsets: Vec<usize> = text[xref..]
+            .lines()
+            .skip(3)
+            .take_while(|line| line.ends_with(" n "))
+            .map(|line| line[..10].parse().unwrap())
+            .collect();
+        assert_eq!(offsets.len(), 2 + 3 + 2 * 2);
+        for (index, offset) in offsets.into_iter().enumerate() {
+            assert!(pdf[offset..].starts_with(format!("{} 0 obj", index + 1).as_bytes()));
+        }
+    }
+}
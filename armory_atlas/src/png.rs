@@ -0,0 +1,112 @@
+//! A minimal PNG encoder for the label sheets
+//!
+//! Writes 8-bit grayscale images without interlacing, which is all the labels need. The image
+//! data is compressed with flate2 like the archives.
+
+use std::io::Write;
+
+use anyhow::{ensure, Result};
+use flate2::write::ZlibEncoder;
+use flate2::{Compression, Crc};
+
+/// A grayscale image, 0 is black and 255 is white
+#[derive(Debug, Clone)]
+pub struct GrayImage {
+    pub width: usize,
+    pub height: usize,
+    pixels: Vec<u8>,
+}
+
+impl GrayImage {
+    /// A white image
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![255; width * height],
+        }
+    }
+
+    /// Paints the pixels from `x0`, `y0` up to, but not including, `x1`, `y1` black
+    ///
+    /// Parts outside of the image are ignored.
+    pub fn fill(&mut self, x0: usize, y0: usize, x1: usize, y1: usize) {
+        for y in y0..y1.min(self.height) {
+            let row = y * self.width;
+            for x in x0..x1.min(self.width) {
+                self.pixels[row + x] = 0;
+            }
+        }
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> u8 {
+        self.pixels[y * self.width + x]
+    }
+
+    /// Encodes the image as a PNG file
+    pub fn to_png(&self) -> Result<Vec<u8>> {
+        ensure!(
+            self.width > 0 && self.height > 0,
+            "An image needs at least one pixel"
+        );
+
+        let mut header = Vec::with_capacity(13);
+        header.extend((self.width as u32).to_be_bytes());
+        header.extend((self.height as u32).to_be_bytes());
+        // Bit depth 8, grayscale, deflate, adaptive filtering, no interlacing
+        header.extend([8, 0, 0, 0, 0]);
+
+        // Every row starts with its filter type, 0 leaves the row as it is
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        for row in self.pixels.chunks(self.width) {
+            encoder.write_all(&[0])?;
+            encoder.write_all(row)?;
+        }
+        let data = encoder.finish()?;
+
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        chunk(&mut png, b"IHDR", &header);
+        chunk(&mut png, b"IDAT", &data);
+        chunk(&mut png, b"IEND", &[]);
+        Ok(png)
+    }
+}
+
+fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend((data.len() as u32).to_be_bytes());
+    png.extend(kind);
+    png.extend(data);
+
+    let mut crc = Crc::new();
+    crc.update(kind);
+    crc.update(data);
+    png.extend(crc.sum().to_be_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::ZlibDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn test_to_png() {
+        let mut image = GrayImage::new(3, 2);
+        image.fill(1, 0, 5, 1);
+        let png = image.to_png().unwrap();
+
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+        assert_eq!(&png[8..16], b"\0\0\0\x0dIHDR");
+        assert_eq!(&png[16..24], &[0, 0, 0, 3, 0, 0, 0, 2]);
+        // The CRC of an empty IEND chunk is always the same
+        assert!(png.ends_with(b"\0\0\0\0IEND\xae\x42\x60\x82"));
+
+        let length = u32::from_be_bytes(png[33..37].try_into().unwrap()) as usize;
+        assert_eq!(&png[37..41], b"IDAT");
+        let mut rows = Vec::new();
+        ZlibDecoder::new(&png[41..41 + length])
+            .read_to_end(&mut rows)
+            .unwrap();
+        assert_eq!(rows, vec![0, 255, 0, 0, 0, 255, 255, 255]);
+    }
+}
@@ -0,0 +1,227 @@
+use std::collections::BTreeMap;
+
+use anyhow::{bail, Result};
+use rand::Rng;
+use rand_distr::{Distribution, Normal};
+use serde::{Deserialize, Serialize};
+
+use crate::items::SIZES;
+use crate::products::Product;
+
+/// A number of items of a product, used for the stock `generate items` keeps and the minimum
+/// stock level
+///
+/// Written as a single number for the same target for every size, or as an object with a target
+/// per size, e.g. `{"M": 10, "L": 5}`. Sizes left out of the object have a target of 0.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum StockTarget {
+    All(usize),
+    PerSize(BTreeMap<String, usize>),
+}
+
+impl StockTarget {
+    /// The target for a size, `None` for products without sizes
+    pub fn target(&self, size: Option<&str>) -> usize {
+        match (self, size) {
+            (StockTarget::All(target), _) => *target,
+            (StockTarget::PerSize(targets), Some(size)) => targets.get(size).copied().unwrap_or(0),
+            (StockTarget::PerSize(_), None) => 0,
+        }
+    }
+}
+
+/// How the quality of generated items is distributed, always kept between 0 and 1
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "Distribution")]
+pub enum QualityDistribution {
+    Uniform {
+        #[serde(rename = "Min")]
+        min: f32,
+        #[serde(rename = "Max")]
+        max: f32,
+    },
+    Normal {
+        #[serde(rename = "Mean")]
+        mean: f32,
+        #[serde(rename = "StdDev")]
+        std_dev: f32,
+    },
+}
+
+impl Default for QualityDistribution {
+    fn default() -> Self {
+        QualityDistribution::Uniform { min: 0.0, max: 1.0 }
+    }
+}
+
+impl QualityDistribution {
+    pub fn sample(&self, rng: &mut impl Rng) -> f32 {
+        let quality = match *self {
+            QualityDistribution::Uniform { min, max } if min < max => rng.gen_range(min..max),
+            QualityDistribution::Uniform { min, .. } => min,
+            QualityDistribution::Normal { mean, std_dev } => Normal::new(mean, std_dev)
+                .map(|normal| normal.sample(rng))
+                .unwrap_or(mean),
+        };
+
+        quality.clamp(0.0, 1.0)
+    }
+
+    fn validate(&self) -> Result<()> {
+        match *self {
+            QualityDistribution::Uniform { min, max } => {
+                if !(0.0..=1.0).contains(&min) || !(0.0..=1.0).contains(&max) || min > max {
+                    bail!("Min and Max must be between 0 and 1 with Min no larger than Max");
+                }
+            }
+            QualityDistribution::Normal { mean, std_dev } => {
+                if !(0.0..=1.0).contains(&mean) || !std_dev.is_finite() || std_dev < 0.0 {
+                    bail!("Mean must be between 0 and 1 and StdDev zero or more");
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Product {
+    /// The sizes items of the product come in, a single `None` for products without sizes
+    pub fn generated_sizes(&self) -> Vec<Option<&str>> {
+        match &self.sizes {
+            None => SIZES.iter().map(|size| Some(*size)).collect(),
+            Some(sizes) if sizes.is_empty() => vec![None],
+            Some(sizes) => sizes.iter().map(|size| Some(size.as_str())).collect(),
+        }
+    }
+
+    /// Checks the generation settings of a product from the catalog
+    pub fn validate_catalog_entry(&self) -> Result<()> {
+        let context = |message: String| format!("{}: {message}", self.product_id);
+
+        for size in self.sizes.iter().flatten() {
+            if !SIZES.contains(&size.as_str()) {
+                bail!(context(format!(
+                    "{size} is not a known size, expected one of {}",
+                    SIZES.join(", ")
+                )));
+            }
+        }
+
+        if self.consumable && (self.generated_sizes() != vec![None]) {
+            bail!(context(
+                "is a consumable, which are stocked per product and must have \"Sizes\": []"
+                    .to_string()
+            ));
+        }
+
+        for (name, target) in [
+            ("stock target", &self.stock),
+            ("minimum stock", &self.min_stock),
+        ] {
+            if let Some(StockTarget::PerSize(targets)) = target {
+                let sizes = self.generated_sizes();
+                for size in targets.keys() {
+                    if !sizes.contains(&Some(size.as_str())) {
+                        bail!(context(format!(
+                            "has a {name} for {size} which is not one of its sizes"
+                        )));
+                    }
+                }
+            }
+        }
+
+        if let Some(quality) = &self.quality {
+            quality
+                .validate()
+                .map_err(|err| anyhow::anyhow!(context(err.to_string())))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn product(json: &str) -> Product {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_catalog_entry() {
+        let flare = product(
+            r#"{"ProductID": "M1", "NameOfProduct": "Signal Flare", "Type": "Equipment",
+                "Sizes": [], "Stock": 20, "Quality": {"Distribution": "Normal", "Mean": 0.9, "StdDev": 0.05}}"#,
+        );
+        assert_eq!(flare.generated_sizes(), vec![None]);
+        assert_eq!(flare.stock.as_ref().unwrap().target(None), 20);
+        assert!(!flare.consumable);
+        assert!(flare.validate_catalog_entry().is_ok());
+
+        let tablets = product(
+            r#"{"ProductID": "M4", "NameOfProduct": "Water Purification Tablets", "Type": "Supplies",
+                "Sizes": [], "Stock": 50, "Consumable": true}"#,
+        );
+        assert!(tablets.consumable);
+        assert!(tablets.validate_catalog_entry().is_ok());
+
+        let boots = product(
+            r#"{"ProductID": "M2", "NameOfProduct": "Tactical Boots", "Type": "Footwear",
+                "Sizes": ["M", "L"], "Stock": {"M": 10, "L": 5}}"#,
+        );
+        assert_eq!(boots.generated_sizes(), vec![Some("M"), Some("L")]);
+        assert_eq!(boots.stock.as_ref().unwrap().target(Some("L")), 5);
+        assert!(boots.validate_catalog_entry().is_ok());
+
+        let cap = product(r#"{"ProductID": "M3", "NameOfProduct": "Field Cap", "Type": "Hat"}"#);
+        assert_eq!(cap.generated_sizes().len(), SIZES.len());
+        assert!(cap.validate_catalog_entry().is_ok());
+    }
+
+    #[test]
+    fn test_invalid_catalog_entries() {
+        let unknown_size = product(
+            r#"{"ProductID": "M1", "NameOfProduct": "Boots", "Type": "Footwear", "Sizes": ["XXXL"]}"#,
+        );
+        assert!(unknown_size.validate_catalog_entry().is_err());
+
+        let stock_for_missing_size = product(
+            r#"{"ProductID": "M1", "NameOfProduct": "Boots", "Type": "Footwear",
+                "Sizes": ["M"], "Stock": {"L": 5}}"#,
+        );
+        assert!(stock_for_missing_size.validate_catalog_entry().is_err());
+
+        let minimum_for_missing_size = product(
+            r#"{"ProductID": "M1", "NameOfProduct": "Boots", "Type": "Footwear",
+                "Sizes": ["M"], "MinStock": {"L": 2}}"#,
+        );
+        assert!(minimum_for_missing_size.validate_catalog_entry().is_err());
+
+        let bad_quality = product(
+            r#"{"ProductID": "M1", "NameOfProduct": "Boots", "Type": "Footwear",
+                "Quality": {"Distribution": "Uniform", "Min": 0.8, "Max": 0.2}}"#,
+        );
+        assert!(bad_quality.validate_catalog_entry().is_err());
+
+        let sized_consumable = product(
+            r#"{"ProductID": "M1", "NameOfProduct": "Rations", "Type": "Supplies", "Consumable": true}"#,
+        );
+        assert!(sized_consumable.validate_catalog_entry().is_err());
+    }
+
+    #[test]
+    fn test_quality_is_clamped() {
+        let quality = QualityDistribution::Normal {
+            mean: 0.95,
+            std_dev: 0.5,
+        };
+        let mut rng = crate::seeded_rng(Some(1));
+
+        assert!((0..100)
+            .map(|_| quality.sample(&mut rng))
+            .all(|quality| (0.0..=1.0).contains(&quality)));
+    }
+}
@@ -1,7 +1,10 @@
+pub mod catalog;
 #[cfg(feature = "python-db")]
 mod python_impl;
+pub mod reorder;
 
 use crate::cli::InsertProductArgs;
+use crate::products::catalog::{QualityDistribution, StockTarget};
 #[cfg(feature = "python-db")]
 use crate::python_db_handler::DBHandlerPy as DBHandler;
 use crate::{CONFIG_DIR, DEFAULT_PRODUCTS, PRODUCTS_FILE};
@@ -10,7 +13,11 @@ use serde::{Deserialize, Serialize};
 use std::io::{Read, Write};
 use std::path::PathBuf;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+/// A product, as stored in the database and in the `products.json` catalog
+///
+/// The catalog can also say how `generate items` stocks the product and the minimum stock level,
+/// these settings are left out for products read from the database.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 #[cfg_attr(feature = "python-db", pyo3::pyclass)]
 pub struct Product {
     #[serde(rename = "ProductID")]
@@ -19,6 +26,26 @@ pub struct Product {
     pub product_name: String,
     #[serde(rename = "Type")]
     pub product_type: String,
+    /// The sizes the product comes in, empty for products without sizes and every size in
+    /// [`SIZES`](crate::items::SIZES) if not given
+    #[serde(rename = "Sizes", default, skip_serializing_if = "Option::is_none")]
+    pub sizes: Option<Vec<String>>,
+    /// How many items `generate items` keeps in stock, `num_items` per product if not given
+    #[serde(rename = "Stock", default, skip_serializing_if = "Option::is_none")]
+    pub stock: Option<StockTarget>,
+    /// The least number of available items before the product shows up in `get low-stock`
+    #[serde(rename = "MinStock", default, skip_serializing_if = "Option::is_none")]
+    pub min_stock: Option<StockTarget>,
+    /// The quality of generated items, uniform between 0 and 1 if not given
+    #[serde(rename = "Quality", default, skip_serializing_if = "Option::is_none")]
+    pub quality: Option<QualityDistribution>,
+    /// Consumables are kept as a quantity on hand and issued, not lent out as single items
+    #[serde(
+        rename = "Consumable",
+        default,
+        skip_serializing_if = "std::ops::Not::not"
+    )]
+    pub consumable: bool,
 }
 
 impl Product {
@@ -27,6 +54,7 @@ impl Product {
             product_id,
             product_name,
             product_type,
+            ..Default::default()
         }
     }
 }
@@ -37,6 +65,7 @@ impl From<InsertProductArgs> for Product {
             product_id: args.product_id,
             product_name: args.product_name,
             product_type: args.product_type,
+            ..Default::default()
         }
     }
 }
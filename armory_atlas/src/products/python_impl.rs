@@ -9,6 +9,7 @@ impl Product {
             product_id,
             product_name,
             product_type,
+            ..Default::default()
         }
     }
 
@@ -0,0 +1,302 @@
+use std::collections::HashMap;
+
+use prettytable::{row, Row, Table};
+use serde::Serialize;
+
+use crate::output::Records;
+use crate::products::Product;
+
+/// How many items of a product and size are available, and how many were lent out recently
+///
+/// For consumables these are the quantity on hand and the number of units issued.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StockLevel {
+    pub product_id: String,
+    /// `None` for products without sizes
+    pub size: Option<String>,
+    pub available: usize,
+    pub recent_demand: usize,
+}
+
+/// A product and size that is below its minimum stock level
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LowStock {
+    pub product_id: String,
+    pub product_name: String,
+    pub size: Option<String>,
+    pub available: usize,
+    pub minimum: usize,
+    pub recent_demand: usize,
+    /// How many to order to get back to the minimum and cover the recent demand once more
+    pub reorder: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct LowStockReport(Vec<LowStock>);
+
+/// How many items of a product and size are available, compared to the minimum stock level
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StockStatus {
+    pub product_id: String,
+    pub product_name: String,
+    pub size: Option<String>,
+    pub available: usize,
+    /// `None` for products without a `MinStock`
+    pub minimum: Option<usize>,
+    pub low: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct StockStatusReport(Vec<StockStatus>);
+
+/// Compares the stock levels against the minimum stock levels of the catalog
+///
+/// Every size of every product with a `MinStock` is checked, sizes without any items count as
+/// having none available. The suggested reorder quantity brings the stock up to the minimum plus
+/// the demand seen in the recent period, so the minimum still holds if the demand keeps up until
+/// the order arrives.
+pub fn low_stock(products: &[Product], levels: &[StockLevel]) -> LowStockReport {
+    let levels: HashMap<(&str, Option<&str>), &StockLevel> = levels
+        .iter()
+        .map(|level| ((level.product_id.as_str(), level.size.as_deref()), level))
+        .collect();
+
+    let mut report = Vec::new();
+    for product in products {
+        let Some(min_stock) = &product.min_stock else {
+            continue;
+        };
+
+        for size in product.generated_sizes() {
+            let minimum = min_stock.target(size);
+            let (available, recent_demand) = levels
+                .get(&(product.product_id.as_str(), size))
+                .map_or((0, 0), |level| (level.available, level.recent_demand));
+
+            if available < minimum {
+                report.push(LowStock {
+                    product_id: product.product_id.clone(),
+                    product_name: product.product_name.clone(),
+                    size: size.map(str::to_string),
+                    available,
+                    minimum,
+                    recent_demand,
+                    reorder: minimum + recent_demand - available,
+                });
+            }
+        }
+    }
+
+    LowStockReport(report)
+}
+
+/// The available count of every size of every product in the catalog
+pub fn stock_status(products: &[Product], levels: &[StockLevel]) -> StockStatusReport {
+    let levels: HashMap<(&str, Option<&str>), usize> = levels
+        .iter()
+        .map(|level| {
+            (
+                (level.product_id.as_str(), level.size.as_deref()),
+                level.available,
+            )
+        })
+        .collect();
+
+    let mut report = Vec::new();
+    for product in products {
+        for size in product.generated_sizes() {
+            let available = levels
+                .get(&(product.product_id.as_str(), size))
+                .copied()
+                .unwrap_or_default();
+            let minimum = product
+                .min_stock
+                .as_ref()
+                .map(|min_stock| min_stock.target(size));
+
+            report.push(StockStatus {
+                product_id: product.product_id.clone(),
+                product_name: product.product_name.clone(),
+                size: size.map(str::to_string),
+                available,
+                minimum,
+                low: minimum.is_some_and(|minimum| available < minimum),
+            });
+        }
+    }
+
+    StockStatusReport(report)
+}
+
+impl Records for LowStockReport {
+    type Record = LowStock;
+
+    fn records(&self) -> &[Self::Record] {
+        &self.0
+    }
+}
+
+impl From<&LowStock> for Row {
+    fn from(value: &LowStock) -> Self {
+        row![
+            value.product_id,
+            value.product_name,
+            value.size.clone().unwrap_or_default(),
+            value.available,
+            value.minimum,
+            value.recent_demand,
+            value.reorder
+        ]
+    }
+}
+
+impl From<LowStockReport> for Table {
+    fn from(report: LowStockReport) -> Self {
+        let mut table = Table::new();
+        table.add_row(row![
+            "Product ID",
+            "Product Name",
+            "Size",
+            "Available",
+            "Minimum",
+            "Recent Demand",
+            "Reorder"
+        ]);
+        for low in report.0 {
+            table.add_row((&low).into());
+        }
+        table
+    }
+}
+
+impl Records for StockStatusReport {
+    type Record = StockStatus;
+
+    fn records(&self) -> &[Self::Record] {
+        &self.0
+    }
+}
+
+impl From<&StockStatus> for Row {
+    fn from(value: &StockStatus) -> Self {
+        row![
+            value.product_id,
+            value.product_name,
+            value.size.clone().unwrap_or_default(),
+            value.available,
+            value
+                .minimum
+                .map(|minimum| minimum.to_string())
+                .unwrap_or_default(),
+            if value.low { "Low" } else { "" }
+        ]
+    }
+}
+
+impl From<StockStatusReport> for Table {
+    fn from(report: StockStatusReport) -> Self {
+        let mut table = Table::new();
+        table.add_row(row![
+            "Product ID",
+            "Product Name",
+            "Size",
+            "Available",
+            "Minimum",
+            "Status"
+        ]);
+        for status in report.0 {
+            table.add_row((&status).into());
+        }
+        table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn level(product_id: &str, size: Option<&str>, available: usize, demand: usize) -> StockLevel {
+        StockLevel {
+            product_id: product_id.to_string(),
+            size: size.map(str::to_string),
+            available,
+            recent_demand: demand,
+        }
+    }
+
+    #[test]
+    fn test_low_stock() {
+        let products: Vec<Product> = serde_json::from_str(
+            r#"[
+                {"ProductID": "M1", "NameOfProduct": "Signal Flare", "Type": "Equipment", "Sizes": [], "MinStock": 10, "Consumable": true},
+                {"ProductID": "M2", "NameOfProduct": "Tactical Boots", "Type": "Footwear", "Sizes": ["M", "L"], "MinStock": {"M": 4}},
+                {"ProductID": "M3", "NameOfProduct": "Field Cap", "Type": "Hat", "Sizes": ["S"]}
+            ]"#,
+        )
+        .unwrap();
+        let levels = vec![
+            level("M1", None, 6, 8),
+            level("M2", Some("M"), 4, 3),
+            level("M2", Some("L"), 0, 2),
+            level("M3", Some("S"), 0, 5),
+        ];
+
+        let report = low_stock(&products, &levels);
+        assert_eq!(
+            report.records(),
+            &[LowStock {
+                product_id: "M1".to_string(),
+                product_name: "Signal Flare".to_string(),
+                size: None,
+                available: 6,
+                minimum: 10,
+                recent_demand: 8,
+                reorder: 12,
+            }]
+        );
+
+        let report = low_stock(&products, &[]);
+        let short: Vec<(&str, Option<&str>, usize)> = report
+            .records()
+            .iter()
+            .map(|low| (low.product_id.as_str(), low.size.as_deref(), low.reorder))
+            .collect();
+        assert_eq!(short, vec![("M1", None, 10), ("M2", Some("M"), 4)]);
+    }
+
+    #[test]
+    fn test_stock_status() {
+        let products: Vec<Product> = serde_json::from_str(
+            r#"[
+                {"ProductID": "M2", "NameOfProduct": "Tactical Boots", "Type": "Footwear", "Sizes": ["M", "L"], "MinStock": {"M": 4}},
+                {"ProductID": "M3", "NameOfProduct": "Field Cap", "Type": "Hat", "Sizes": []}
+            ]"#,
+        )
+        .unwrap();
+        let report = stock_status(
+            &products,
+            &[level("M2", Some("M"), 3, 0), level("M3", None, 2, 1)],
+        );
+
+        let status: Vec<(Option<&str>, usize, Option<usize>, bool)> = report
+            .records()
+            .iter()
+            .map(|status| {
+                (
+                    status.size.as_deref(),
+                    status.available,
+                    status.minimum,
+                    status.low,
+                )
+            })
+            .collect();
+        assert_eq!(
+            status,
+            vec![
+                (Some("M"), 3, Some(4), true),
+                (Some("L"), 0, Some(0), false),
+                (None, 2, None, false)
+            ]
+        );
+    }
+}
@@ -0,0 +1,161 @@
+use chrono::NaiveDate;
+use prettytable::{row, Row, Table};
+use pyo3::{pyclass, pymethods, FromPyObject};
+use serde::Serialize;
+
+use crate::output::Records;
+use crate::users::ssn::{mask_ssn, MaskSsns};
+
+#[derive(FromPyObject)]
+pub struct PyAccountabilityEntry {
+    pub item_id: String,
+    pub serial_number: Option<String>,
+    pub product_name: String,
+    pub product_type: String,
+    pub status: String,
+    pub ssn: Option<String>,
+    pub name: Option<String>,
+    pub unit: Option<String>,
+    pub since: Option<NaiveDate>,
+    pub operator: Option<String>,
+    pub witness: Option<String>,
+}
+
+/// Where a regulated item was at the end of a day, and who signed it out if it was lent out
+#[derive(Clone, Debug, Serialize)]
+#[pyclass(get_all)]
+pub struct AccountabilityEntry {
+    pub item_id: String,
+    pub serial_number: Option<String>,
+    pub product_name: String,
+    pub product_type: String,
+    pub status: String,
+    pub ssn: Option<String>,
+    pub name: Option<String>,
+    pub unit: Option<String>,
+    pub since: Option<NaiveDate>,
+    pub operator: Option<String>,
+    pub witness: Option<String>,
+}
+
+#[pymethods]
+impl AccountabilityEntry {
+    #[pyo3(name = "__repr__")]
+    pub fn repr(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    #[pyo3(name = "__str__")]
+    pub fn str(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+#[derive(Debug)]
+#[pyclass]
+pub struct AccountabilityReport(Vec<AccountabilityEntry>);
+
+#[pymethods]
+impl AccountabilityReport {
+    #[getter(entries)]
+    fn get_entries(&self) -> Vec<AccountabilityEntry> {
+        self.0.clone()
+    }
+
+    #[pyo3(name = "__repr__")]
+    pub fn repr(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    #[pyo3(name = "__str__")]
+    pub fn str(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+impl From<PyAccountabilityEntry> for AccountabilityEntry {
+    fn from(py_entry: PyAccountabilityEntry) -> Self {
+        Self {
+            item_id: py_entry.item_id,
+            serial_number: py_entry.serial_number,
+            product_name: py_entry.product_name,
+            product_type: py_entry.product_type,
+            status: py_entry.status,
+            ssn: py_entry.ssn,
+            name: py_entry.name,
+            unit: py_entry.unit,
+            since: py_entry.since,
+            operator: py_entry.operator,
+            witness: py_entry.witness,
+        }
+    }
+}
+
+impl From<Vec<AccountabilityEntry>> for AccountabilityReport {
+    fn from(entries: Vec<AccountabilityEntry>) -> Self {
+        Self(entries)
+    }
+}
+
+impl From<AccountabilityReport> for Vec<AccountabilityEntry> {
+    fn from(report: AccountabilityReport) -> Self {
+        report.0
+    }
+}
+
+impl Records for AccountabilityReport {
+    type Record = AccountabilityEntry;
+
+    fn records(&self) -> &[Self::Record] {
+        &self.0
+    }
+}
+
+impl MaskSsns for AccountabilityReport {
+    fn mask_ssns(&mut self) {
+        for entry in &mut self.0 {
+            entry.ssn = entry.ssn.as_deref().map(mask_ssn);
+        }
+    }
+}
+
+impl From<&AccountabilityEntry> for Row {
+    fn from(value: &AccountabilityEntry) -> Self {
+        row![
+            value.item_id,
+            value.serial_number.clone().unwrap_or_default(),
+            value.product_name,
+            value.product_type,
+            value.status,
+            value.ssn.clone().unwrap_or_default(),
+            value.name.clone().unwrap_or_default(),
+            value.unit.clone().unwrap_or_default(),
+            value.since.map(|date| date.to_string()).unwrap_or_default(),
+            value.operator.clone().unwrap_or_default(),
+            value.witness.clone().unwrap_or_default()
+        ]
+    }
+}
+
+impl From<AccountabilityReport> for Table {
+    fn from(report: AccountabilityReport) -> Self {
+        let mut table = Table::new();
+        table.add_row(row![
+            "Item ID",
+            "Serial",
+            "Product Name",
+            "Product Type",
+            "Status",
+            "SSN",
+            "Name",
+            "Unit",
+            "Since",
+            "Signed Out By",
+            "Witness"
+        ]);
+        for entry in report.0 {
+            table.add_row((&entry).into());
+        }
+        table
+    }
+}
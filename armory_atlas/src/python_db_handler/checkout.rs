@@ -0,0 +1,23 @@
+use chrono::NaiveDate;
+use pyo3::FromPyObject;
+
+use crate::leandings::rules::CheckoutFacts;
+use crate::python_db_handler::users::PyUser;
+use crate::users::User;
+
+#[derive(FromPyObject)]
+pub struct PyCheckoutFacts {
+    pub user: PyUser,
+    pub product_type: String,
+    pub held: Vec<(String, NaiveDate)>,
+}
+
+impl From<PyCheckoutFacts> for CheckoutFacts {
+    fn from(py_facts: PyCheckoutFacts) -> Self {
+        Self {
+            user: User::from(py_facts.user),
+            product_type: py_facts.product_type,
+            held: py_facts.held,
+        }
+    }
+}
@@ -0,0 +1,239 @@
+use prettytable::{row, Row, Table};
+use pyo3::{pyclass, pymethods, FromPyObject};
+use serde::Serialize;
+
+use crate::output::Records;
+use crate::users::ssn::{mask_ssn, MaskSsns};
+
+#[derive(FromPyObject)]
+pub struct PyConsumableStock {
+    pub product_id: String,
+    pub product_name: String,
+    pub product_type: String,
+    pub quantity: u32,
+}
+
+/// The quantity on hand of a consumable
+#[derive(Clone, Debug, Serialize)]
+#[pyclass(get_all)]
+pub struct ConsumableStock {
+    pub product_id: String,
+    pub product_name: String,
+    pub product_type: String,
+    pub quantity: u32,
+}
+
+#[pymethods]
+impl ConsumableStock {
+    #[pyo3(name = "__repr__")]
+    pub fn repr(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    #[pyo3(name = "__str__")]
+    pub fn str(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+#[derive(Debug)]
+#[pyclass]
+pub struct ConsumableStocks(Vec<ConsumableStock>);
+
+#[pymethods]
+impl ConsumableStocks {
+    #[getter(stock)]
+    fn get_stock(&self) -> Vec<ConsumableStock> {
+        self.0.clone()
+    }
+
+    #[pyo3(name = "__repr__")]
+    pub fn repr(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    #[pyo3(name = "__str__")]
+    pub fn str(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+impl From<PyConsumableStock> for ConsumableStock {
+    fn from(py_stock: PyConsumableStock) -> Self {
+        Self {
+            product_id: py_stock.product_id,
+            product_name: py_stock.product_name,
+            product_type: py_stock.product_type,
+            quantity: py_stock.quantity,
+        }
+    }
+}
+
+impl From<Vec<ConsumableStock>> for ConsumableStocks {
+    fn from(stock: Vec<ConsumableStock>) -> Self {
+        Self(stock)
+    }
+}
+
+impl Records for ConsumableStocks {
+    type Record = ConsumableStock;
+
+    fn records(&self) -> &[Self::Record] {
+        &self.0
+    }
+}
+
+impl From<&ConsumableStock> for Row {
+    fn from(value: &ConsumableStock) -> Self {
+        row![
+            value.product_id,
+            value.product_name,
+            value.product_type,
+            value.quantity
+        ]
+    }
+}
+
+impl From<ConsumableStocks> for Table {
+    fn from(stock: ConsumableStocks) -> Self {
+        let mut table = Table::new();
+        table.add_row(row![
+            "Product ID",
+            "Product Name",
+            "Product Type",
+            "On Hand"
+        ]);
+        for stock in stock.0 {
+            table.add_row((&stock).into());
+        }
+        table
+    }
+}
+
+#[derive(FromPyObject)]
+pub struct PyConsumption {
+    pub ssn: Option<String>,
+    pub name: Option<String>,
+    pub unit: Option<String>,
+    pub product_id: String,
+    pub product_name: String,
+    pub quantity: u64,
+    pub issues: u64,
+}
+
+/// How much of a consumable was issued to a user or unit, the SSN and name are empty per unit
+#[derive(Clone, Debug, Serialize)]
+#[pyclass(get_all)]
+pub struct Consumption {
+    pub ssn: Option<String>,
+    pub name: Option<String>,
+    pub unit: Option<String>,
+    pub product_id: String,
+    pub product_name: String,
+    pub quantity: u64,
+    pub issues: u64,
+}
+
+#[pymethods]
+impl Consumption {
+    #[pyo3(name = "__repr__")]
+    pub fn repr(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    #[pyo3(name = "__str__")]
+    pub fn str(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+#[derive(Debug)]
+#[pyclass]
+pub struct Consumptions(Vec<Consumption>);
+
+#[pymethods]
+impl Consumptions {
+    #[getter(consumption)]
+    fn get_consumption(&self) -> Vec<Consumption> {
+        self.0.clone()
+    }
+
+    #[pyo3(name = "__repr__")]
+    pub fn repr(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    #[pyo3(name = "__str__")]
+    pub fn str(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+impl From<PyConsumption> for Consumption {
+    fn from(py_consumption: PyConsumption) -> Self {
+        Self {
+            ssn: py_consumption.ssn,
+            name: py_consumption.name,
+            unit: py_consumption.unit,
+            product_id: py_consumption.product_id,
+            product_name: py_consumption.product_name,
+            quantity: py_consumption.quantity,
+            issues: py_consumption.issues,
+        }
+    }
+}
+
+impl From<Vec<Consumption>> for Consumptions {
+    fn from(consumption: Vec<Consumption>) -> Self {
+        Self(consumption)
+    }
+}
+
+impl Records for Consumptions {
+    type Record = Consumption;
+
+    fn records(&self) -> &[Self::Record] {
+        &self.0
+    }
+}
+
+impl MaskSsns for Consumptions {
+    fn mask_ssns(&mut self) {
+        for consumption in &mut self.0 {
+            consumption.ssn = consumption.ssn.as_deref().map(mask_ssn);
+        }
+    }
+}
+
+impl From<&Consumption> for Row {
+    fn from(value: &Consumption) -> Self {
+        row![
+            value.ssn.clone().unwrap_or_default(),
+            value.name.clone().unwrap_or_default(),
+            value.unit.clone().unwrap_or_default(),
+            value.product_id,
+            value.product_name,
+            value.quantity,
+            value.issues
+        ]
+    }
+}
+
+impl From<Consumptions> for Table {
+    fn from(consumption: Consumptions) -> Self {
+        let mut table = Table::new();
+        table.add_row(row![
+            "SSN",
+            "Name",
+            "Unit",
+            "Product ID",
+            "Product Name",
+            "Quantity",
+            "Issues"
+        ]);
+        for consumption in consumption.0 {
+            table.add_row((&consumption).into());
+        }
+        table
+    }
+}
@@ -1,13 +1,17 @@
 use prettytable::{row, Row, Table};
 use pyo3::{pyclass, pymethods, FromPyObject};
+use serde::Serialize;
 use std::ops::Index;
 
-#[derive(FromPyObject, Debug)]
+use crate::output::Records;
+
+#[derive(FromPyObject, Debug, Serialize)]
 #[pyclass]
 pub struct InStockSize {
     pub product_id: String,
     pub product_name: String,
     pub size: String,
+    #[serde(rename = "in_stock")]
     pub tot_in: i32,
 }
 
@@ -45,6 +49,14 @@ impl InStockSizes {
     }
 }
 
+impl Records for InStockSizes {
+    type Record = InStockSize;
+
+    fn records(&self) -> &[Self::Record] {
+        &self.0
+    }
+}
+
 impl Index<usize> for InStockSizes {
     type Output = InStockSize;
 
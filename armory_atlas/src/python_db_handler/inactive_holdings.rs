@@ -0,0 +1,149 @@
+use chrono::NaiveDate;
+use prettytable::{row, Row, Table};
+use pyo3::{pyclass, pymethods, FromPyObject};
+use serde::Serialize;
+
+use crate::output::Records;
+use crate::users::ssn::{mask_ssn, MaskSsns};
+
+#[derive(FromPyObject)]
+pub struct PyInactiveHolding {
+    pub ssn: String,
+    pub name: String,
+    pub unit: Option<String>,
+    pub end_of_service: Option<NaiveDate>,
+    pub item_id: String,
+    pub product_name: String,
+    pub size: Option<String>,
+    pub borrow_date: NaiveDate,
+}
+
+/// An item still held by a user that is inactive or whose service has ended
+#[derive(Clone, Debug, Serialize)]
+#[pyclass(get_all)]
+pub struct InactiveHolding {
+    pub ssn: String,
+    pub name: String,
+    pub unit: Option<String>,
+    pub end_of_service: Option<NaiveDate>,
+    pub item_id: String,
+    pub product_name: String,
+    pub size: Option<String>,
+    pub borrow_date: NaiveDate,
+}
+
+#[pymethods]
+impl InactiveHolding {
+    #[pyo3(name = "__repr__")]
+    pub fn repr(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    #[pyo3(name = "__str__")]
+    pub fn str(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+#[derive(Debug)]
+#[pyclass]
+pub struct InactiveHoldings(Vec<InactiveHolding>);
+
+#[pymethods]
+impl InactiveHoldings {
+    #[getter(holdings)]
+    fn get_holdings(&self) -> Vec<InactiveHolding> {
+        self.0.clone()
+    }
+
+    #[pyo3(name = "__repr__")]
+    pub fn repr(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    #[pyo3(name = "__str__")]
+    pub fn str(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+impl From<PyInactiveHolding> for InactiveHolding {
+    fn from(py_holding: PyInactiveHolding) -> Self {
+        Self {
+            ssn: py_holding.ssn,
+            name: py_holding.name,
+            unit: py_holding.unit,
+            end_of_service: py_holding.end_of_service,
+            item_id: py_holding.item_id,
+            product_name: py_holding.product_name,
+            size: py_holding.size,
+            borrow_date: py_holding.borrow_date,
+        }
+    }
+}
+
+impl From<Vec<InactiveHolding>> for InactiveHoldings {
+    fn from(holdings: Vec<InactiveHolding>) -> Self {
+        Self(holdings)
+    }
+}
+
+impl From<InactiveHoldings> for Vec<InactiveHolding> {
+    fn from(holdings: InactiveHoldings) -> Self {
+        holdings.0
+    }
+}
+
+impl Records for InactiveHoldings {
+    type Record = InactiveHolding;
+
+    fn records(&self) -> &[Self::Record] {
+        &self.0
+    }
+}
+
+impl MaskSsns for InactiveHoldings {
+    fn mask_ssns(&mut self) {
+        for holding in &mut self.0 {
+            holding.ssn = mask_ssn(&holding.ssn);
+        }
+    }
+}
+
+impl From<&InactiveHolding> for Row {
+    fn from(value: &InactiveHolding) -> Self {
+        row![
+            value.ssn,
+            value.name,
+            value.unit.clone().unwrap_or_default(),
+            value
+                .end_of_service
+                .map(|date| date.to_string())
+                .unwrap_or_default(),
+            value.item_id,
+            value.product_name,
+            value.size.clone().unwrap_or_default(),
+            value.borrow_date
+        ]
+    }
+}
+
+impl From<InactiveHoldings> for Table {
+    fn from(holdings: InactiveHoldings) -> Self {
+        let mut table = Table::new();
+        table.add_row(row![
+            "SSN",
+            "Name",
+            "Unit",
+            "End of Service",
+            "Item ID",
+            "Product Name",
+            "Size",
+            "Borrow Date"
+        ]);
+        for holding in holdings.0 {
+            table.add_row((&holding).into());
+        }
+        table
+    }
+}
@@ -0,0 +1,147 @@
+use prettytable::{row, Row, Table};
+use pyo3::{pyclass, pymethods, FromPyObject};
+use serde::Serialize;
+use std::ops::Index;
+
+use crate::output::Records;
+use crate::users::ssn::{mask_ssn, MaskSsns};
+
+#[derive(FromPyObject)]
+pub struct PyItemHistoryEvent {
+    pub event_date: String,
+    pub event: String,
+    pub ssn: Option<String>,
+    pub name: Option<String>,
+    pub end_date: Option<String>,
+    pub details: Option<String>,
+}
+
+/// One entry in the lifecycle of an item.
+///
+/// Lendings are reported as a single `Lent` event where `end_date` is the date the item came back,
+/// followed by a `Returned` event on that date. Every other event comes from the `ItemEvents` table,
+/// these are `Created`, `Condition`, `Maintenance`, `Transfer` and `Retired`.
+#[derive(Clone, Debug, Serialize)]
+#[pyclass]
+pub struct ItemHistoryEvent {
+    pub event_date: String,
+    pub event: String,
+    pub ssn: Option<String>,
+    pub name: Option<String>,
+    pub end_date: Option<String>,
+    pub details: Option<String>,
+}
+
+#[pymethods]
+impl ItemHistoryEvent {
+    #[pyo3(name = "__repr__")]
+    pub fn repr(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    #[pyo3(name = "__str__")]
+    pub fn str(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+#[derive(Debug)]
+#[pyclass]
+pub struct ItemHistory(Vec<ItemHistoryEvent>);
+
+#[pymethods]
+impl ItemHistory {
+    #[getter(events)]
+    fn get_events(&self) -> Vec<ItemHistoryEvent> {
+        self.0.clone()
+    }
+
+    #[pyo3(name = "__repr__")]
+    pub fn repr(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    #[pyo3(name = "__str__")]
+    pub fn str(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+impl From<PyItemHistoryEvent> for ItemHistoryEvent {
+    fn from(py_event: PyItemHistoryEvent) -> Self {
+        Self {
+            event_date: py_event.event_date,
+            event: py_event.event,
+            ssn: py_event.ssn,
+            name: py_event.name,
+            end_date: py_event.end_date,
+            details: py_event.details,
+        }
+    }
+}
+
+impl From<Vec<ItemHistoryEvent>> for ItemHistory {
+    fn from(events: Vec<ItemHistoryEvent>) -> Self {
+        Self(events)
+    }
+}
+
+impl From<ItemHistory> for Vec<ItemHistoryEvent> {
+    fn from(history: ItemHistory) -> Self {
+        history.0
+    }
+}
+
+impl Records for ItemHistory {
+    type Record = ItemHistoryEvent;
+
+    fn records(&self) -> &[Self::Record] {
+        &self.0
+    }
+}
+
+impl MaskSsns for ItemHistory {
+    fn mask_ssns(&mut self) {
+        for event in &mut self.0 {
+            event.ssn = event.ssn.as_deref().map(mask_ssn);
+        }
+    }
+}
+
+impl Index<usize> for ItemHistory {
+    type Output = ItemHistoryEvent;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.0[index]
+    }
+}
+
+impl From<&ItemHistoryEvent> for Row {
+    fn from(value: &ItemHistoryEvent) -> Self {
+        let end_date = match (value.event.as_str(), &value.end_date) {
+            (_, Some(end_date)) => end_date.clone(),
+            ("Lent", None) => "Not Returned yet".to_string(),
+            _ => String::new(),
+        };
+
+        row![
+            value.event_date,
+            value.event,
+            value.ssn.clone().unwrap_or_default(),
+            value.name.clone().unwrap_or_default(),
+            end_date,
+            value.details.clone().unwrap_or_default()
+        ]
+    }
+}
+
+impl From<ItemHistory> for Table {
+    fn from(history: ItemHistory) -> Self {
+        let mut table = Table::new();
+        table.add_row(row!["Date", "Event", "SSN", "Name", "Until", "Details"]);
+        for event in history.0 {
+            table.add_row((&event).into());
+        }
+        table
+    }
+}
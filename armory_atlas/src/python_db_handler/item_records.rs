@@ -0,0 +1,149 @@
+use crate::output::Records;
+use prettytable::{row, Row, Table};
+use pyo3::{pyclass, pymethods, FromPyObject};
+use serde::Serialize;
+use std::ops::Index;
+
+#[derive(FromPyObject)]
+pub struct PyItemRecord {
+    pub item_id: String,
+    pub product_id: String,
+    pub product_name: String,
+    pub product_type: String,
+    pub size: Option<String>,
+    pub quality: f32,
+    pub available: bool,
+    pub serial_number: Option<String>,
+    pub item_code: Option<String>,
+}
+
+/// A single item together with its product and whether it can be borrowed right now
+#[derive(Clone, Debug, Serialize)]
+#[pyclass(get_all)]
+pub struct ItemRecord {
+    pub item_id: String,
+    pub product_id: String,
+    pub product_name: String,
+    pub product_type: String,
+    pub size: Option<String>,
+    pub quality: f32,
+    pub available: bool,
+    pub serial_number: Option<String>,
+    pub item_code: Option<String>,
+}
+
+#[pymethods]
+impl ItemRecord {
+    #[pyo3(name = "__repr__")]
+    pub fn repr(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    #[pyo3(name = "__str__")]
+    pub fn str(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+#[derive(Debug)]
+#[pyclass]
+pub struct ItemRecords(Vec<ItemRecord>);
+
+#[pymethods]
+impl ItemRecords {
+    #[getter(items)]
+    fn get_items(&self) -> Vec<ItemRecord> {
+        self.0.clone()
+    }
+
+    #[pyo3(name = "__repr__")]
+    pub fn repr(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    #[pyo3(name = "__str__")]
+    pub fn str(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+impl From<PyItemRecord> for ItemRecord {
+    fn from(py_item: PyItemRecord) -> Self {
+        Self {
+            item_id: py_item.item_id,
+            product_id: py_item.product_id,
+            product_name: py_item.product_name,
+            product_type: py_item.product_type,
+            size: py_item.size,
+            quality: py_item.quality,
+            available: py_item.available,
+            serial_number: py_item.serial_number,
+            item_code: py_item.item_code,
+        }
+    }
+}
+
+impl From<Vec<ItemRecord>> for ItemRecords {
+    fn from(items: Vec<ItemRecord>) -> Self {
+        Self(items)
+    }
+}
+
+impl From<ItemRecords> for Vec<ItemRecord> {
+    fn from(items: ItemRecords) -> Self {
+        items.0
+    }
+}
+
+impl Records for ItemRecords {
+    type Record = ItemRecord;
+
+    fn records(&self) -> &[Self::Record] {
+        &self.0
+    }
+}
+
+impl Index<usize> for ItemRecords {
+    type Output = ItemRecord;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.0[index]
+    }
+}
+
+impl From<&ItemRecord> for Row {
+    fn from(value: &ItemRecord) -> Self {
+        row![
+            value.item_id,
+            value.item_code.clone().unwrap_or_default(),
+            value.product_id,
+            value.product_name,
+            value.product_type,
+            value.size.clone().unwrap_or_default(),
+            format!("{:.2}", value.quality),
+            if value.available { "Yes" } else { "No" },
+            value.serial_number.clone().unwrap_or_default()
+        ]
+    }
+}
+
+impl From<ItemRecords> for Table {
+    fn from(items: ItemRecords) -> Self {
+        let mut table = Table::new();
+        table.add_row(row![
+            "Item ID",
+            "Item Code",
+            "Product ID",
+            "Product Name",
+            "Product Type",
+            "Size",
+            "Quality",
+            "Available",
+            "Serial"
+        ]);
+        for item in items.0 {
+            table.add_row((&item).into());
+        }
+        table
+    }
+}
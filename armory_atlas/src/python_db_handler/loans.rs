@@ -1,7 +1,11 @@
 use prettytable::{row, Row, Table};
 use pyo3::{pyclass, pymethods, FromPyObject};
+use serde::Serialize;
 use std::ops::Index;
 
+use crate::output::Records;
+use crate::users::ssn::{mask_ssn, MaskSsns};
+
 #[derive(FromPyObject)]
 pub struct PyDetailedLoan {
     pub lending_id: String,
@@ -12,9 +16,10 @@ pub struct PyDetailedLoan {
     pub size: String,
     pub borrow_date: String,
     pub return_date: Option<String>,
+    pub item_code: Option<String>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 #[pyclass]
 pub struct DetailedLoan {
     pub lending_id: String,
@@ -25,6 +30,7 @@ pub struct DetailedLoan {
     pub size: String,
     pub borrow_date: String,
     pub return_date: Option<String>,
+    pub item_code: Option<String>,
 }
 
 #[pymethods]
@@ -73,6 +79,7 @@ impl From<DetailedLoan> for PyDetailedLoan {
             size: detailed_loan.size,
             borrow_date: detailed_loan.borrow_date,
             return_date: detailed_loan.return_date,
+            item_code: detailed_loan.item_code,
         }
     }
 }
@@ -88,6 +95,7 @@ impl From<PyDetailedLoan> for DetailedLoan {
             size: py_detailed_loan.size,
             borrow_date: py_detailed_loan.borrow_date,
             return_date: py_detailed_loan.return_date,
+            item_code: py_detailed_loan.item_code,
         }
     }
 }
@@ -110,6 +118,22 @@ impl From<DetailedLoan> for DetailedLoans {
     }
 }
 
+impl Records for DetailedLoans {
+    type Record = DetailedLoan;
+
+    fn records(&self) -> &[Self::Record] {
+        &self.0
+    }
+}
+
+impl MaskSsns for DetailedLoans {
+    fn mask_ssns(&mut self) {
+        for loan in &mut self.0 {
+            loan.ssn = mask_ssn(&loan.ssn);
+        }
+    }
+}
+
 impl Index<usize> for DetailedLoans {
     type Output = DetailedLoan;
 
@@ -125,6 +149,7 @@ impl From<DetailedLoan> for Row {
             value.ssn,
             value.name,
             value.item_id,
+            value.item_code.clone().unwrap_or_default(),
             value.product_name,
             value.size,
             value.borrow_date,
@@ -140,6 +165,7 @@ impl From<&DetailedLoan> for Row {
             value.ssn,
             value.name,
             value.item_id,
+            value.item_code.clone().unwrap_or_default(),
             value.product_name,
             value.size,
             value.borrow_date,
@@ -159,6 +185,7 @@ impl From<DetailedLoans> for Table {
             "SSN",
             "Name",
             "Item ID",
+            "Item Code",
             "Product Name",
             "Size",
             "Borrowing Date",
@@ -1,22 +1,66 @@
+use std::collections::{HashMap, HashSet};
 use std::ops::Index;
 
+use chrono::{Local, NaiveDate};
 use prettytable::{row, Row, Table};
 use pyo3::prelude::*;
 use rayon::prelude::*;
+use serde::Serialize;
 
+use crate::archive::ArchiveData;
+use crate::config::get_config;
+use crate::consumables::ConsumptionGroup;
+use crate::import::ImportRecords;
+use crate::items::code::ItemCode;
 use crate::items::Item;
+use crate::leandings::rules::{get_checkout_rules, CheckoutFacts, RuleViolation};
 use crate::leandings::Loans;
+use crate::operators::{Permission, Role, Session, SignOff};
+use crate::output::Records;
+use crate::products::reorder::StockLevel;
 use crate::products::Product;
+use crate::python_db_handler::accountability::{
+    AccountabilityEntry, AccountabilityReport, PyAccountabilityEntry,
+};
+use crate::python_db_handler::checkout::PyCheckoutFacts;
+use crate::python_db_handler::consumables::{
+    ConsumableStock, Consumption, Consumptions, PyConsumableStock, PyConsumption,
+};
 use crate::python_db_handler::in_stock_size::{InStockSize, InStockSizes};
+use crate::python_db_handler::inactive_holdings::{
+    InactiveHolding, InactiveHoldings, PyInactiveHolding,
+};
+use crate::python_db_handler::item_history::{ItemHistory, ItemHistoryEvent, PyItemHistoryEvent};
+use crate::python_db_handler::item_records::{ItemRecord, PyItemRecord};
 use crate::python_db_handler::loans::{DetailedLoan, PyDetailedLoan};
 use crate::python_db_handler::num_borrows::{NumberBorrow, PyNumberBorrow};
+use crate::python_db_handler::open_loans::{OpenLoan, OpenLoans, PyOpenLoan};
+use crate::python_db_handler::operators::{Operator, Operators, PyOperator};
+use crate::python_db_handler::products::PyProduct;
+use crate::python_db_handler::report_data::{PyReportItem, PyReportLoan};
+use crate::python_db_handler::stock_levels::PyStockLevel;
 use crate::python_db_handler::users::PyUser;
+use crate::query::QueryOptions;
+use crate::reports::{ReportData, ReportItem, ReportLoan};
+use crate::search::SearchQuery;
 use crate::users::User;
 use crate::{ItemProduct, PYTHON_DATABASE_HANDLER};
+use uuid::Uuid;
 
+pub mod accountability;
+pub mod checkout;
+pub mod consumables;
 pub mod in_stock_size;
+pub mod inactive_holdings;
+pub mod item_history;
+pub mod item_records;
 pub mod loans;
 pub mod num_borrows;
+pub mod open_loans;
+pub mod operators;
+pub mod products;
+pub mod report_data;
+pub mod stock_levels;
 pub mod users;
 
 /// The main struct for the database handler
@@ -36,9 +80,10 @@ pub mod users;
 #[pyclass]
 pub struct DBHandlerPy {
     pool: PyObject,
+    session: Session,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 #[pyclass]
 pub struct DetailedItem {
     pub product_id: String,
@@ -142,6 +187,14 @@ impl DetailedItems {
     }
 }
 
+impl Records for DetailedItems {
+    type Record = DetailedItem;
+
+    fn records(&self) -> &[Self::Record] {
+        &self.0
+    }
+}
+
 impl Index<usize> for DetailedItems {
     type Output = DetailedItem;
 
@@ -185,12 +238,14 @@ impl DBHandlerPy {
     pub fn new() -> anyhow::Result<Self> {
         pyo3::prepare_freethreaded_python();
         let pool = DBHandlerPy::get_db_handler_obj()?;
-        Ok(Self { pool })
+        let session = DBHandlerPy::identify_operator(&pool)?;
+        Ok(Self { pool, session })
     }
 
-    pub fn get_items(&self) -> anyhow::Result<Vec<DetailedItem>> {
+    pub fn get_items(&self, options: QueryOptions) -> anyhow::Result<Vec<DetailedItem>> {
+        self.session.require(Permission::Read)?;
         Python::with_gil(|py| {
-            let items = self.pool.call_method0(py, "get_items")?;
+            let items = self.pool.call_method1(py, "get_items", (options,))?;
             let items: Vec<ItemProduct> = items.extract(py)?;
             let items: Vec<DetailedItem> = items.into_par_iter().map(DetailedItem::from).collect();
             Ok(items)
@@ -202,6 +257,7 @@ impl DBHandlerPy {
         product_id: String,
         size: String,
     ) -> anyhow::Result<InStockSizes> {
+        self.session.require(Permission::Read)?;
         Python::with_gil(|py| {
             let items = self
                 .pool
@@ -212,31 +268,18 @@ impl DBHandlerPy {
         })
     }
 
-    pub fn get_loans(&self) -> anyhow::Result<Vec<DetailedLoan>> {
+    pub fn get_loans(&self, options: QueryOptions) -> anyhow::Result<Vec<DetailedLoan>> {
+        self.session.require(Permission::Read)?;
         Python::with_gil(|py| {
-            let loans = self.pool.call_method0(py, "get_loans")?;
+            let loans = self.pool.call_method1(py, "get_loans", (options,))?;
             let loans: Vec<PyDetailedLoan> = loans.extract(py)?;
             let loans: Vec<DetailedLoan> = loans.into_par_iter().map(DetailedLoan::from).collect();
             Ok(loans)
         })
     }
 
-    #[staticmethod]
-    pub fn get_db_handler_obj() -> anyhow::Result<PyObject> {
-        Python::with_gil(|py| {
-            let module = PyModule::from_code_bound(
-                py,
-                PYTHON_DATABASE_HANDLER,
-                "ArmoryAtlasDBHandler.py",
-                "ArmoryAtlasDBHandler",
-            )?;
-            let db_handler = module.getattr("DBHandler")?;
-            let db = db_handler.call0()?.to_object(py);
-            Ok(db)
-        })
-    }
-
     pub fn get_rand_item(&self) -> anyhow::Result<Item> {
+        self.session.require(Permission::Read)?;
         Python::with_gil(|py| {
             let items = self.pool.call_method0(py, "get_rand_item")?;
             let item: Item = items.extract(py)?;
@@ -245,6 +288,7 @@ impl DBHandlerPy {
     }
 
     pub fn get_rand_user(&self) -> anyhow::Result<User> {
+        self.session.require(Permission::Read)?;
         Python::with_gil(|py| {
             let users = self.pool.call_method0(py, "get_rand_user")?;
             let user: PyUser = users.extract(py)?;
@@ -253,6 +297,7 @@ impl DBHandlerPy {
     }
 
     pub fn insert_product(&self, product: Product) -> anyhow::Result<()> {
+        self.session.require(Permission::Catalog)?;
         Python::with_gil(|py| {
             self.pool.call_method1(py, "insert_product", (product,))?;
             Ok(())
@@ -260,6 +305,7 @@ impl DBHandlerPy {
     }
 
     pub fn insert_item(&self, item: Item) -> anyhow::Result<()> {
+        self.session.require(Permission::Catalog)?;
         Python::with_gil(|py| {
             self.pool.call_method1(py, "insert_item", (item,))?;
             Ok(())
@@ -267,20 +313,181 @@ impl DBHandlerPy {
     }
 
     pub fn insert_user(&self, user: User) -> anyhow::Result<()> {
+        self.session.require(Permission::Checkout)?;
         Python::with_gil(|py| {
             self.pool.call_method1(py, "insert_user", (user,))?;
             Ok(())
         })
     }
 
-    pub fn insert_loan(&self, loan: Loans) -> anyhow::Result<()> {
+    /// Inserts a loan, loans of regulated items need [`DBHandlerPy::insert_signed_loan`]
+    pub fn insert_loan(&self, mut loan: Loans) -> anyhow::Result<()> {
+        self.session.require(Permission::Checkout)?;
+        loan.item_id = self.resolve_item_id(&loan.item_id)?;
+        self.refuse_unsigned([loan.item_id.as_str()])?;
+        self.refuse_rule_violations([&loan])?;
         Python::with_gil(|py| {
             self.pool.call_method1(py, "insert_loan", (loan,))?;
             Ok(())
         })
     }
 
+    pub fn update_item(
+        &self,
+        item_id: String,
+        product_id: Option<String>,
+        size: Option<String>,
+        quality: Option<f32>,
+        serial_number: Option<String>,
+    ) -> anyhow::Result<()> {
+        self.session.require(Permission::Catalog)?;
+        let item_id = self.resolve_item_id(&item_id)?;
+        Python::with_gil(|py| {
+            self.pool.call_method1(
+                py,
+                "update_item",
+                (item_id, product_id, size, quality, serial_number),
+            )?;
+            Ok(())
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_user(
+        &self,
+        ssn: String,
+        name: Option<String>,
+        unit: Option<String>,
+        rank: Option<String>,
+        phone: Option<String>,
+        email: Option<String>,
+        active: Option<bool>,
+        end_of_service: Option<NaiveDate>,
+    ) -> anyhow::Result<()> {
+        self.session.require(Permission::Checkout)?;
+        Python::with_gil(|py| {
+            self.pool.call_method1(
+                py,
+                "update_user",
+                (ssn, name, unit, rank, phone, email, active, end_of_service),
+            )?;
+            Ok(())
+        })
+    }
+
+    pub fn update_product(
+        &self,
+        product_id: String,
+        product_name: Option<String>,
+        product_type: Option<String>,
+    ) -> anyhow::Result<()> {
+        self.session.require(Permission::Catalog)?;
+        Python::with_gil(|py| {
+            self.pool.call_method1(
+                py,
+                "update_product",
+                (product_id, product_name, product_type),
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Changes the dates of a loan
+    ///
+    /// Closing an open loan of a regulated item returns it, which needs
+    /// [`DBHandlerPy::update_signed_loan`].
+    pub fn update_loan(
+        &self,
+        lending_id: String,
+        borrowing_date: Option<NaiveDate>,
+        return_date: Option<NaiveDate>,
+    ) -> anyhow::Result<()> {
+        self.session.require(Permission::Checkout)?;
+        if return_date.is_some() {
+            let open_loan = self
+                .find_loans(SearchQuery::by("id", &lending_id))?
+                .into_iter()
+                .find(|loan| loan.return_date.is_none());
+            if let Some(loan) = open_loan {
+                self.refuse_unsigned([loan.item_id.as_str()])?;
+            }
+        }
+        Python::with_gil(|py| {
+            self.pool
+                .call_method1(py, "update_loan", (lending_id, borrowing_date, return_date))?;
+            Ok(())
+        })
+    }
+
+    /// Records maintenance or a transfer of an item in its history
+    pub fn record_item_event(
+        &self,
+        item_id: String,
+        event_type: &str,
+        details: String,
+        day: Option<NaiveDate>,
+    ) -> anyhow::Result<()> {
+        self.session.require(Permission::Catalog)?;
+        let item_id = self.resolve_item_id(&item_id)?;
+        Python::with_gil(|py| {
+            self.pool
+                .call_method1(py, "record_item_event", (item_id, event_type, details, day))?;
+            Ok(())
+        })
+    }
+
+    /// Retires an item, it is kept with its history but can no longer be lent out
+    pub fn retire_item(
+        &self,
+        item_id: String,
+        reason: String,
+        day: Option<NaiveDate>,
+    ) -> anyhow::Result<()> {
+        self.session.require(Permission::Catalog)?;
+        let item_id = self.resolve_item_id(&item_id)?;
+        Python::with_gil(|py| {
+            self.pool
+                .call_method1(py, "retire_item", (item_id, reason, day))?;
+            Ok(())
+        })
+    }
+
+    pub fn delete_user(&self, ssn: String, force: bool) -> anyhow::Result<()> {
+        self.session.require(Permission::Catalog)?;
+        Python::with_gil(|py| {
+            self.pool.call_method1(py, "delete_user", (ssn, force))?;
+            Ok(())
+        })
+    }
+
+    /// Replaces the SSN and name of a user with a pseudonym, returns the pseudonym
+    pub fn erase_user(&self, ssn: String) -> anyhow::Result<String> {
+        self.session.require(Permission::Manage)?;
+        Python::with_gil(|py| {
+            let pseudonym = self.pool.call_method1(py, "erase_user", (ssn,))?;
+            Ok(pseudonym.extract(py)?)
+        })
+    }
+
+    pub fn delete_product(&self, product_id: String, force: bool) -> anyhow::Result<()> {
+        self.session.require(Permission::Catalog)?;
+        Python::with_gil(|py| {
+            self.pool
+                .call_method1(py, "delete_product", (product_id, force))?;
+            Ok(())
+        })
+    }
+
+    pub fn delete_loan(&self, lending_id: String) -> anyhow::Result<()> {
+        self.session.require(Permission::Checkout)?;
+        Python::with_gil(|py| {
+            self.pool.call_method1(py, "delete_loan", (lending_id,))?;
+            Ok(())
+        })
+    }
+
     pub fn search_items(&self, query: &str) -> anyhow::Result<Vec<DetailedItem>> {
+        self.session.require(Permission::Read)?;
         Python::with_gil(|py| {
             let items = self.pool.call_method1(py, "search_items", (query,))?;
             let items: Vec<ItemProduct> = items.extract(py)?;
@@ -289,48 +496,216 @@ impl DBHandlerPy {
         })
     }
 
+    pub fn find_products(&self, query: SearchQuery) -> anyhow::Result<Vec<Product>> {
+        self.session.require(Permission::Read)?;
+        Python::with_gil(|py| {
+            let products = self.pool.call_method1(py, "find_products", (query,))?;
+            let products: Vec<PyProduct> = products.extract(py)?;
+            let products = products.into_par_iter().map(Product::from).collect();
+            Ok(products)
+        })
+    }
+
+    pub fn find_items(&self, query: SearchQuery) -> anyhow::Result<Vec<ItemRecord>> {
+        self.session.require(Permission::Read)?;
+        Python::with_gil(|py| {
+            let items = self.pool.call_method1(py, "find_items", (query,))?;
+            let items: Vec<PyItemRecord> = items.extract(py)?;
+            let items = items.into_par_iter().map(ItemRecord::from).collect();
+            Ok(items)
+        })
+    }
+
+    pub fn find_users(&self, query: SearchQuery) -> anyhow::Result<Vec<User>> {
+        self.session.require(Permission::Read)?;
+        Python::with_gil(|py| {
+            let users = self.pool.call_method1(py, "find_users", (query,))?;
+            let users: Vec<PyUser> = users.extract(py)?;
+            let users = users.into_par_iter().map(User::from).collect();
+            Ok(users)
+        })
+    }
+
+    pub fn find_loans(&self, query: SearchQuery) -> anyhow::Result<Vec<DetailedLoan>> {
+        self.session.require(Permission::Read)?;
+        Python::with_gil(|py| {
+            let loans = self.pool.call_method1(py, "find_loans", (query,))?;
+            let loans: Vec<PyDetailedLoan> = loans.extract(py)?;
+            let loans = loans.into_par_iter().map(DetailedLoan::from).collect();
+            Ok(loans)
+        })
+    }
+
     pub fn drop_all(&self) -> anyhow::Result<()> {
+        self.session.require(Permission::Manage)?;
         Python::with_gil(|py| {
             self.pool.call_method0(py, "drop_all")?;
             Ok(())
         })
     }
 
+    /// Creates the schema, tables made by an earlier version are migrated to the latest one
     pub fn create_all(&self) -> anyhow::Result<()> {
+        self.session.require(Permission::Manage)?;
         Python::with_gil(|py| {
             self.pool.call_method0(py, "create_all")?;
             Ok(())
         })
     }
 
-    pub fn get_users(&self) -> anyhow::Result<Vec<User>> {
+    pub fn get_users(&self, options: QueryOptions) -> anyhow::Result<Vec<User>> {
+        self.session.require(Permission::Read)?;
         Python::with_gil(|py| {
-            let users = self.pool.call_method0(py, "get_users")?;
+            let users = self.pool.call_method1(py, "get_users", (options,))?;
             let users: Vec<PyUser> = users.extract(py)?;
             let users = users.into_par_iter().map(User::from).collect();
             Ok(users)
         })
     }
 
+    /// Returns an item, regulated items need [`DBHandlerPy::return_signed_item`]
     pub fn return_item(&self, item_id: String) -> anyhow::Result<()> {
+        self.session.require(Permission::Checkout)?;
+        let item_id = self.resolve_item_id(&item_id)?;
+        self.refuse_unsigned([item_id.as_str()])?;
         Python::with_gil(|py| {
             self.pool.call_method1(py, "return_item", (item_id,))?;
             Ok(())
         })
     }
 
-    pub fn user_all_borrowed(&self, ssn: String) -> anyhow::Result<Vec<DetailedLoan>> {
+    pub fn user_all_borrowed(
+        &self,
+        ssn: String,
+        options: QueryOptions,
+    ) -> anyhow::Result<Vec<DetailedLoan>> {
+        self.session.require(Permission::Read)?;
         Python::with_gil(|py| {
-            let loans = self.pool.call_method1(py, "user_all_borrowed", (ssn,))?;
+            let loans = self
+                .pool
+                .call_method1(py, "user_all_borrowed", (ssn, options))?;
             let loans: Vec<PyDetailedLoan> = loans.extract(py)?;
             let loans: Vec<DetailedLoan> = loans.into_par_iter().map(DetailedLoan::from).collect();
             Ok(loans)
         })
     }
 
-    pub fn number_of_borrowes(&self) -> anyhow::Result<Vec<NumberBorrow>> {
+    pub fn get_item_history(&self, item_id: String) -> anyhow::Result<ItemHistory> {
+        self.session.require(Permission::Read)?;
+        let item_id = self.resolve_item_id(&item_id)?;
+        Python::with_gil(|py| {
+            let events = self.pool.call_method1(py, "get_item_history", (item_id,))?;
+            let events: Vec<PyItemHistoryEvent> = events.extract(py)?;
+            let events: Vec<ItemHistoryEvent> =
+                events.into_par_iter().map(ItemHistoryEvent::from).collect();
+            Ok(events.into())
+        })
+    }
+
+    pub fn get_inactive_holdings(&self) -> anyhow::Result<InactiveHoldings> {
+        self.session.require(Permission::Read)?;
         Python::with_gil(|py| {
-            let loans = self.pool.call_method0(py, "number_of_borrowes")?;
+            let holdings = self.pool.call_method0(py, "get_inactive_holdings")?;
+            let holdings: Vec<PyInactiveHolding> = holdings.extract(py)?;
+            let holdings: Vec<InactiveHolding> = holdings
+                .into_par_iter()
+                .map(InactiveHolding::from)
+                .collect();
+            Ok(holdings.into())
+        })
+    }
+
+    /// Every item that is lent out and not yet returned, without due dates
+    pub fn get_open_loans(&self) -> anyhow::Result<OpenLoans> {
+        self.session.require(Permission::Read)?;
+        Python::with_gil(|py| {
+            let loans = self.pool.call_method0(py, "get_open_loans")?;
+            let loans: Vec<PyOpenLoan> = loans.extract(py)?;
+            let loans: Vec<OpenLoan> = loans.into_par_iter().map(OpenLoan::from).collect();
+            Ok(loans.into())
+        })
+    }
+
+    /// Where every item of the regulated product types was at the end of `date`
+    pub fn get_accountability(
+        &self,
+        regulated_types: Vec<String>,
+        date: NaiveDate,
+    ) -> anyhow::Result<AccountabilityReport> {
+        self.session.require(Permission::Read)?;
+        Python::with_gil(|py| {
+            let entries =
+                self.pool
+                    .call_method1(py, "get_accountability", (regulated_types, date))?;
+            let entries: Vec<PyAccountabilityEntry> = entries.extract(py)?;
+            let entries: Vec<AccountabilityEntry> = entries
+                .into_par_iter()
+                .map(AccountabilityEntry::from)
+                .collect();
+            Ok(entries.into())
+        })
+    }
+
+    /// The quantity on hand of every consumable that has been received
+    pub fn get_consumable_stock(&self) -> anyhow::Result<Vec<ConsumableStock>> {
+        self.session.require(Permission::Read)?;
+        Python::with_gil(|py| {
+            let stock = self.pool.call_method0(py, "get_consumable_stock")?;
+            let stock: Vec<PyConsumableStock> = stock.extract(py)?;
+            Ok(stock.into_iter().map(ConsumableStock::from).collect())
+        })
+    }
+
+    pub fn receive_stock(
+        &self,
+        product_id: String,
+        quantity: u32,
+        date: NaiveDate,
+        note: Option<String>,
+    ) -> anyhow::Result<()> {
+        self.session.require(Permission::Catalog)?;
+        Python::with_gil(|py| {
+            self.pool.call_method1(
+                py,
+                "receive_stock",
+                (product_id, quantity, date, self.session.username(), note),
+            )?;
+            Ok(())
+        })
+    }
+
+    pub fn issue_stock(
+        &self,
+        product_id: String,
+        quantity: u32,
+        ssn: String,
+        date: NaiveDate,
+        note: Option<String>,
+    ) -> anyhow::Result<()> {
+        self.session.require(Permission::Checkout)?;
+        Python::with_gil(|py| {
+            self.pool.call_method1(
+                py,
+                "issue_stock",
+                (
+                    product_id,
+                    quantity,
+                    ssn,
+                    date,
+                    self.session.username(),
+                    note,
+                ),
+            )?;
+            Ok(())
+        })
+    }
+
+    pub fn number_of_borrowes(&self, options: QueryOptions) -> anyhow::Result<Vec<NumberBorrow>> {
+        self.session.require(Permission::Read)?;
+        Python::with_gil(|py| {
+            let loans = self
+                .pool
+                .call_method1(py, "number_of_borrowes", (options,))?;
             let loans: Vec<PyNumberBorrow> = loans.extract(py)?;
             let loans: Vec<NumberBorrow> = loans.into_par_iter().map(NumberBorrow::from).collect();
             Ok(loans)
@@ -338,11 +713,447 @@ impl DBHandlerPy {
     }
 }
 
+impl DBHandlerPy {
+    /// Creates the Python database handler that every method goes through
+    ///
+    /// It is kept private, the Python handler checks no permissions of its own.
+    fn get_db_handler_obj() -> anyhow::Result<PyObject> {
+        Python::with_gil(|py| {
+            let module = PyModule::from_code_bound(
+                py,
+                PYTHON_DATABASE_HANDLER,
+                "ArmoryAtlasDBHandler.py",
+                "ArmoryAtlasDBHandler",
+            )?;
+            let db_handler = module.getattr("DBHandler")?;
+            let db = db_handler.call0()?.to_object(py);
+            Ok(db)
+        })
+    }
+
+    /// Finds out who is running the handler from the login saved in the config file
+    ///
+    /// The operator counts only together with the token of its last login, a username alone in
+    /// the config file gives an anonymous session.
+    fn identify_operator(pool: &PyObject) -> anyhow::Result<Session> {
+        Python::with_gil(|py| {
+            let operators: usize = pool.call_method0(py, "count_operators")?.extract(py)?;
+            if operators == 0 {
+                return Ok(Session::Setup);
+            }
+
+            let config = get_config()?;
+            let (Ok(username), Ok(token)) = (
+                config.get_string("operator"),
+                config.get_string("login_token"),
+            ) else {
+                return Ok(Session::Anonymous);
+            };
+            let role: Option<String> = pool
+                .call_method1(py, "get_logged_in_role", (username.as_str(), token))?
+                .extract(py)?;
+
+            Ok(match role {
+                Some(role) => Session::Operator {
+                    username,
+                    role: role.parse()?,
+                },
+                None => Session::Anonymous,
+            })
+        })
+    }
+
+    /// The ID of the item given by its ID or by its short item code, see [`ItemCode`]
+    ///
+    /// IDs are passed on as they are. A code with a wrong check digit is refused before the
+    /// database is asked, so a typo never picks out another item.
+    pub fn resolve_item_id(&self, item: &str) -> anyhow::Result<String> {
+        match self.find_item_id(item)? {
+            Some(item_id) => Ok(item_id),
+            None => anyhow::bail!("No item has the code {}", item.trim().to_uppercase()),
+        }
+    }
+
+    /// Like [`DBHandlerPy::resolve_item_id`], but `None` if no item has the code
+    pub fn find_item_id(&self, item: &str) -> anyhow::Result<Option<String>> {
+        if Uuid::parse_str(item).is_ok() {
+            return Ok(Some(item.to_string()));
+        }
+        let code: ItemCode = item.parse()?;
+        self.session.require(Permission::Read)?;
+        Python::with_gil(|py| {
+            let item_id = self
+                .pool
+                .call_method1(py, "get_item_id_by_code", (code.to_string(),))?;
+            Ok(item_id.extract(py)?)
+        })
+    }
+
+    /// Refuses to write loans of the items without a sign-off if one of them is regulated
+    ///
+    /// Only the signed methods, like [`DBHandlerPy::insert_signed_loan`], check out and return
+    /// regulated items, so no caller of the handler gets around the second operator.
+    fn refuse_unsigned<'a>(
+        &self,
+        item_ids: impl IntoIterator<Item = &'a str>,
+    ) -> anyhow::Result<()> {
+        let rules = get_checkout_rules()?;
+        let mut regulated = HashSet::new();
+        for product_type in &rules.regulated_types {
+            let items = self.find_items(SearchQuery::by("type", product_type))?;
+            regulated.extend(items.into_iter().map(|item| item.item_id));
+        }
+
+        if let Some(item_id) = item_ids
+            .into_iter()
+            .find(|item_id| regulated.contains(*item_id))
+        {
+            anyhow::bail!(
+                "Item {item_id} is regulated, its loans have to be signed off by a second operator"
+            );
+        }
+        Ok(())
+    }
+
+    /// Checks the open loans against the checkout rules as of today, see [`CheckoutRules::check`]
+    ///
+    /// Returns the rules each loan breaks. Loans earlier in `loans` count as held by their borrower
+    /// for the loans after them, loans returned by today are records of the past and break none.
+    ///
+    /// [`CheckoutRules::check`]: crate::leandings::rules::CheckoutRules::check
+    pub fn check_checkout_rules<'a>(
+        &self,
+        loans: impl IntoIterator<Item = &'a Loans>,
+    ) -> anyhow::Result<Vec<Vec<RuleViolation>>> {
+        let rules = get_checkout_rules()?;
+        let today = Local::now().date_naive();
+        let mut earlier: HashMap<&str, Vec<(String, NaiveDate)>> = HashMap::new();
+
+        let mut violations = Vec::new();
+        for loan in loans {
+            if loan
+                .return_date
+                .is_some_and(|return_date| return_date <= today)
+            {
+                violations.push(Vec::new());
+                continue;
+            }
+            let mut facts = self.get_checkout_facts(&loan.ssn, &loan.item_id)?;
+            let held = earlier.entry(loan.ssn.as_str()).or_default();
+            facts.held.extend(held.iter().cloned());
+
+            violations.push(rules.check(&facts, today, &self.session));
+            held.push((facts.product_type, loan.borrowing_date));
+        }
+        Ok(violations)
+    }
+
+    /// Refuses to insert the loans if one of them breaks the checkout rules
+    fn refuse_rule_violations<'a>(
+        &self,
+        loans: impl IntoIterator<Item = &'a Loans>,
+    ) -> anyhow::Result<()> {
+        let loans: Vec<&Loans> = loans.into_iter().collect();
+        let violations = self.check_checkout_rules(loans.iter().copied())?;
+        if let Some((loan, violations)) = loans
+            .iter()
+            .zip(violations)
+            .find(|(_, violations)| !violations.is_empty())
+        {
+            anyhow::bail!(
+                "The loan of item {} to {} breaks the checkout rules: {}",
+                loan.item_id,
+                loan.ssn,
+                violations
+                    .iter()
+                    .map(RuleViolation::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+        Ok(())
+    }
+
+    /// Who the handler is working for
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
+    /// Reads what the checkout rules are checked against when the item is lent to the user
+    pub fn get_checkout_facts(&self, ssn: &str, item_id: &str) -> anyhow::Result<CheckoutFacts> {
+        self.session.require(Permission::Read)?;
+        let item_id = self.resolve_item_id(item_id)?;
+        Python::with_gil(|py| {
+            let facts = self
+                .pool
+                .call_method1(py, "get_checkout_facts", (ssn, item_id))?;
+            let facts: PyCheckoutFacts = facts.extract(py)?;
+            Ok(facts.into())
+        })
+    }
+
+    /// Has a second operator prove who they are to sign off a regulated checkout or return
+    pub fn witness(&self, witness: &str, password: &str) -> anyhow::Result<SignOff> {
+        if !self.verify_operator(witness, password)? {
+            anyhow::bail!("Wrong username or password for the witness {witness}");
+        }
+        Python::with_gil(|py| {
+            let role: Option<String> = self
+                .pool
+                .call_method1(py, "get_operator_role", (witness,))?
+                .extract(py)?;
+            let Some(role) = role else {
+                anyhow::bail!("{witness} is not an operator");
+            };
+            SignOff::new(&self.session, witness, role.parse()?)
+        })
+    }
+
+    /// Inserts a loan of a regulated item together with its sign-off
+    pub fn insert_signed_loan(&self, mut loan: Loans, sign_off: &SignOff) -> anyhow::Result<()> {
+        self.session.require(Permission::Checkout)?;
+        loan.item_id = self.resolve_item_id(&loan.item_id)?;
+        self.refuse_rule_violations([&loan])?;
+        Python::with_gil(|py| {
+            self.pool.call_method1(
+                py,
+                "insert_loan",
+                (
+                    loan,
+                    (sign_off.operator.as_str(), sign_off.witness.as_str()),
+                ),
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Inserts the loans of a checkout in one transaction, none of them if one can't be made
+    ///
+    /// Loans of regulated items come with their sign-off.
+    pub fn insert_checkout(&self, checkout: Vec<(Loans, Option<&SignOff>)>) -> anyhow::Result<()> {
+        self.session.require(Permission::Checkout)?;
+        let checkout = checkout
+            .into_iter()
+            .map(|(mut loan, sign_off)| {
+                loan.item_id = self.resolve_item_id(&loan.item_id)?;
+                let sign_off =
+                    sign_off.map(|sign_off| (sign_off.operator.clone(), sign_off.witness.clone()));
+                Ok((loan, sign_off))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        self.refuse_unsigned(
+            checkout
+                .iter()
+                .filter(|(_, sign_off)| sign_off.is_none())
+                .map(|(loan, _)| loan.item_id.as_str()),
+        )?;
+        self.refuse_rule_violations(checkout.iter().map(|(loan, _)| loan))?;
+        Python::with_gil(|py| {
+            self.pool.call_method1(py, "insert_checkout", (checkout,))?;
+            Ok(())
+        })
+    }
+
+    /// Closes an open loan of a regulated item with a return date and records its sign-off
+    pub fn update_signed_loan(
+        &self,
+        lending_id: String,
+        borrowing_date: Option<NaiveDate>,
+        return_date: NaiveDate,
+        sign_off: &SignOff,
+    ) -> anyhow::Result<()> {
+        self.session.require(Permission::Checkout)?;
+        Python::with_gil(|py| {
+            self.pool.call_method1(
+                py,
+                "update_loan",
+                (
+                    lending_id,
+                    borrowing_date,
+                    return_date,
+                    (sign_off.operator.as_str(), sign_off.witness.as_str()),
+                ),
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Returns a regulated item and records its sign-off
+    pub fn return_signed_item(&self, item_id: String, sign_off: &SignOff) -> anyhow::Result<()> {
+        self.session.require(Permission::Checkout)?;
+        let item_id = self.resolve_item_id(&item_id)?;
+        Python::with_gil(|py| {
+            self.pool.call_method1(
+                py,
+                "return_item",
+                (
+                    item_id,
+                    (sign_off.operator.as_str(), sign_off.witness.as_str()),
+                ),
+            )?;
+            Ok(())
+        })
+    }
+
+    /// How much of each consumable was issued per user or unit between the dates
+    pub fn get_consumption(
+        &self,
+        group: ConsumptionGroup,
+        since: Option<NaiveDate>,
+        until: Option<NaiveDate>,
+    ) -> anyhow::Result<Consumptions> {
+        self.session.require(Permission::Read)?;
+        Python::with_gil(|py| {
+            let consumption =
+                self.pool
+                    .call_method1(py, "get_consumption", (group.as_str(), since, until))?;
+            let consumption: Vec<PyConsumption> = consumption.extract(py)?;
+            let consumption: Vec<Consumption> =
+                consumption.into_iter().map(Consumption::from).collect();
+            Ok(consumption.into())
+        })
+    }
+
+    /// The available count of every product and size, and how many were lent out or issued since
+    /// the date
+    pub fn get_stock_levels(&self, since: NaiveDate) -> anyhow::Result<Vec<StockLevel>> {
+        self.session.require(Permission::Read)?;
+        Python::with_gil(|py| {
+            let levels = self.pool.call_method1(py, "get_stock_levels", (since,))?;
+            let levels: Vec<PyStockLevel> = levels.extract(py)?;
+            Ok(levels.into_iter().map(StockLevel::from).collect())
+        })
+    }
+
+    /// Every item and loan, for the inventory reports
+    pub fn get_report_data(&self) -> anyhow::Result<ReportData> {
+        self.session.require(Permission::Read)?;
+        Python::with_gil(|py| {
+            let items = self.pool.call_method0(py, "get_report_items")?;
+            let items: Vec<PyReportItem> = items.extract(py)?;
+            let loans = self.pool.call_method0(py, "get_report_loans")?;
+            let loans: Vec<PyReportLoan> = loans.extract(py)?;
+            Ok(ReportData {
+                items: items.into_iter().map(ReportItem::from).collect(),
+                loans: loans.into_iter().map(ReportLoan::from).collect(),
+            })
+        })
+    }
+
+    /// Checks the password of an operator, allowed for anyone so operators can log in
+    pub fn verify_operator(&self, username: &str, password: &str) -> anyhow::Result<bool> {
+        Python::with_gil(|py| {
+            let valid = self
+                .pool
+                .call_method1(py, "verify_operator", (username, password))?;
+            Ok(valid.extract(py)?)
+        })
+    }
+
+    /// Logs an operator in, the token is `None` if the username or password is wrong
+    pub fn login_operator(&self, username: &str, password: &str) -> anyhow::Result<Option<String>> {
+        Python::with_gil(|py| {
+            let token = self
+                .pool
+                .call_method1(py, "login_operator", (username, password))?;
+            Ok(token.extract(py)?)
+        })
+    }
+
+    /// Makes the login of the current operator stop working, also from other copies of the token
+    pub fn logout_operator(&self) -> anyhow::Result<()> {
+        let Session::Operator { username, .. } = &self.session else {
+            return Ok(());
+        };
+        Python::with_gil(|py| {
+            self.pool
+                .call_method1(py, "logout_operator", (username.as_str(),))?;
+            Ok(())
+        })
+    }
+
+    pub fn get_operators(&self) -> anyhow::Result<Operators> {
+        self.session.require(Permission::Manage)?;
+        Python::with_gil(|py| {
+            let operators = self.pool.call_method0(py, "get_operators")?;
+            let operators: Vec<PyOperator> = operators.extract(py)?;
+            let operators: Vec<Operator> = operators.into_iter().map(Operator::from).collect();
+            Ok(operators.into())
+        })
+    }
+
+    pub fn add_operator(&self, username: &str, role: Role, password: &str) -> anyhow::Result<()> {
+        self.session.require(Permission::Manage)?;
+        Python::with_gil(|py| {
+            self.pool
+                .call_method1(py, "add_operator", (username, role.as_str(), password))?;
+            Ok(())
+        })
+    }
+
+    pub fn remove_operator(&self, username: &str) -> anyhow::Result<()> {
+        self.session.require(Permission::Manage)?;
+        Python::with_gil(|py| {
+            self.pool.call_method1(py, "remove_operator", (username,))?;
+            Ok(())
+        })
+    }
+
+    /// Reads every record in the database for an archive
+    pub fn export_all(&self) -> anyhow::Result<ArchiveData> {
+        self.session.require(Permission::Manage)?;
+        Python::with_gil(|py| {
+            let data = self.pool.call_method0(py, "export_all")?;
+            let data: String = data.extract(py)?;
+            Ok(serde_json::from_str(&data)?)
+        })
+    }
+
+    /// Restores the records of an archive into an empty database in a single transaction
+    pub fn restore_all(&self, data: ArchiveData) -> anyhow::Result<()> {
+        self.session.require(Permission::Manage)?;
+        let data = serde_json::to_string(&data)?;
+        Python::with_gil(|py| {
+            self.pool.call_method1(py, "restore_all", (data,))?;
+            Ok(())
+        })
+    }
+
+    /// Inserts every record in a single transaction, nothing is inserted if one of them fails
+    ///
+    /// Imported loans have no sign-off, so loans of regulated items are refused.
+    pub fn import_records(&self, records: ImportRecords) -> anyhow::Result<()> {
+        self.session.require(Permission::Catalog)?;
+        if let ImportRecords::Loans(loans) = &records {
+            self.refuse_unsigned(loans.iter().map(|loan| loan.item_id.as_str()))?;
+            self.refuse_rule_violations(loans)?;
+        }
+        Python::with_gil(|py| {
+            match records {
+                ImportRecords::Products(products) => {
+                    self.pool.call_method1(py, "import_products", (products,))?
+                }
+                ImportRecords::Items(items) => {
+                    self.pool.call_method1(py, "import_items", (items,))?
+                }
+                ImportRecords::Users(users) => {
+                    self.pool.call_method1(py, "import_users", (users,))?
+                }
+                ImportRecords::Loans(loans) => {
+                    self.pool.call_method1(py, "import_loans", (loans,))?
+                }
+            };
+            Ok(())
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
+    #[allow(clippy::assertions_on_constants)]
     fn test_get_db_handler_obj() {
         let db_handler = DBHandlerPy::get_db_handler_obj();
         match db_handler {
@@ -357,7 +1168,7 @@ mod tests {
         assert!(db_handler.is_ok());
 
         let db_handler = db_handler.unwrap();
-        let items = db_handler.get_items();
+        let items = db_handler.get_items(QueryOptions::default());
         assert!(items.is_ok());
     }
 
@@ -1,5 +1,9 @@
 use prettytable::{row, Row, Table};
 use pyo3::{pyclass, pymethods, FromPyObject};
+use serde::Serialize;
+
+use crate::output::Records;
+use crate::users::ssn::{mask_ssn, MaskSsns};
 
 #[derive(FromPyObject)]
 pub struct PyNumberBorrow {
@@ -9,12 +13,14 @@ pub struct PyNumberBorrow {
     pub curr_borrowes: i64,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 #[pyclass]
 pub struct NumberBorrow {
     pub ssn: String,
     pub name: String,
+    #[serde(rename = "total_borrows")]
     pub tot_borrowes: i64,
+    #[serde(rename = "current_borrows")]
     pub curr_borrowes: i64,
 }
 
@@ -93,6 +99,22 @@ impl From<NumberBorrow> for NumberBorrows {
     }
 }
 
+impl Records for NumberBorrows {
+    type Record = NumberBorrow;
+
+    fn records(&self) -> &[Self::Record] {
+        &self.0
+    }
+}
+
+impl MaskSsns for NumberBorrows {
+    fn mask_ssns(&mut self) {
+        for borrow in &mut self.0 {
+            borrow.ssn = mask_ssn(&borrow.ssn);
+        }
+    }
+}
+
 impl std::ops::Index<usize> for NumberBorrows {
     type Output = NumberBorrow;
 
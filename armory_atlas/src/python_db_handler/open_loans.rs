@@ -0,0 +1,197 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+use prettytable::{row, Row, Table};
+use pyo3::{pyclass, pymethods, FromPyObject};
+use serde::Serialize;
+
+use crate::leandings::rules::CheckoutRules;
+use crate::output::Records;
+use crate::users::ssn::{mask_ssn, MaskSsns};
+
+#[derive(FromPyObject)]
+pub struct PyOpenLoan {
+    pub ssn: String,
+    pub name: String,
+    pub unit: Option<String>,
+    pub item_id: String,
+    pub product_name: String,
+    pub product_type: String,
+    pub size: Option<String>,
+    pub borrow_date: NaiveDate,
+    pub item_code: Option<String>,
+}
+
+/// An item that is lent out and not yet returned
+///
+/// `due_date` is not stored in the database, it is filled in from the loan periods of the checkout
+/// rules with [`OpenLoans::with_due_dates`].
+#[derive(Clone, Debug, Serialize)]
+#[pyclass(get_all)]
+pub struct OpenLoan {
+    pub ssn: String,
+    pub name: String,
+    pub unit: Option<String>,
+    pub item_id: String,
+    pub product_name: String,
+    pub product_type: String,
+    pub size: Option<String>,
+    pub borrow_date: NaiveDate,
+    pub due_date: Option<NaiveDate>,
+    pub item_code: Option<String>,
+}
+
+#[pymethods]
+impl OpenLoan {
+    #[pyo3(name = "__repr__")]
+    pub fn repr(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    #[pyo3(name = "__str__")]
+    pub fn str(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+#[derive(Debug, Default)]
+#[pyclass]
+pub struct OpenLoans(Vec<OpenLoan>);
+
+#[pymethods]
+impl OpenLoans {
+    #[getter(loans)]
+    fn get_loans(&self) -> Vec<OpenLoan> {
+        self.0.clone()
+    }
+
+    #[pyo3(name = "__repr__")]
+    pub fn repr(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    #[pyo3(name = "__str__")]
+    pub fn str(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+impl OpenLoans {
+    /// Sets the due date of every loan from the loan period of its product type
+    pub fn with_due_dates(mut self, rules: &CheckoutRules) -> Self {
+        for loan in &mut self.0 {
+            loan.due_date = rules.due_date(&loan.product_type, loan.borrow_date);
+        }
+        self
+    }
+
+    /// Only the loans that were due before `date`
+    pub fn overdue(self, date: NaiveDate) -> Self {
+        Self(
+            self.0
+                .into_iter()
+                .filter(|loan| loan.due_date.is_some_and(|due| due < date))
+                .collect(),
+        )
+    }
+
+    /// The loans grouped per unit, ordered by unit with the users without a unit last
+    pub fn per_unit(self) -> Vec<(Option<String>, OpenLoans)> {
+        let mut units: BTreeMap<(bool, Option<String>), Vec<OpenLoan>> = BTreeMap::new();
+        for loan in self.0 {
+            units
+                .entry((loan.unit.is_none(), loan.unit.clone()))
+                .or_default()
+                .push(loan);
+        }
+        units
+            .into_iter()
+            .map(|((_, unit), loans)| (unit, Self(loans)))
+            .collect()
+    }
+}
+
+impl From<PyOpenLoan> for OpenLoan {
+    fn from(py_loan: PyOpenLoan) -> Self {
+        Self {
+            ssn: py_loan.ssn,
+            name: py_loan.name,
+            unit: py_loan.unit,
+            item_id: py_loan.item_id,
+            product_name: py_loan.product_name,
+            product_type: py_loan.product_type,
+            size: py_loan.size,
+            borrow_date: py_loan.borrow_date,
+            due_date: None,
+            item_code: py_loan.item_code,
+        }
+    }
+}
+
+impl From<Vec<OpenLoan>> for OpenLoans {
+    fn from(loans: Vec<OpenLoan>) -> Self {
+        Self(loans)
+    }
+}
+
+impl From<OpenLoans> for Vec<OpenLoan> {
+    fn from(loans: OpenLoans) -> Self {
+        loans.0
+    }
+}
+
+impl Records for OpenLoans {
+    type Record = OpenLoan;
+
+    fn records(&self) -> &[Self::Record] {
+        &self.0
+    }
+}
+
+impl MaskSsns for OpenLoans {
+    fn mask_ssns(&mut self) {
+        for loan in &mut self.0 {
+            loan.ssn = mask_ssn(&loan.ssn);
+        }
+    }
+}
+
+impl From<&OpenLoan> for Row {
+    fn from(value: &OpenLoan) -> Self {
+        row![
+            value.ssn,
+            value.name,
+            value.unit.clone().unwrap_or_default(),
+            value.item_id,
+            value.item_code.clone().unwrap_or_default(),
+            value.product_name,
+            value.size.clone().unwrap_or_default(),
+            value.borrow_date,
+            value
+                .due_date
+                .map(|date| date.to_string())
+                .unwrap_or_default()
+        ]
+    }
+}
+
+impl From<OpenLoans> for Table {
+    fn from(loans: OpenLoans) -> Self {
+        let mut table = Table::new();
+        table.add_row(row![
+            "SSN",
+            "Name",
+            "Unit",
+            "Item ID",
+            "Item Code",
+            "Product Name",
+            "Size",
+            "Borrow Date",
+            "Due Date"
+        ]);
+        for loan in loans.0 {
+            table.add_row((&loan).into());
+        }
+        table
+    }
+}
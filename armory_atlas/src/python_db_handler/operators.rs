@@ -0,0 +1,99 @@
+use prettytable::{row, Row, Table};
+use pyo3::{pyclass, pymethods, FromPyObject};
+use serde::Serialize;
+
+use crate::output::Records;
+
+#[derive(FromPyObject)]
+pub struct PyOperator {
+    pub username: String,
+    pub role: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[pyclass(get_all)]
+pub struct Operator {
+    pub username: String,
+    pub role: String,
+}
+
+#[pymethods]
+impl Operator {
+    #[pyo3(name = "__repr__")]
+    pub fn repr(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    #[pyo3(name = "__str__")]
+    pub fn str(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+#[derive(Debug)]
+#[pyclass]
+pub struct Operators(Vec<Operator>);
+
+#[pymethods]
+impl Operators {
+    #[getter(operators)]
+    fn get_operators(&self) -> Vec<Operator> {
+        self.0.clone()
+    }
+
+    #[pyo3(name = "__repr__")]
+    pub fn repr(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    #[pyo3(name = "__str__")]
+    pub fn str(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+impl From<PyOperator> for Operator {
+    fn from(py_operator: PyOperator) -> Self {
+        Self {
+            username: py_operator.username,
+            role: py_operator.role,
+        }
+    }
+}
+
+impl From<Vec<Operator>> for Operators {
+    fn from(operators: Vec<Operator>) -> Self {
+        Self(operators)
+    }
+}
+
+impl From<Operators> for Vec<Operator> {
+    fn from(operators: Operators) -> Self {
+        operators.0
+    }
+}
+
+impl Records for Operators {
+    type Record = Operator;
+
+    fn records(&self) -> &[Self::Record] {
+        &self.0
+    }
+}
+
+impl From<&Operator> for Row {
+    fn from(value: &Operator) -> Self {
+        row![value.username, value.role]
+    }
+}
+
+impl From<Operators> for Table {
+    fn from(operators: Operators) -> Self {
+        let mut table = Table::new();
+        table.add_row(row!["Username", "Role"]);
+        for operator in operators.0 {
+            table.add_row((&operator).into());
+        }
+        table
+    }
+}
@@ -0,0 +1,189 @@
+use crate::fuzzy::Scored;
+use crate::output::Records;
+use crate::products::Product;
+use prettytable::{row, Row, Table};
+use pyo3::{pyclass, pymethods, FromPyObject};
+use serde::Serialize;
+use std::ops::Index;
+
+#[derive(FromPyObject)]
+pub struct PyProduct {
+    pub product_id: String,
+    pub product_name: String,
+    pub product_type: String,
+}
+
+#[derive(Debug)]
+#[pyclass]
+pub struct Products(Vec<Product>);
+
+#[pymethods]
+impl Products {
+    #[getter(products)]
+    fn get_products(&self) -> Vec<Product> {
+        self.0.clone()
+    }
+
+    #[pyo3(name = "__repr__")]
+    pub fn repr(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    #[pyo3(name = "__str__")]
+    pub fn str(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+impl From<PyProduct> for Product {
+    fn from(py_product: PyProduct) -> Self {
+        Product::new(
+            py_product.product_id,
+            py_product.product_name,
+            py_product.product_type,
+        )
+    }
+}
+
+impl From<Vec<Product>> for Products {
+    fn from(products: Vec<Product>) -> Self {
+        Self(products)
+    }
+}
+
+impl From<Products> for Vec<Product> {
+    fn from(products: Products) -> Self {
+        products.0
+    }
+}
+
+impl Records for Products {
+    type Record = Product;
+
+    fn records(&self) -> &[Self::Record] {
+        &self.0
+    }
+}
+
+impl Index<usize> for Products {
+    type Output = Product;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.0[index]
+    }
+}
+
+impl From<&Product> for Row {
+    fn from(value: &Product) -> Self {
+        row![value.product_id, value.product_name, value.product_type]
+    }
+}
+
+impl From<Products> for Table {
+    fn from(products: Products) -> Self {
+        let mut table = Table::new();
+        table.add_row(row!["Product ID", "Product Name", "Product Type"]);
+        for product in products.0 {
+            table.add_row((&product).into());
+        }
+        table
+    }
+}
+
+/// A product found by a fuzzy search together with how well it matched
+#[derive(Clone, Debug, Serialize)]
+#[pyclass(get_all)]
+pub struct ProductMatch {
+    pub product_id: String,
+    pub product_name: String,
+    pub product_type: String,
+    pub score: f64,
+}
+
+#[pymethods]
+impl ProductMatch {
+    #[pyo3(name = "__repr__")]
+    pub fn repr(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    #[pyo3(name = "__str__")]
+    pub fn str(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+#[derive(Debug)]
+#[pyclass]
+pub struct ProductMatches(Vec<ProductMatch>);
+
+#[pymethods]
+impl ProductMatches {
+    #[getter(matches)]
+    fn get_matches(&self) -> Vec<ProductMatch> {
+        self.0.clone()
+    }
+
+    #[pyo3(name = "__repr__")]
+    pub fn repr(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    #[pyo3(name = "__str__")]
+    pub fn str(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+impl From<Scored<Product>> for ProductMatch {
+    fn from(scored: Scored<Product>) -> Self {
+        Self {
+            product_id: scored.value.product_id,
+            product_name: scored.value.product_name,
+            product_type: scored.value.product_type,
+            score: scored.score,
+        }
+    }
+}
+
+impl From<Vec<ProductMatch>> for ProductMatches {
+    fn from(matches: Vec<ProductMatch>) -> Self {
+        Self(matches)
+    }
+}
+
+impl From<ProductMatches> for Vec<ProductMatch> {
+    fn from(matches: ProductMatches) -> Self {
+        matches.0
+    }
+}
+
+impl Records for ProductMatches {
+    type Record = ProductMatch;
+
+    fn records(&self) -> &[Self::Record] {
+        &self.0
+    }
+}
+
+impl From<&ProductMatch> for Row {
+    fn from(value: &ProductMatch) -> Self {
+        row![
+            value.product_id,
+            value.product_name,
+            value.product_type,
+            format!("{:.2}", value.score)
+        ]
+    }
+}
+
+impl From<ProductMatches> for Table {
+    fn from(matches: ProductMatches) -> Self {
+        let mut table = Table::new();
+        table.add_row(row!["Product ID", "Product Name", "Product Type", "Score"]);
+        for product in matches.0 {
+            table.add_row((&product).into());
+        }
+        table
+    }
+}
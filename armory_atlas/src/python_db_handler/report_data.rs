@@ -0,0 +1,42 @@
+use chrono::NaiveDate;
+use pyo3::FromPyObject;
+
+use crate::reports::{ReportItem, ReportLoan};
+
+#[derive(FromPyObject)]
+pub struct PyReportItem {
+    pub item_id: String,
+    pub product_id: String,
+    pub product_name: String,
+    pub size: Option<String>,
+    pub quality: f32,
+}
+
+#[derive(FromPyObject)]
+pub struct PyReportLoan {
+    pub item_id: String,
+    pub borrow_date: NaiveDate,
+    pub return_date: Option<NaiveDate>,
+}
+
+impl From<PyReportItem> for ReportItem {
+    fn from(py_item: PyReportItem) -> Self {
+        Self {
+            item_id: py_item.item_id,
+            product_id: py_item.product_id,
+            product_name: py_item.product_name,
+            size: py_item.size,
+            quality: py_item.quality,
+        }
+    }
+}
+
+impl From<PyReportLoan> for ReportLoan {
+    fn from(py_loan: PyReportLoan) -> Self {
+        Self {
+            item_id: py_loan.item_id,
+            borrowed: py_loan.borrow_date,
+            returned: py_loan.return_date,
+        }
+    }
+}
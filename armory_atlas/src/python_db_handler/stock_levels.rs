@@ -0,0 +1,22 @@
+use pyo3::FromPyObject;
+
+use crate::products::reorder::StockLevel;
+
+#[derive(FromPyObject)]
+pub struct PyStockLevel {
+    pub product_id: String,
+    pub size: Option<String>,
+    pub available: usize,
+    pub recent_demand: usize,
+}
+
+impl From<PyStockLevel> for StockLevel {
+    fn from(py_level: PyStockLevel) -> Self {
+        Self {
+            product_id: py_level.product_id,
+            size: py_level.size,
+            available: py_level.available,
+            recent_demand: py_level.recent_demand,
+        }
+    }
+}
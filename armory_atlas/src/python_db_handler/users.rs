@@ -1,4 +1,7 @@
+use crate::output::Records;
+use crate::users::ssn::{mask_ssn, MaskSsns};
 use crate::users::User;
+use chrono::NaiveDate;
 use prettytable::{row, Row, Table};
 use pyo3::{pyclass, pymethods, FromPyObject};
 use rayon::prelude::*;
@@ -8,6 +11,12 @@ use std::ops::Index;
 pub struct PyUser {
     pub ssn: String,
     pub name: String,
+    pub unit: Option<String>,
+    pub rank: Option<String>,
+    pub phone: Option<String>,
+    pub email: Option<String>,
+    pub active: bool,
+    pub end_of_service: Option<NaiveDate>,
 }
 
 #[derive(Debug)]
@@ -37,6 +46,12 @@ impl From<PyUser> for User {
         Self {
             ssn: py_user.ssn,
             name: py_user.name,
+            unit: py_user.unit,
+            rank: py_user.rank,
+            phone: py_user.phone,
+            email: py_user.email,
+            active: py_user.active,
+            end_of_service: py_user.end_of_service,
         }
     }
 }
@@ -46,6 +61,12 @@ impl From<User> for PyUser {
         Self {
             ssn: user.ssn,
             name: user.name,
+            unit: user.unit,
+            rank: user.rank,
+            phone: user.phone,
+            email: user.email,
+            active: user.active,
+            end_of_service: user.end_of_service,
         }
     }
 }
@@ -82,7 +103,34 @@ impl From<User> for Users {
 
 impl From<User> for Row {
     fn from(user: User) -> Self {
-        row![user.ssn, user.name]
+        row![
+            user.ssn,
+            user.name,
+            user.unit.unwrap_or_default(),
+            user.rank.unwrap_or_default(),
+            user.phone.unwrap_or_default(),
+            user.email.unwrap_or_default(),
+            if user.active { "Yes" } else { "No" },
+            user.end_of_service
+                .map(|date| date.to_string())
+                .unwrap_or_default()
+        ]
+    }
+}
+
+impl Records for Users {
+    type Record = User;
+
+    fn records(&self) -> &[Self::Record] {
+        &self.0
+    }
+}
+
+impl MaskSsns for Users {
+    fn mask_ssns(&mut self) {
+        for user in &mut self.0 {
+            user.ssn = mask_ssn(&user.ssn);
+        }
     }
 }
 
@@ -97,7 +145,16 @@ impl Index<usize> for Users {
 impl From<Users> for Table {
     fn from(users: Users) -> Self {
         let mut table = Table::new();
-        table.add_row(row!["SSN", "Name"]);
+        table.add_row(row![
+            "SSN",
+            "Name",
+            "Unit",
+            "Rank",
+            "Phone",
+            "Email",
+            "Active",
+            "End of Service"
+        ]);
         for user in users.0 {
             table.add_row(user.into());
         }
@@ -0,0 +1,143 @@
+use chrono::NaiveDate;
+
+use crate::cli::{
+    DateRangeArgs, FilterArgs, GetItemsArgs, GetLoansArgs, GetUsersArgs, NumberOfLoansArgs,
+    PageArgs,
+};
+
+/// Options for the list queries of the database handler
+///
+/// Everything here is applied by the database, so only the requested rows are fetched. Filters
+/// that a query does not support are rejected by the handler instead of being silently ignored.
+///
+/// # Example
+///
+/// ```
+/// # use armory_atlas_lib::query::QueryOptions;
+/// let options = QueryOptions {
+///     limit: Some(10),
+///     sort_by: Some("product_name".to_string()),
+///     ..Default::default()
+/// };
+/// ```
+///
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "python-db", pyo3::pyclass(get_all))]
+pub struct QueryOptions {
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+    /// The field to sort by, uses the same names as the JSON output
+    pub sort_by: Option<String>,
+    pub descending: bool,
+    pub product_type: Option<String>,
+    pub size: Option<String>,
+    pub min_quality: Option<f32>,
+    pub max_quality: Option<f32>,
+    pub from_date: Option<NaiveDate>,
+    pub to_date: Option<NaiveDate>,
+}
+
+impl QueryOptions {
+    fn paged(limit: Option<usize>, page: &PageArgs) -> Self {
+        Self {
+            limit,
+            offset: page.offset,
+            sort_by: page.sort.clone(),
+            descending: page.desc,
+            ..Default::default()
+        }
+    }
+
+    fn filtered(self, filter: &FilterArgs) -> Self {
+        Self {
+            product_type: filter.product_type.clone(),
+            size: filter.size.clone(),
+            min_quality: filter.min_quality,
+            max_quality: filter.max_quality,
+            ..self
+        }
+    }
+
+    fn dated(self, dates: &DateRangeArgs) -> Self {
+        Self {
+            from_date: dates.from,
+            to_date: dates.to,
+            ..self
+        }
+    }
+}
+
+impl From<&GetItemsArgs> for QueryOptions {
+    fn from(args: &GetItemsArgs) -> Self {
+        Self::paged(args.limit, &args.page).filtered(&args.filter)
+    }
+}
+
+impl From<&GetLoansArgs> for QueryOptions {
+    fn from(args: &GetLoansArgs) -> Self {
+        Self::paged(args.limit, &args.page)
+            .filtered(&args.filter)
+            .dated(&args.dates)
+    }
+}
+
+impl From<&GetUsersArgs> for QueryOptions {
+    fn from(args: &GetUsersArgs) -> Self {
+        Self::paged(args.limit, &args.page)
+    }
+}
+
+impl From<&NumberOfLoansArgs> for QueryOptions {
+    fn from(args: &NumberOfLoansArgs) -> Self {
+        Self::paged(args.limit, &args.page)
+    }
+}
+
+#[cfg(feature = "python-db")]
+#[pyo3::pymethods]
+impl QueryOptions {
+    #[new]
+    #[pyo3(signature = (
+        limit=None,
+        offset=None,
+        sort_by=None,
+        descending=false,
+        product_type=None,
+        size=None,
+        min_quality=None,
+        max_quality=None,
+        from_date=None,
+        to_date=None
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn py_new(
+        limit: Option<usize>,
+        offset: Option<usize>,
+        sort_by: Option<String>,
+        descending: bool,
+        product_type: Option<String>,
+        size: Option<String>,
+        min_quality: Option<f32>,
+        max_quality: Option<f32>,
+        from_date: Option<NaiveDate>,
+        to_date: Option<NaiveDate>,
+    ) -> Self {
+        Self {
+            limit,
+            offset,
+            sort_by,
+            descending,
+            product_type,
+            size,
+            min_quality,
+            max_quality,
+            from_date,
+            to_date,
+        }
+    }
+
+    #[pyo3(name = "__repr__")]
+    pub fn py_repr(&self) -> String {
+        format!("{:?}", self)
+    }
+}
@@ -0,0 +1,750 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use anyhow::{bail, Result};
+use chrono::{Datelike, Months, NaiveDate};
+use prettytable::{row, Row, Table};
+use serde::Serialize;
+
+use crate::output::Records;
+
+/// An item as the reports see it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportItem {
+    pub item_id: String,
+    pub product_id: String,
+    pub product_name: String,
+    /// `None` for products without sizes
+    pub size: Option<String>,
+    pub quality: f32,
+}
+
+/// A loan as the reports see it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportLoan {
+    pub item_id: String,
+    pub borrowed: NaiveDate,
+    pub returned: Option<NaiveDate>,
+}
+
+/// The days a report covers, both ends included
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Period {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+impl Period {
+    /// The number of days in the period
+    pub fn days(&self) -> i64 {
+        (self.to - self.from).num_days() + 1
+    }
+
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.from <= date && date <= self.to
+    }
+
+    /// The number of days of the period the item was lent out
+    ///
+    /// A loan covers the days from the day it was borrowed up to, but not including, the day it
+    /// was returned. Open loans cover every day up to the end of the period.
+    fn days_on_loan(&self, loan: &ReportLoan) -> i64 {
+        let start = loan.borrowed.max(self.from);
+        let end = loan
+            .returned
+            .and_then(|returned| returned.pred_opt())
+            .map_or(self.to, |last| last.min(self.to));
+        ((end - start).num_days() + 1).max(0)
+    }
+}
+
+/// Every item and loan in the database, the reports are computed from these
+#[derive(Debug, Clone, Default)]
+pub struct ReportData {
+    pub items: Vec<ReportItem>,
+    pub loans: Vec<ReportLoan>,
+}
+
+/// The share of time the items of a product were lent out during the period
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProductUtilisation {
+    pub product_id: String,
+    pub product_name: String,
+    pub items: usize,
+    pub days_on_loan: i64,
+    /// The number of items times the number of days in the period
+    pub item_days: i64,
+    /// `days_on_loan` as a percentage of `item_days`
+    pub utilisation: f64,
+}
+
+/// How long the loans of a product made during the period lasted
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LoanDuration {
+    pub product_id: String,
+    pub product_name: String,
+    pub returned_loans: usize,
+    pub open_loans: usize,
+    /// The average number of days of the returned loans
+    pub average_days: Option<f64>,
+    pub longest_days: Option<i64>,
+}
+
+/// How many times the items of a product and size were borrowed during the period
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SizeActivity {
+    pub product_id: String,
+    pub product_name: String,
+    pub size: String,
+    pub items: usize,
+    pub loans: usize,
+    pub loans_per_item: f64,
+}
+
+/// An item that was not borrowed during the period
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UnusedItem {
+    pub item_id: String,
+    pub product_id: String,
+    pub product_name: String,
+    pub size: Option<String>,
+    pub quality: f32,
+}
+
+/// How the qualities of the items of a product are spread
+///
+/// `poor` counts the items below 0.25, `fair` below 0.5, `good` below 0.75 and `excellent` the
+/// rest.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QualityDistribution {
+    pub product_id: String,
+    pub product_name: String,
+    pub items: usize,
+    pub average: f64,
+    pub lowest: f32,
+    pub highest: f32,
+    pub poor: usize,
+    pub fair: usize,
+    pub good: usize,
+    pub excellent: usize,
+}
+
+/// The number of loans made and returned in a month
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MonthlyLoans {
+    /// The month as `YYYY-MM`
+    pub month: String,
+    pub loans: usize,
+    pub returns: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct Utilisations(Vec<ProductUtilisation>);
+
+#[derive(Debug, Default)]
+pub struct LoanDurations(Vec<LoanDuration>);
+
+#[derive(Debug, Default)]
+pub struct BusiestSizes(Vec<SizeActivity>);
+
+#[derive(Debug, Default)]
+pub struct UnusedItems(Vec<UnusedItem>);
+
+#[derive(Debug, Default)]
+pub struct QualityDistributions(Vec<QualityDistribution>);
+
+#[derive(Debug, Default)]
+pub struct LoansPerMonth(Vec<MonthlyLoans>);
+
+impl ReportData {
+    /// The period to report on
+    ///
+    /// Starts at `from`, or the day of the first loan, and ends at `to`, or `today`.
+    pub fn period(
+        &self,
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+        today: NaiveDate,
+    ) -> Result<Period> {
+        let to = to.unwrap_or(today);
+        let from = match from {
+            Some(from) if from > to => bail!("The period has to start on or before {to}"),
+            Some(from) => from,
+            None => self
+                .loans
+                .iter()
+                .map(|loan| loan.borrowed)
+                .min()
+                .map_or(to, |first| first.min(to)),
+        };
+
+        Ok(Period { from, to })
+    }
+
+    /// The items grouped per product, ordered by product name
+    fn products(&self) -> BTreeMap<(&str, &str), Vec<&ReportItem>> {
+        let mut products: BTreeMap<(&str, &str), Vec<&ReportItem>> = BTreeMap::new();
+        for item in &self.items {
+            products
+                .entry((item.product_name.as_str(), item.product_id.as_str()))
+                .or_default()
+                .push(item);
+        }
+        products
+    }
+
+    fn loans_per_item(&self) -> HashMap<&str, Vec<&ReportLoan>> {
+        let mut loans: HashMap<&str, Vec<&ReportLoan>> = HashMap::new();
+        for loan in &self.loans {
+            loans.entry(loan.item_id.as_str()).or_default().push(loan);
+        }
+        loans
+    }
+
+    /// The share of time the items of every product were lent out during the period
+    pub fn utilisation(&self, period: &Period) -> Utilisations {
+        let loans = self.loans_per_item();
+        let utilisations = self
+            .products()
+            .into_iter()
+            .map(|((product_name, product_id), items)| {
+                let days_on_loan = items
+                    .iter()
+                    .flat_map(|item| loans.get(item.item_id.as_str()).into_iter().flatten())
+                    .map(|loan| period.days_on_loan(loan))
+                    .sum();
+                let item_days = items.len() as i64 * period.days();
+
+                ProductUtilisation {
+                    product_id: product_id.to_string(),
+                    product_name: product_name.to_string(),
+                    items: items.len(),
+                    days_on_loan,
+                    item_days,
+                    utilisation: round(100.0 * days_on_loan as f64 / item_days as f64, 1),
+                }
+            })
+            .collect();
+
+        Utilisations(utilisations)
+    }
+
+    /// The number and length of the loans of every product made during the period
+    pub fn loan_durations(&self, period: &Period) -> LoanDurations {
+        let loans = self.loans_per_item();
+        let durations = self
+            .products()
+            .into_iter()
+            .map(|((product_name, product_id), items)| {
+                let made: Vec<&ReportLoan> = items
+                    .iter()
+                    .flat_map(|item| loans.get(item.item_id.as_str()).into_iter().flatten())
+                    .filter(|loan| period.contains(loan.borrowed))
+                    .copied()
+                    .collect();
+                let days: Vec<i64> = made
+                    .iter()
+                    .filter_map(|loan| {
+                        loan.returned
+                            .filter(|returned| *returned <= period.to)
+                            .map(|returned| (returned - loan.borrowed).num_days())
+                    })
+                    .collect();
+
+                LoanDuration {
+                    product_id: product_id.to_string(),
+                    product_name: product_name.to_string(),
+                    returned_loans: days.len(),
+                    open_loans: made.len() - days.len(),
+                    average_days: (!days.is_empty())
+                        .then(|| round(days.iter().sum::<i64>() as f64 / days.len() as f64, 1)),
+                    longest_days: days.iter().max().copied(),
+                }
+            })
+            .collect();
+
+        LoanDurations(durations)
+    }
+
+    /// The sizes borrowed the most during the period, the busiest first
+    ///
+    /// Products without sizes are left out.
+    pub fn busiest_sizes(&self, period: &Period) -> BusiestSizes {
+        let loans = self.loans_per_item();
+        let mut sizes: BTreeMap<(&str, &str, &str), (usize, usize)> = BTreeMap::new();
+        for item in &self.items {
+            let Some(size) = item.size.as_deref() else {
+                continue;
+            };
+            let borrowed = loans
+                .get(item.item_id.as_str())
+                .into_iter()
+                .flatten()
+                .filter(|loan| period.contains(loan.borrowed))
+                .count();
+
+            let (items, loans) = sizes
+                .entry((item.product_name.as_str(), item.product_id.as_str(), size))
+                .or_default();
+            *items += 1;
+            *loans += borrowed;
+        }
+
+        let mut busiest: Vec<SizeActivity> = sizes
+            .into_iter()
+            .map(
+                |((product_name, product_id, size), (items, loans))| SizeActivity {
+                    product_id: product_id.to_string(),
+                    product_name: product_name.to_string(),
+                    size: size.to_string(),
+                    items,
+                    loans,
+                    loans_per_item: round(loans as f64 / items as f64, 1),
+                },
+            )
+            .collect();
+        // Stable, so sizes with as many loans stay ordered by product name
+        busiest.sort_by_key(|size| std::cmp::Reverse(size.loans));
+
+        BusiestSizes(busiest)
+    }
+
+    /// The items that were not borrowed during the period, ordered by product name and size
+    pub fn never_borrowed(&self, period: &Period) -> UnusedItems {
+        let borrowed: HashSet<&str> = self
+            .loans
+            .iter()
+            .filter(|loan| period.contains(loan.borrowed))
+            .map(|loan| loan.item_id.as_str())
+            .collect();
+
+        let mut unused: Vec<UnusedItem> = self
+            .items
+            .iter()
+            .filter(|item| !borrowed.contains(item.item_id.as_str()))
+            .map(|item| UnusedItem {
+                item_id: item.item_id.clone(),
+                product_id: item.product_id.clone(),
+                product_name: item.product_name.clone(),
+                size: item.size.clone(),
+                quality: item.quality,
+            })
+            .collect();
+        unused.sort_by(|a, b| {
+            (&a.product_name, &a.size, &a.item_id).cmp(&(&b.product_name, &b.size, &b.item_id))
+        });
+
+        UnusedItems(unused)
+    }
+
+    /// How the qualities of the items of every product are spread
+    pub fn quality_distribution(&self) -> QualityDistributions {
+        let distributions = self
+            .products()
+            .into_iter()
+            .map(|((product_name, product_id), items)| {
+                let qualities: Vec<f32> = items.iter().map(|item| item.quality).collect();
+                let bucket = |low: f32, high: f32| {
+                    qualities
+                        .iter()
+                        .filter(|quality| low <= **quality && **quality < high)
+                        .count()
+                };
+
+                QualityDistribution {
+                    product_id: product_id.to_string(),
+                    product_name: product_name.to_string(),
+                    items: qualities.len(),
+                    average: round(
+                        qualities.iter().map(|quality| *quality as f64).sum::<f64>()
+                            / qualities.len() as f64,
+                        2,
+                    ),
+                    lowest: qualities.iter().copied().fold(f32::INFINITY, f32::min),
+                    highest: qualities.iter().copied().fold(f32::NEG_INFINITY, f32::max),
+                    poor: bucket(f32::NEG_INFINITY, 0.25),
+                    fair: bucket(0.25, 0.5),
+                    good: bucket(0.5, 0.75),
+                    excellent: bucket(0.75, f32::INFINITY),
+                }
+            })
+            .collect();
+
+        QualityDistributions(distributions)
+    }
+
+    /// The number of loans made and returned in every month of the period
+    pub fn loans_per_month(&self, period: &Period) -> LoansPerMonth {
+        let mut months: BTreeMap<NaiveDate, (usize, usize)> = BTreeMap::new();
+        let mut month = first_of_month(period.from);
+        while month <= period.to {
+            months.insert(month, (0, 0));
+            month = month + Months::new(1);
+        }
+
+        for loan in &self.loans {
+            if period.contains(loan.borrowed) {
+                if let Some((loans, _)) = months.get_mut(&first_of_month(loan.borrowed)) {
+                    *loans += 1;
+                }
+            }
+            if let Some(returned) = loan.returned.filter(|returned| period.contains(*returned)) {
+                if let Some((_, returns)) = months.get_mut(&first_of_month(returned)) {
+                    *returns += 1;
+                }
+            }
+        }
+
+        LoansPerMonth(
+            months
+                .into_iter()
+                .map(|(month, (loans, returns))| MonthlyLoans {
+                    month: month.format("%Y-%m").to_string(),
+                    loans,
+                    returns,
+                })
+                .collect(),
+        )
+    }
+}
+
+fn first_of_month(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap_or(date)
+}
+
+fn round(value: f64, decimals: i32) -> f64 {
+    let scale = 10f64.powi(decimals);
+    (value * scale).round() / scale
+}
+
+impl Records for Utilisations {
+    type Record = ProductUtilisation;
+
+    fn records(&self) -> &[Self::Record] {
+        &self.0
+    }
+}
+
+impl From<Utilisations> for Table {
+    fn from(utilisations: Utilisations) -> Self {
+        let mut table = Table::new();
+        table.add_row(row![
+            "Product ID",
+            "Product Name",
+            "Items",
+            "Days On Loan",
+            "Item Days",
+            "Utilisation"
+        ]);
+        for value in utilisations.0 {
+            table.add_row(row![
+                value.product_id,
+                value.product_name,
+                value.items,
+                value.days_on_loan,
+                value.item_days,
+                format!("{:.1}%", value.utilisation)
+            ]);
+        }
+        table
+    }
+}
+
+impl Records for LoanDurations {
+    type Record = LoanDuration;
+
+    fn records(&self) -> &[Self::Record] {
+        &self.0
+    }
+}
+
+impl From<LoanDurations> for Table {
+    fn from(durations: LoanDurations) -> Self {
+        let mut table = Table::new();
+        table.add_row(row![
+            "Product ID",
+            "Product Name",
+            "Returned Loans",
+            "Open Loans",
+            "Average Days",
+            "Longest Days"
+        ]);
+        for value in durations.0 {
+            table.add_row(row![
+                value.product_id,
+                value.product_name,
+                value.returned_loans,
+                value.open_loans,
+                value
+                    .average_days
+                    .map(|days| format!("{days:.1}"))
+                    .unwrap_or_default(),
+                value
+                    .longest_days
+                    .map(|days| days.to_string())
+                    .unwrap_or_default()
+            ]);
+        }
+        table
+    }
+}
+
+impl Records for BusiestSizes {
+    type Record = SizeActivity;
+
+    fn records(&self) -> &[Self::Record] {
+        &self.0
+    }
+}
+
+impl From<BusiestSizes> for Table {
+    fn from(sizes: BusiestSizes) -> Self {
+        let mut table = Table::new();
+        table.add_row(row![
+            "Product ID",
+            "Product Name",
+            "Size",
+            "Items",
+            "Loans",
+            "Loans Per Item"
+        ]);
+        for value in sizes.0 {
+            table.add_row(row![
+                value.product_id,
+                value.product_name,
+                value.size,
+                value.items,
+                value.loans,
+                format!("{:.1}", value.loans_per_item)
+            ]);
+        }
+        table
+    }
+}
+
+impl Records for UnusedItems {
+    type Record = UnusedItem;
+
+    fn records(&self) -> &[Self::Record] {
+        &self.0
+    }
+}
+
+impl From<&UnusedItem> for Row {
+    fn from(value: &UnusedItem) -> Self {
+        row![
+            value.item_id,
+            value.product_id,
+            value.product_name,
+            value.size.clone().unwrap_or_default(),
+            value.quality
+        ]
+    }
+}
+
+impl From<UnusedItems> for Table {
+    fn from(items: UnusedItems) -> Self {
+        let mut table = Table::new();
+        table.add_row(row![
+            "Item ID",
+            "Product ID",
+            "Product Name",
+            "Size",
+            "Quality"
+        ]);
+        for item in items.0 {
+            table.add_row((&item).into());
+        }
+        table
+    }
+}
+
+impl Records for QualityDistributions {
+    type Record = QualityDistribution;
+
+    fn records(&self) -> &[Self::Record] {
+        &self.0
+    }
+}
+
+impl From<QualityDistributions> for Table {
+    fn from(distributions: QualityDistributions) -> Self {
+        let mut table = Table::new();
+        table.add_row(row![
+            "Product ID",
+            "Product Name",
+            "Items",
+            "Average",
+            "Lowest",
+            "Highest",
+            "Poor",
+            "Fair",
+            "Good",
+            "Excellent"
+        ]);
+        for value in distributions.0 {
+            table.add_row(row![
+                value.product_id,
+                value.product_name,
+                value.items,
+                format!("{:.2}", value.average),
+                format!("{:.2}", value.lowest),
+                format!("{:.2}", value.highest),
+                value.poor,
+                value.fair,
+                value.good,
+                value.excellent
+            ]);
+        }
+        table
+    }
+}
+
+impl Records for LoansPerMonth {
+    type Record = MonthlyLoans;
+
+    fn records(&self) -> &[Self::Record] {
+        &self.0
+    }
+}
+
+impl From<LoansPerMonth> for Table {
+    fn from(months: LoansPerMonth) -> Self {
+        let mut table = Table::new();
+        table.add_row(row!["Month", "Loans", "Returns"]);
+        for value in months.0 {
+            table.add_row(row![value.month, value.loans, value.returns]);
+        }
+        table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, month, day).unwrap()
+    }
+
+    fn item(item_id: &str, product: &str, size: Option<&str>, quality: f32) -> ReportItem {
+        ReportItem {
+            item_id: item_id.to_string(),
+            product_id: format!("ID-{product}"),
+            product_name: product.to_string(),
+            size: size.map(str::to_string),
+            quality,
+        }
+    }
+
+    fn loan(item_id: &str, borrowed: NaiveDate, returned: Option<NaiveDate>) -> ReportLoan {
+        ReportLoan {
+            item_id: item_id.to_string(),
+            borrowed,
+            returned,
+        }
+    }
+
+    fn data() -> ReportData {
+        ReportData {
+            items: vec![
+                item("b1", "Boots", Some("M"), 0.9),
+                item("b2", "Boots", Some("L"), 0.3),
+                item("b3", "Boots", Some("M"), 0.6),
+                item("k1", "Knife", None, 0.1),
+            ],
+            loans: vec![
+                loan("b1", date(1, 1), Some(date(1, 11))),
+                loan("b1", date(2, 1), None),
+                loan("b3", date(1, 21), Some(date(2, 10))),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_period() {
+        let data = data();
+        let period = data.period(None, Some(date(2, 9)), date(6, 1)).unwrap();
+        assert_eq!(period.from, date(1, 1));
+        assert_eq!(period.days(), 40);
+        assert!(data
+            .period(Some(date(3, 1)), Some(date(2, 1)), date(6, 1))
+            .is_err());
+    }
+
+    #[test]
+    fn test_utilisation_and_durations() {
+        let data = data();
+        let period = data.period(None, Some(date(2, 9)), date(6, 1)).unwrap();
+
+        let utilisation = data.utilisation(&period);
+        let boots = &utilisation.records()[0];
+        // b1: 10 days in January and 9 in February, b3: 11 days in January and 9 in February
+        assert_eq!(boots.days_on_loan, 39);
+        assert_eq!(boots.item_days, 120);
+        assert_eq!(boots.utilisation, 32.5);
+        assert_eq!(utilisation.records()[1].days_on_loan, 0);
+
+        let durations = data.loan_durations(&period);
+        let boots = &durations.records()[0];
+        assert_eq!((boots.returned_loans, boots.open_loans), (1, 2));
+        assert_eq!(boots.average_days, Some(10.0));
+        assert_eq!(durations.records()[1].longest_days, None);
+    }
+
+    #[test]
+    fn test_sizes_and_unused_items() {
+        let data = data();
+        let period = data.period(Some(date(1, 15)), None, date(3, 1)).unwrap();
+
+        let busiest = data.busiest_sizes(&period);
+        let sizes: Vec<(&str, usize, usize)> = busiest
+            .records()
+            .iter()
+            .map(|size| (size.size.as_str(), size.items, size.loans))
+            .collect();
+        assert_eq!(sizes, vec![("M", 2, 2), ("L", 1, 0)]);
+
+        let unused: Vec<String> = data
+            .never_borrowed(&period)
+            .records()
+            .iter()
+            .map(|item| item.item_id.clone())
+            .collect();
+        assert_eq!(unused, vec!["b2", "k1"]);
+    }
+
+    #[test]
+    fn test_quality_and_months() {
+        let data = data();
+        let quality = data.quality_distribution();
+        let boots = &quality.records()[0];
+        assert_eq!(
+            (boots.poor, boots.fair, boots.good, boots.excellent),
+            (0, 1, 1, 1)
+        );
+        assert_eq!(boots.average, 0.6);
+        assert_eq!(boots.lowest, 0.3);
+
+        let period = data.period(None, Some(date(3, 5)), date(6, 1)).unwrap();
+        assert_eq!(
+            data.loans_per_month(&period).records(),
+            &[
+                MonthlyLoans {
+                    month: "2024-01".to_string(),
+                    loans: 2,
+                    returns: 1
+                },
+                MonthlyLoans {
+                    month: "2024-02".to_string(),
+                    loans: 1,
+                    returns: 1
+                },
+                MonthlyLoans {
+                    month: "2024-03".to_string(),
+                    loans: 0,
+                    returns: 0
+                },
+            ]
+        );
+    }
+}
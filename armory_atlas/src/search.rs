@@ -0,0 +1,346 @@
+use std::str::FromStr;
+
+use anyhow::{bail, Result};
+use clap::ValueEnum;
+
+#[cfg(feature = "python-db")]
+use crate::fuzzy::{rank, Scored};
+use crate::output::{render, OutputFormat};
+#[cfg(feature = "python-db")]
+use crate::products::Product;
+#[cfg(feature = "python-db")]
+use crate::python_db_handler::{
+    item_records::ItemRecords,
+    loans::DetailedLoans,
+    products::{ProductMatch, ProductMatches},
+    users::Users,
+    DBHandlerPy as DBHandler,
+};
+#[cfg(feature = "python-db")]
+use crate::users::ssn::MaskSsns;
+
+/// The operators a qualifier can use, longest first so `>=` is not read as `>`
+const OPERATORS: [&str; 5] = [">=", "<=", ">", "<", ":"];
+
+/// What a search is run against
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchScope {
+    Products,
+    Items,
+    Users,
+    Loans,
+}
+
+/// A single `field:value` or `field>value` part of a search query
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "python-db", pyo3::pyclass(get_all))]
+pub struct Qualifier {
+    pub field: String,
+    /// One of `:`, `>`, `>=`, `<` or `<=`
+    pub op: String,
+    pub value: String,
+}
+
+/// A parsed search query
+///
+/// Words without a qualifier end up in `terms` and are matched against the names of whatever is
+/// searched for. Which fields a qualifier can use depends on the [`SearchScope`], that is checked
+/// by the database handler.
+///
+/// # Example
+///
+/// ```
+/// # use armory_atlas_lib::search::SearchQuery;
+/// let query: SearchQuery = r#"type:Weapon name:"combat knife" quality>0.5"#.parse().unwrap();
+///
+/// assert_eq!(query.qualifiers.len(), 3);
+/// assert_eq!(query.qualifiers[1].value, "combat knife");
+/// ```
+///
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "python-db", pyo3::pyclass(get_all))]
+pub struct SearchQuery {
+    pub terms: Vec<String>,
+    pub qualifiers: Vec<Qualifier>,
+}
+
+impl SearchQuery {
+    /// Builds a query from command line arguments
+    ///
+    /// The shell has already removed the quotes from something like `name:"combat knife"`, so
+    /// arguments containing whitespace are quoted again before parsing.
+    pub fn from_args(args: &[String]) -> Result<Self> {
+        let query = args
+            .iter()
+            .map(|arg| {
+                if !arg.contains(char::is_whitespace) || arg.contains('"') {
+                    return arg.clone();
+                }
+                match split_qualifier(arg) {
+                    Some((field, op, value)) => format!("{field}{op}\"{value}\""),
+                    None => format!("\"{arg}\""),
+                }
+            })
+            .collect::<Vec<String>>()
+            .join(" ");
+
+        query.parse()
+    }
+
+    /// A search for the records whose `field` is `value`
+    pub fn by(field: &str, value: &str) -> Self {
+        Self {
+            terms: Vec::new(),
+            qualifiers: vec![Qualifier {
+                field: field.to_string(),
+                op: ":".to_string(),
+                value: value.to_string(),
+            }],
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty() && self.qualifiers.is_empty()
+    }
+}
+
+impl FromStr for SearchQuery {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut query = SearchQuery::default();
+
+        for token in tokenize(s)? {
+            match split_qualifier(&token) {
+                Some((field, op, value)) => {
+                    let value = unquote(value);
+                    if value.is_empty() {
+                        bail!("The qualifier {field}{op} is missing a value");
+                    }
+                    query.qualifiers.push(Qualifier {
+                        field: field.to_lowercase(),
+                        op: op.to_string(),
+                        value,
+                    });
+                }
+                None => query.terms.push(unquote(&token)),
+            }
+        }
+
+        Ok(query)
+    }
+}
+
+/// Splits the query at whitespace that is not inside double quotes
+fn tokenize(s: &str) -> Result<Vec<String>> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+
+    for c in s.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                current.push(c);
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+
+    if in_quotes {
+        bail!("Unterminated quote in search query: {s}");
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+
+    Ok(tokens)
+}
+
+/// Returns the field, operator and value if the token is a qualifier
+fn split_qualifier(token: &str) -> Option<(&str, &str, &str)> {
+    let (idx, op) = OPERATORS
+        .iter()
+        .filter_map(|op| token.find(op).map(|idx| (idx, *op)))
+        .min_by_key(|(idx, op)| (*idx, std::cmp::Reverse(op.len())))?;
+
+    let field = &token[..idx];
+    if field.is_empty() || !field.chars().all(|c| c.is_ascii_alphabetic() || c == '_') {
+        return None;
+    }
+
+    Some((field, op, &token[idx + op.len()..]))
+}
+
+fn unquote(value: &str) -> String {
+    value.replace('"', "")
+}
+
+/// The result of a search, one variant for each [`SearchScope`]
+#[cfg(feature = "python-db")]
+pub enum SearchResults {
+    Products(ProductMatches),
+    Items(ItemRecords),
+    Users(Users),
+    Loans(DetailedLoans),
+}
+
+#[cfg(feature = "python-db")]
+impl SearchResults {
+    pub fn render(self, format: OutputFormat) -> Result<String> {
+        match self {
+            SearchResults::Products(products) => render(products, format),
+            SearchResults::Items(items) => render(items, format),
+            SearchResults::Users(users) => render(users, format),
+            SearchResults::Loans(loans) => render(loans, format),
+        }
+    }
+}
+
+#[cfg(feature = "python-db")]
+impl MaskSsns for SearchResults {
+    fn mask_ssns(&mut self) {
+        match self {
+            SearchResults::Users(users) => users.mask_ssns(),
+            SearchResults::Loans(loans) => loans.mask_ssns(),
+            SearchResults::Products(_) | SearchResults::Items(_) => {}
+        }
+    }
+}
+
+/// Searches the database
+///
+/// Products are matched fuzzily, the qualifiers narrow down the products in the database and the
+/// words of the query are then scored against the name, type and ID of each of them. Every other
+/// scope matches the words as they are written.
+///
+/// # Arguments
+///
+/// * `db_handler`: The database handler to search with.
+/// * `scope`: What to search for.
+/// * `query`: The parsed search query.
+///
+/// # Example
+///
+/// ```no_run
+/// # use armory_atlas_lib::python_db_handler::DBHandlerPy;
+/// # use armory_atlas_lib::search::{search, SearchScope};
+/// let db_handler = DBHandlerPy::new().unwrap();
+/// let query = "type:Weapon available:yes".parse().unwrap();
+///
+/// let items = search(&db_handler, SearchScope::Items, query).unwrap();
+/// ```
+///
+#[cfg(feature = "python-db")]
+pub fn search(
+    db_handler: &DBHandler,
+    scope: SearchScope,
+    mut query: SearchQuery,
+) -> Result<SearchResults> {
+    let results = match scope {
+        SearchScope::Products => {
+            let terms = std::mem::take(&mut query.terms).join(" ");
+            let products = db_handler.find_products(query)?;
+            SearchResults::Products(rank_products(&terms, products).into())
+        }
+        SearchScope::Items => SearchResults::Items(db_handler.find_items(query)?.into()),
+        SearchScope::Users => SearchResults::Users(db_handler.find_users(query)?.into()),
+        SearchScope::Loans => SearchResults::Loans(db_handler.find_loans(query)?.into()),
+    };
+
+    Ok(results)
+}
+
+/// Ranks products by how well their name, type or ID match the query
+///
+/// An empty query keeps every product with a score of 1.0.
+#[cfg(feature = "python-db")]
+pub fn rank_products(query: &str, products: Vec<Product>) -> Vec<ProductMatch> {
+    let ranked = if query.trim().is_empty() {
+        products
+            .into_iter()
+            .map(|value| Scored { value, score: 1.0 })
+            .collect()
+    } else {
+        rank(query, products, |product| {
+            vec![
+                product.product_name.as_str(),
+                product.product_type.as_str(),
+                product.product_id.as_str(),
+            ]
+        })
+    };
+
+    ranked.into_iter().map(ProductMatch::from).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn qualifier(field: &str, op: &str, value: &str) -> Qualifier {
+        Qualifier {
+            field: field.to_string(),
+            op: op.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_qualifiers() {
+        let query: SearchQuery =
+            r#"type:Weapon size:M name:"combat knife" available:yes quality>0.5 night"#
+                .parse()
+                .unwrap();
+
+        assert_eq!(
+            query.qualifiers,
+            vec![
+                qualifier("type", ":", "Weapon"),
+                qualifier("size", ":", "M"),
+                qualifier("name", ":", "combat knife"),
+                qualifier("available", ":", "yes"),
+                qualifier("quality", ">", "0.5"),
+            ]
+        );
+        assert_eq!(query.terms, vec!["night".to_string()]);
+    }
+
+    #[test]
+    fn test_parse_comparisons() {
+        let query: SearchQuery = "quality>=0.25 borrowed<2024-01-01".parse().unwrap();
+
+        assert_eq!(
+            query.qualifiers,
+            vec![
+                qualifier("quality", ">=", "0.25"),
+                qualifier("borrowed", "<", "2024-01-01"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(r#"name:"combat knife"#.parse::<SearchQuery>().is_err());
+        assert!("type:".parse::<SearchQuery>().is_err());
+    }
+
+    #[test]
+    fn test_from_args_requotes() {
+        let args = vec!["name:combat knife".to_string(), "type:Weapon".to_string()];
+        let query = SearchQuery::from_args(&args).unwrap();
+
+        assert_eq!(
+            query.qualifiers,
+            vec![
+                qualifier("name", ":", "combat knife"),
+                qualifier("type", ":", "Weapon"),
+            ]
+        );
+    }
+}
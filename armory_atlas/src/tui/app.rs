@@ -3,6 +3,7 @@ use anyhow::Result;
 use sqlx_mysql::MySqlPool;
 
 use crate::config::get_config;
+use crate::leandings::scan::{Feedback, ScanSession};
 use crate::python_db_handler::DBHandlerPy;
 
 #[derive(Clone, Eq, PartialEq)]
@@ -10,6 +11,7 @@ pub enum CurrentScreen {
     Main,
     Settings,
     Config,
+    Scan,
     Exit,
 }
 
@@ -31,6 +33,13 @@ pub struct App {
     pub current_page: usize,
     pub items_per_page: usize,
     pub max_page: usize,
+    pub scan_session: ScanSession,
+    /// What the scanner has typed since the last Enter
+    pub scan_input: String,
+    /// The answers to the scans, newest last
+    pub scan_log: Vec<Feedback>,
+    /// The relevance of each search result shown, `None` while a page of the stock is shown
+    pub search_relevance: Option<Vec<f64>>,
 }
 
 impl App {
@@ -54,6 +63,10 @@ impl App {
             current_page: 0,
             items_per_page: 15,
             max_page: 0,
+            scan_session: ScanSession::new(),
+            scan_input: String::new(),
+            scan_log: Vec::new(),
+            search_relevance: None,
         })
     }
 
@@ -1,7 +1,9 @@
+use crate::leandings::scan::{DatabaseCounter, Feedback, Scan, ScanSession};
 use crate::tui::app::{App, CurrentScreen};
 use crate::ItemProduct;
 use crossterm::event::{KeyCode, KeyEvent};
 use log::info;
+use std::io::Write;
 
 pub fn screen_key_events(
     app: &mut App,
@@ -17,6 +19,7 @@ pub fn screen_key_events(
             }
             KeyCode::Left => {
                 info!("Moving to prev page!");
+                app.search_relevance = None;
                 if app.current_page > 0 {
                     app.current_page = (app.current_page - 1) % app.max_page;
                     info!("new page: {}", app.current_page);
@@ -29,6 +32,7 @@ pub fn screen_key_events(
             }
             KeyCode::Right => {
                 info!("moving to next page");
+                app.search_relevance = None;
                 app.current_page = (app.current_page + 1) % app.max_page;
                 info!("new page: {}", app.current_page);
                 data_to_display.clone_from(&data_iterator[app.current_page]);
@@ -49,6 +53,10 @@ pub fn screen_key_events(
                 app.current_screen = CurrentScreen::Config;
                 (false, data_to_display)
             }
+            KeyCode::Char('s') => {
+                app.current_screen = CurrentScreen::Scan;
+                (false, data_to_display)
+            }
             KeyCode::Char('q') => {
                 app.current_screen = CurrentScreen::Exit;
                 (false, data_to_display)
@@ -64,3 +72,38 @@ pub fn screen_key_events(
         },
     }
 }
+
+/// Collects what the scanner types and carries out the scan on Enter
+///
+/// Regulated items are refused, they need a witness and can only be scanned with the `scan`
+/// command.
+pub fn scan_key_events(app: &mut App, key: KeyEvent) {
+    let scan = match key.code {
+        KeyCode::Char(c) => {
+            app.scan_input.push(c);
+            return;
+        }
+        KeyCode::Backspace => {
+            app.scan_input.pop();
+            return;
+        }
+        KeyCode::Enter => Scan::parse(&std::mem::take(&mut app.scan_input)),
+        KeyCode::Esc => Scan::Quit,
+        _ => return,
+    };
+
+    let feedback = match DatabaseCounter::new(&app.db_handler, None) {
+        Ok(counter) => app.scan_session.handle(&counter, scan),
+        Err(err) => vec![Feedback::Error(err.to_string())],
+    };
+    if feedback.iter().any(Feedback::is_error) {
+        // The terminal bell, the alternate screen is drawn on stderr as well
+        let _ = std::io::stderr().write_all(b"\x07");
+    }
+    app.scan_log.extend(feedback);
+
+    if app.scan_session.is_finished() {
+        app.scan_session = ScanSession::new();
+        app.current_screen = CurrentScreen::Main;
+    }
+}
@@ -1,5 +1,6 @@
 use std::io;
 
+use crate::query::QueryOptions;
 use crate::{search_items, ItemProduct};
 use anyhow::Result;
 use crossterm::event::{DisableMouseCapture, EnableMouseCapture, Event};
@@ -17,7 +18,7 @@ use std::sync::{Arc, Mutex};
 use tui_textarea::{Input, Key, TextArea};
 
 use crate::tui::app::{App, CurrentScreen};
-use crate::tui::key_events::screen_key_events;
+use crate::tui::key_events::{scan_key_events, screen_key_events};
 use crate::tui::ui::ui;
 
 mod app;
@@ -66,7 +67,7 @@ async fn fetch_data(app: &Arc<Mutex<App>>) -> Result<Vec<ItemProduct>> {
             FROM
                 Products p
                     JOIN
-                (SELECT ProductID, Size, count(*) as Quantity from Items group by ProductID, Size)
+                (SELECT ProductID, Size, count(*) as Quantity from Items WHERE NOT Retired group by ProductID, Size)
                     AS
                     i ON p.ProductID = i.ProductID
             WHERE
@@ -86,36 +87,54 @@ fn get_data(app: &mut App, items: &Option<Vec<ItemProduct>>) -> Result<Table<'st
         if let Some(page) = items {
             rows = page
                 .iter()
-                .map(|i| {
-                    Row::new(vec![
+                .enumerate()
+                .map(|(index, i)| {
+                    let mut cells = vec![
                         i.product_id.clone(),
                         i.product_name.clone(),
                         i.product_type.clone(),
                         i.quantity.to_string(),
                         i.size.clone(),
-                    ])
+                    ];
+                    if let Some(score) = app.search_relevance.as_ref().and_then(|r| r.get(index)) {
+                        cells.push(format!("{:.0}%", score * 100.0));
+                    }
+                    Row::new(cells)
                 })
                 .collect();
         }
 
-        let widths = [
+        // Search results get a column with their relevance
+        let mut widths = vec![
             Constraint::Percentage(10),
             Constraint::Percentage(30),
             Constraint::Percentage(20),
             Constraint::Percentage(20),
             Constraint::Percentage(20),
         ];
+        let mut header = vec![
+            "Product ID",
+            "Product Name",
+            "Product Type",
+            "Quantity",
+            "Size",
+        ];
+        if app.search_relevance.is_some() {
+            widths = vec![
+                Constraint::Percentage(10),
+                Constraint::Percentage(30),
+                Constraint::Percentage(20),
+                Constraint::Percentage(15),
+                Constraint::Percentage(10),
+                Constraint::Percentage(15),
+            ];
+            header.push("Relevance");
+        }
 
         let table = Table::new(rows, widths)
             .header(
-                Row::new(vec![
-                    "Product ID",
-                    "Product Name",
-                    "Product Type",
-                    "Quantity",
-                    "Size",
-                ])
-                .style(Style::default().add_modifier(ratatui::style::Modifier::BOLD)),
+                Row::new(header)
+                    .style(Style::default().add_modifier(ratatui::style::Modifier::BOLD)),
             )
             .block(
                 Block::default()
@@ -130,7 +149,7 @@ fn get_data(app: &mut App, items: &Option<Vec<ItemProduct>>) -> Result<Table<'st
 }
 
 async fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::Result<bool> {
-    let data: Vec<ItemProduct> = match app.db_handler.get_items() {
+    let data: Vec<ItemProduct> = match app.db_handler.get_items(QueryOptions::default()) {
         Ok(data) => data,
         Err(err) => {
             error!("{err:?}");
@@ -161,14 +180,22 @@ async fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::R
         })?;
 
         if let Event::Key(key) = event::read()? {
+            if app.current_screen == CurrentScreen::Scan {
+                if key.kind == event::KeyEventKind::Press {
+                    scan_key_events(app, key);
+                }
+                continue;
+            }
+
             match key.into() {
                 Input {
                     key: Key::Enter, ..
                 } => {
                     if app.current_screen == CurrentScreen::Main {
                         // search for the item either via name or id
-                        let query = search_box.lines()[0].trim().to_string().replace(" ", "%");
+                        let query = search_box.lines()[0].trim().to_string();
                         if query.is_empty() {
+                            app.search_relevance = None;
                             data_to_display.clone_from(&data_iterator[app.current_page]);
                             continue;
                         }
@@ -176,8 +203,10 @@ async fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::R
                         let search_result = search_items(&query).await;
                         match search_result {
                             Ok(items) => {
+                                app.search_relevance =
+                                    Some(items.iter().map(|item| item.score).collect());
                                 let items: Vec<ItemProduct> =
-                                    items.iter().map(|item| item.into()).collect();
+                                    items.iter().map(|item| (&item.value).into()).collect();
                                 data_to_display.clone_from(&items);
                             }
                             Err(e) => {
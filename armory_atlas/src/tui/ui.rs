@@ -5,6 +5,7 @@ use ratatui::widgets::{Borders, Paragraph, Table};
 use ratatui::Frame;
 use tui_textarea::TextArea;
 
+use crate::leandings::scan::Feedback;
 use crate::tui::app::{App, CurrentScreen};
 
 /// helper function to create a centered rect using up certain percentage of the available rect `r`
@@ -76,6 +77,10 @@ pub fn ui(
         }
     }
 
+    if app.current_screen == CurrentScreen::Scan {
+        render_scan_page(f, app, main_layout[1]);
+    }
+
     let current_keys_hint = {
         match app.current_screen {
             CurrentScreen::Main => Span::styled(
@@ -87,13 +92,17 @@ pub fn ui(
                 Style::default().fg(Color::Red),
             ),
             CurrentScreen::Settings => Span::styled(
-                "(c) to edit the config / (q) to quit",
+                "(c) to edit the config / (s) to scan / (q) to quit",
                 Style::default().fg(Color::Red),
             ),
             CurrentScreen::Exit => Span::styled(
                 "(y) to quit / (n) to go back",
                 Style::default().fg(Color::Red),
             ),
+            CurrentScreen::Scan => Span::styled(
+                "(enter) on an empty line to lend the scanned items / (esc) to leave",
+                Style::default().fg(Color::Red),
+            ),
             CurrentScreen::Config => Span::default(),
         }
     };
@@ -111,6 +120,48 @@ pub fn ui(
     Ok(())
 }
 
+/// The scan mode, the input line on top of the answers to the latest scans
+///
+/// The border turns red when the last scan was refused, so an error is seen from across the
+/// counter.
+fn render_scan_page(f: &mut Frame<'_>, app: &App, area: Rect) {
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(1)])
+        .split(area);
+
+    let refused = app.scan_log.last().is_some_and(Feedback::is_error);
+    let border = match refused {
+        true => Style::default().fg(Color::Red),
+        false => Style::default(),
+    };
+    let input = Paragraph::new(app.scan_input.as_str()).block(
+        Block::default()
+            .title(format!("Scan: {}", app.scan_session.status()))
+            .borders(Borders::ALL)
+            .border_style(border),
+    );
+    f.render_widget(input, layout[0]);
+
+    let rows = layout[1].height.saturating_sub(2) as usize;
+    let lines: Vec<Line> = app
+        .scan_log
+        .iter()
+        .skip(app.scan_log.len().saturating_sub(rows))
+        .map(|feedback| {
+            let style = match feedback {
+                Feedback::Success(_) => Style::default().fg(Color::Green),
+                Feedback::Error(_) => Style::default().fg(Color::Red),
+                Feedback::Info(_) => Style::default(),
+            };
+            Line::from(Span::styled(feedback.message().to_string(), style))
+        })
+        .collect();
+    let log =
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).border_style(border));
+    f.render_widget(log, layout[1]);
+}
+
 pub fn render_config_page(f: &mut Frame<'_>, app: &App) -> Result<()> {
     Ok(())
 }
@@ -7,10 +7,18 @@ use crate::cli::InsertUserArgs;
 use crate::python_db_handler::DBHandlerPy as DBHandler;
 use crate::users::ssn::SSN;
 use anyhow::Result;
+use chrono::{Days, NaiveDate};
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 
-pub fn insert_users(db_handler: &DBHandler, num_users: usize) -> Result<()> {
-    let users = generate_users(num_users);
+/// Inserts random users, `today` decides who is still serving
+pub fn insert_users(
+    db_handler: &DBHandler,
+    num_users: usize,
+    today: NaiveDate,
+    rng: &mut impl Rng,
+) -> Result<()> {
+    let users = generate_users(num_users, today, rng);
 
     for user in users {
         db_handler.insert_user(user)?;
@@ -19,41 +27,112 @@ pub fn insert_users(db_handler: &DBHandler, num_users: usize) -> Result<()> {
     Ok(())
 }
 
-fn generate_users(num_users: usize) -> Vec<User> {
+fn generate_users(num_users: usize, today: NaiveDate, rng: &mut impl Rng) -> Vec<User> {
     let mut users = Vec::new();
 
     for _ in 0..num_users {
-        users.push(User::new_random());
+        users.push(User::new_random(today, rng));
     }
 
     users
 }
 
-#[derive(Debug, Clone)]
+/// A borrower
+///
+/// Only the SSN and name are required. Inactive users and users whose end of service has passed
+/// cannot borrow anything new, but keep the items they already hold until they are returned.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[cfg_attr(feature = "mysql-db", derive(sqlx::FromRow))]
 #[cfg_attr(feature = "python-db", pyo3::pyclass)]
 pub struct User {
     pub ssn: String,
     pub name: String,
+    /// The unit or company the user belongs to
+    pub unit: Option<String>,
+    pub rank: Option<String>,
+    pub phone: Option<String>,
+    pub email: Option<String>,
+    pub active: bool,
+    pub end_of_service: Option<NaiveDate>,
 }
 
+const UNITS: [&str; 5] = [
+    "1st Company",
+    "2nd Company",
+    "3rd Company",
+    "Logistics Company",
+    "Staff Company",
+];
+
+const RANKS: [&str; 6] = [
+    "Private",
+    "Private First Class",
+    "Corporal",
+    "Sergeant",
+    "Lieutenant",
+    "Captain",
+];
+
 impl User {
-    pub fn new_random() -> Self {
-        let gender = Self::generate_random_gender();
-        let (first_name, last_name) = Self::generate_random_name(gender);
+    /// An active user with only an SSN and a name
+    pub fn new(ssn: String, name: String) -> Self {
         Self {
-            ssn: SSN::new_random(gender).into(),
+            ssn,
+            name,
+            unit: None,
+            rank: None,
+            phone: None,
+            email: None,
+            active: true,
+            end_of_service: None,
+        }
+    }
+
+    /// A random user, the end of service is picked around `today`
+    pub fn new_random(today: NaiveDate, rng: &mut impl Rng) -> Self {
+        let gender = Self::generate_random_gender(rng);
+        let (first_name, last_name) = Self::generate_random_name(gender, rng);
+        let ssn: String = SSN::new_random(gender, rng).into();
+        let email = format!(
+            "{}.{}@armoryatlas.test",
+            first_name.to_lowercase(),
+            last_name.to_lowercase()
+        );
+
+        // Most users are still serving, some have left and some are about to
+        let end_of_service = today.checked_add_days(Days::new(rng.gen_range(30..1460)));
+        let (active, end_of_service) = match rng.gen_range(0..10) {
+            0 => (
+                false,
+                today.checked_sub_days(Days::new(rng.gen_range(1..365))),
+            ),
+            1..=3 => (true, end_of_service),
+            _ => (true, None),
+        };
+
+        Self {
+            ssn,
             name: format!("{} {}", first_name, last_name),
+            unit: Some(UNITS[rng.gen_range(0..UNITS.len())].to_string()),
+            rank: Some(RANKS[rng.gen_range(0..RANKS.len())].to_string()),
+            phone: Some(format!("07{:08}", rng.gen_range(0..100_000_000))),
+            email: Some(email),
+            active,
+            end_of_service,
         }
     }
 
-    fn generate_random_gender() -> bool {
-        let mut rng = rand::thread_rng();
+    /// Whether the user may borrow items on the given date
+    pub fn may_borrow_on(&self, date: NaiveDate) -> bool {
+        self.active && self.end_of_service.is_none_or(|end| date <= end)
+    }
+
+    fn generate_random_gender(rng: &mut impl Rng) -> bool {
         // Randomly generates true or false, where true represents a man and false represents a woman.
         rng.gen_bool(0.5) // 50% chance for each gender
     }
 
-    fn generate_random_name(gender: bool) -> (String, String) {
+    fn generate_random_name(gender: bool, rng: &mut impl Rng) -> (String, String) {
         let last_names = [
             "Smith",
             "Johnson",
@@ -67,9 +146,6 @@ impl User {
             "Martinez",
         ];
 
-        // Get a random number generator
-        let mut rng = rand::thread_rng();
-
         let first_name = {
             let male_names: Vec<&str> = vec![
                 "Liam",
@@ -115,6 +191,12 @@ impl From<InsertUserArgs> for User {
         Self {
             ssn: insert_user_args.ssn,
             name: insert_user_args.name,
+            unit: insert_user_args.unit,
+            rank: insert_user_args.rank,
+            phone: insert_user_args.phone,
+            email: insert_user_args.email,
+            active: !insert_user_args.inactive,
+            end_of_service: insert_user_args.end_of_service,
         }
     }
 }
@@ -132,7 +214,36 @@ mod tests {
 
     #[test]
     fn test_new_random() {
-        let user = User::new_random();
+        let user = User::new_random(chrono::Local::now().date_naive(), &mut rand::thread_rng());
         println!("{:?}", user);
     }
+
+    #[test]
+    fn test_may_borrow() {
+        let date = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+        let mut user = User::new("19811218-9876".to_string(), "Liam Smith".to_string());
+        assert!(user.may_borrow_on(date));
+
+        user.end_of_service = NaiveDate::from_ymd_opt(2024, 5, 31);
+        assert!(!user.may_borrow_on(date));
+
+        user.end_of_service = None;
+        user.active = false;
+        assert!(!user.may_borrow_on(date));
+    }
+
+    #[test]
+    fn test_seeded_users_are_identical() {
+        let users = generate_users(5, crate::SEEDED_TODAY, &mut crate::seeded_rng(Some(42)));
+        let again = generate_users(5, crate::SEEDED_TODAY, &mut crate::seeded_rng(Some(42)));
+
+        assert_eq!(
+            users.iter().map(|user| &user.ssn).collect::<Vec<_>>(),
+            again.iter().map(|user| &user.ssn).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            users.iter().map(|user| &user.name).collect::<Vec<_>>(),
+            again.iter().map(|user| &user.name).collect::<Vec<_>>()
+        );
+    }
 }
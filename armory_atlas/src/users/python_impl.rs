@@ -1,16 +1,18 @@
+use chrono::{Local, NaiveDate};
+
 use crate::users::User;
 
 #[pyo3::pymethods]
 impl User {
     #[new]
     pub fn py_new(ssn: String, name: String) -> Self {
-        Self { ssn, name }
+        Self::new(ssn, name)
     }
 
     #[staticmethod]
     #[pyo3(name = "new_random")]
     pub fn py_new_random() -> Self {
-        Self::new_random()
+        Self::new_random(Local::now().date_naive(), &mut rand::thread_rng())
     }
 
     #[getter(ssn)]
@@ -23,6 +25,36 @@ impl User {
         self.name.clone()
     }
 
+    #[getter(unit)]
+    pub fn py_get_unit(&self) -> Option<String> {
+        self.unit.clone()
+    }
+
+    #[getter(rank)]
+    pub fn py_get_rank(&self) -> Option<String> {
+        self.rank.clone()
+    }
+
+    #[getter(phone)]
+    pub fn py_get_phone(&self) -> Option<String> {
+        self.phone.clone()
+    }
+
+    #[getter(email)]
+    pub fn py_get_email(&self) -> Option<String> {
+        self.email.clone()
+    }
+
+    #[getter(active)]
+    pub fn py_get_active(&self) -> bool {
+        self.active
+    }
+
+    #[getter(end_of_service)]
+    pub fn py_get_end_of_service(&self) -> Option<NaiveDate> {
+        self.end_of_service
+    }
+
     #[pyo3(name = "__repr__")]
     pub fn py_repr(&self) -> String {
         format!("{:?}", self)
@@ -1,7 +1,34 @@
-use chrono::{Datelike, NaiveDate};
+use std::fmt::Display;
+use std::str::FromStr;
+
+use std::sync::OnceLock;
+
+use anyhow::{bail, Result};
+use chrono::{Datelike, Local, NaiveDate};
 use rand::Rng;
+use regex::Regex;
+
+/// Erased users get an SSN starting with this, followed by random hex digits
+pub const PSEUDONYM_PREFIX: &str = "ANON-";
 
-#[derive(Debug, Clone)]
+/// A validated Swedish personnummer or samordningsnummer
+///
+/// Numbers are accepted with 10 or 12 digits, with or without a separator, and are always stored
+/// in the canonical form `YYYYMMDD-XXXX`. For 10 digit numbers the century is taken so that the
+/// person is younger than 100, or at least 100 with the `+` separator. A samordningsnummer has 60
+/// added to the day of birth and keeps it in the canonical form.
+///
+/// # Example
+///
+/// ```
+/// # use armory_atlas_lib::users::ssn::SSN;
+/// let ssn: SSN = "811218-9876".parse().unwrap();
+/// assert_eq!(ssn.as_str(), "19811218-9876");
+///
+/// assert!("811218-9875".parse::<SSN>().is_err());
+/// ```
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "python-db", derive(pyo3::FromPyObject))]
 #[cfg_attr(feature = "mysql-db", derive(sqlx::FromRow))]
 pub struct SSN {
@@ -14,14 +41,167 @@ impl From<SSN> for String {
     }
 }
 
+impl Display for SSN {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+impl FromStr for SSN {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse_at(s, Local::now().date_naive())
+    }
+}
+
+/// Parses an SSN given on the command line into its canonical form
+pub fn parse_ssn(s: &str) -> Result<String> {
+    Ok(s.parse::<SSN>()?.into())
+}
+
+/// Masks an SSN as `YYMMDD-****`
+///
+/// Works on anything stored as an SSN, also numbers from before they were normalized. Pseudonyms
+/// of erased users are not personal data and are returned as they are.
+///
+/// # Example
+///
+/// ```
+/// # use armory_atlas_lib::users::ssn::mask_ssn;
+/// assert_eq!(mask_ssn("19811218-9876"), "811218-****");
+/// assert_eq!(mask_ssn("811218-9876"), "811218-****");
+/// assert_eq!(mask_ssn("ANON-3f9a0c1e"), "ANON-3f9a0c1e");
+/// ```
+///
+pub fn mask_ssn(ssn: &str) -> String {
+    if ssn.starts_with(PSEUDONYM_PREFIX) {
+        return ssn.to_string();
+    }
+
+    let digits: String = ssn.chars().filter(char::is_ascii_digit).collect();
+    match digits.len() {
+        10 => format!("{}-****", &digits[..6]),
+        12 => format!("{}-****", &digits[2..8]),
+        _ => "*".repeat(ssn.chars().count()),
+    }
+}
+
+/// Masks everything that looks like an SSN in a free text, such as a log line or an error
+pub fn redact_ssns(text: &str) -> String {
+    static SSN_PATTERN: OnceLock<Regex> = OnceLock::new();
+    let pattern = SSN_PATTERN
+        .get_or_init(|| Regex::new(r"\b(?:\d{2})?\d{6}[-+]?\d{4}\b").expect("valid regex"));
+
+    pattern
+        .replace_all(text, |captures: &regex::Captures| mask_ssn(&captures[0]))
+        .into_owned()
+}
+
+/// Results that contain SSNs, which are masked before they are shown to an operator that may not
+/// see them in full
+pub trait MaskSsns {
+    fn mask_ssns(&mut self);
+}
+
+/// Masks the SSNs in the data unless `show_full` is set
+pub fn mask_unless<T: MaskSsns>(mut data: T, show_full: bool) -> T {
+    if !show_full {
+        data.mask_ssns();
+    }
+    data
+}
+
 impl SSN {
-    pub fn new(value: String) -> Self {
-        Self { value }
+    /// Parses an SSN, using `today` to find the century of 10 digit numbers
+    pub fn parse_at(s: &str, today: NaiveDate) -> Result<Self> {
+        let s = s.trim();
+        let (digits, separator) = match s.find(['-', '+']) {
+            Some(idx) if s.len() - idx == 5 => {
+                (format!("{}{}", &s[..idx], &s[idx + 1..]), &s[idx..=idx])
+            }
+            Some(_) => {
+                bail!("{s} is not a valid SSN, the separator must come before the last four digits")
+            }
+            None => (s.to_string(), ""),
+        };
+        if !digits.chars().all(|c| c.is_ascii_digit()) {
+            bail!("{s} is not a valid SSN, it may only contain digits and a separator");
+        }
+
+        let (century, short) = match digits.len() {
+            10 => (None, digits.as_str()),
+            12 if separator != "+" => (Some(&digits[..2]), &digits[2..]),
+            12 => bail!("{s} is not a valid SSN, the + separator is only used with 10 digits"),
+            _ => bail!("{s} is not a valid SSN, expected 10 or 12 digits"),
+        };
+
+        let (first_nine, control) = short.split_at(9);
+        if Self::generate_control_digit(first_nine) != control {
+            bail!("{s} is not a valid SSN, the control digit is wrong");
+        }
+
+        let yy: i32 = short[..2].parse()?;
+        let month: u32 = short[2..4].parse()?;
+        let mut day: u32 = short[4..6].parse()?;
+        if day > 60 {
+            day -= 60;
+        }
+
+        let year = match century {
+            Some(century) => century.parse::<i32>()? * 100 + yy,
+            None => {
+                let mut year = today.year() / 100 * 100 + yy;
+                let birthday = NaiveDate::from_ymd_opt(year, month, day);
+                if birthday.map_or(year > today.year(), |birthday| birthday > today) {
+                    year -= 100;
+                }
+                if separator == "+" {
+                    year -= 100;
+                }
+                year
+            }
+        };
+
+        let Some(birth_date) = NaiveDate::from_ymd_opt(year, month, day) else {
+            bail!("{s} is not a valid SSN, {year}-{month:02}-{day:02} is not a date");
+        };
+        if birth_date > today {
+            bail!("{s} is not a valid SSN, the date of birth is in the future");
+        }
+
+        Ok(Self {
+            value: format!("{year:04}{}-{}", &short[2..6], &short[6..]),
+        })
     }
 
-    pub fn generate_random_date() -> String {
-        let mut rng = rand::thread_rng();
+    pub fn as_str(&self) -> &str {
+        &self.value
+    }
+
+    /// The SSN masked as `YYMMDD-****`
+    pub fn masked(&self) -> String {
+        mask_ssn(&self.value)
+    }
+
+    /// The date of birth, for a samordningsnummer without the 60 added to the day
+    pub fn birth_date(&self) -> NaiveDate {
+        let year = self.value[..4].parse().expect("validated when parsed");
+        let month = self.value[4..6].parse().expect("validated when parsed");
+        let day: u32 = self.value[6..8].parse().expect("validated when parsed");
 
+        NaiveDate::from_ymd_opt(year, month, day % 60).expect("validated when parsed")
+    }
+
+    /// Whether this is a samordningsnummer rather than a personnummer
+    pub fn is_coordination_number(&self) -> bool {
+        self.value[6..8]
+            .parse::<u32>()
+            .expect("validated when parsed")
+            > 60
+    }
+
+    pub fn generate_random_date(rng: &mut impl Rng) -> NaiveDate {
         // Define the range of years, months, and days
         // For example, for years between 1950 and 1999
         let year = rng.gen_range(1950..2006);
@@ -40,44 +220,49 @@ impl SSN {
         };
 
         // Create a NaiveDate object from the generated year, month, and day
-        let date = NaiveDate::from_ymd_opt(year, month, day).unwrap();
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
 
-        // Format the date as "YYMMDD"
-        format!(
+    pub fn new_random(gender: bool, rng: &mut impl Rng) -> Self {
+        let date = Self::generate_random_date(rng);
+        // The control digit only covers the last two digits of the year
+        let date_part = format!(
             "{:02}{:02}{:02}",
             date.year() % 100,
             date.month(),
             date.day()
-        )
-    }
-
-    pub fn new_random(gender: bool) -> Self {
-        let date_part = Self::generate_random_date();
+        );
 
         // Generate a random number between 0 and 9
-        let random_part_1 = rand::thread_rng().gen_range(0..=9).to_string();
-        let random_part_2 = rand::thread_rng().gen_range(0..=9).to_string();
+        let random_part_1 = rng.gen_range(0..=9).to_string();
+        let random_part_2 = rng.gen_range(0..=9).to_string();
         let random_part = format!("{}{}", random_part_1, random_part_2);
 
         let gender_part = if gender {
             // generate a random uneaven number between 0 and 9
             let uneaven = [1, 3, 5, 7, 9];
 
-            uneaven[rand::thread_rng().gen_range(0..=4)].to_string()
+            uneaven[rng.gen_range(0..=4)].to_string()
         } else {
             let even = [0, 2, 4, 6, 8];
 
-            even[rand::thread_rng().gen_range(0..=4)].to_string()
+            even[rng.gen_range(0..=4)].to_string()
         };
 
         let value = format!("{}{}{}", &date_part, &random_part, &gender_part);
 
         let ssn = SSN::generate_control_digit(&value);
 
-        Self::new(format!(
-            "{}-{}{}{}",
-            date_part, random_part, gender_part, ssn
-        ))
+        Self {
+            value: format!(
+                "{}{}-{}{}{}",
+                date.year(),
+                &date_part[2..],
+                random_part,
+                gender_part,
+                ssn
+            ),
+        }
     }
 
     pub fn generate_control_digit(first_nine: &str) -> String {
@@ -128,3 +313,84 @@ impl SSN {
         total.to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Result<String> {
+        let today = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+        SSN::parse_at(s, today).map(String::from)
+    }
+
+    #[test]
+    fn test_forms() {
+        for form in [
+            "811218-9876",
+            "8112189876",
+            "19811218-9876",
+            "198112189876",
+            " 811218-9876 ",
+        ] {
+            assert_eq!(parse(form).unwrap(), "19811218-9876", "{form}");
+        }
+    }
+
+    #[test]
+    fn test_century() {
+        assert_eq!(parse("240531-1230").unwrap(), "20240531-1230");
+        // Turns 100 tomorrow, so still written with a -
+        assert_eq!(parse("240602-1234").unwrap(), "19240602-1234");
+        assert_eq!(parse("240602+1234").unwrap(), "18240602-1234");
+        assert_eq!(parse("811218+9876").unwrap(), "18811218-9876");
+    }
+
+    #[test]
+    fn test_coordination_number() {
+        let ssn =
+            SSN::parse_at("811278-9873", NaiveDate::from_ymd_opt(2024, 6, 1).unwrap()).unwrap();
+
+        assert_eq!(ssn.as_str(), "19811278-9873");
+        assert!(ssn.is_coordination_number());
+        assert_eq!(
+            ssn.birth_date(),
+            NaiveDate::from_ymd_opt(1981, 12, 18).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        // Wrong control digit
+        assert!(parse("811218-9875").is_err());
+        // Misplaced separator
+        assert!(parse("8112-189876").is_err());
+        // Too short
+        assert!(parse("81121898").is_err());
+        // Letters
+        assert!(parse("81121A-9876").is_err());
+        // + with 12 digits
+        assert!(parse("19811218+9876").is_err());
+        // February 30th, with a correct control digit
+        assert!(parse("810230-9872").is_err());
+        // Born in the future
+        assert!(parse("20300101-0002").is_err());
+    }
+
+    #[test]
+    fn test_redact() {
+        assert_eq!(
+            redact_ssns("No user with SSN 19811218-9876 exists! (8112189876, 811218+9876)"),
+            "No user with SSN 811218-**** exists! (811218-****, 811218-****)"
+        );
+        assert_eq!(redact_ssns("Item 42 of 1000"), "Item 42 of 1000");
+    }
+
+    #[test]
+    fn test_random_is_valid() {
+        let mut rng = rand::thread_rng();
+        for gender in [true, false] {
+            let ssn = SSN::new_random(gender, &mut rng);
+            assert_eq!(SSN::from_str(ssn.as_str()).unwrap(), ssn);
+        }
+    }
+}